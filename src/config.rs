@@ -2,7 +2,8 @@ pub const NR_CPUS: usize = 4;
 
 pub const KSTACK_RANK: usize = 10;
 
-pub const USTACK_PAGES: usize = 256; // 1MB
+pub const USTACK_LIMIT: usize = 8 * 1024 * 1024; // default RLIMIT_STACK
+pub const USTACK_GUARD_PAGES: usize = 1;
 pub const USTACK_BASE: usize = 0xc000_0000;
 
 pub const MAX_PAGE_PER_ARG: usize = 1;
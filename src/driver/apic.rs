@@ -1,3 +1,4 @@
 pub mod apic_timer;
 pub mod io;
+pub mod ipi;
 pub mod local;
@@ -2,10 +2,15 @@ use kfs_macro::interrupt_handler;
 
 use crate::driver::apic::local::LOCAL_APIC;
 use crate::interrupt::InterruptFrame;
+use crate::process::itimer::{tick_process, tick_real};
+use crate::process::rlimit::tick_cpu_limit;
 use crate::process::task::CURRENT;
+use crate::scheduler::balance::balance_tick;
 use crate::scheduler::context::yield_now;
 use crate::scheduler::nano_sleep::ALARM;
+use crate::scheduler::need_resched;
 use crate::scheduler::preempt::preemptable;
+use crate::scheduler::timer::run_timers;
 use crate::sync::CpuLocal;
 
 #[interrupt_handler]
@@ -13,13 +18,22 @@ pub unsafe extern "C" fn handle_timer_impl(frame: InterruptFrame) {
 	*JIFFIES.get_mut() += 1;
 	LOCAL_APIC.end_of_interrupt();
 
+	CURRENT.get_ref().lock_usage().tick(frame.is_user());
+	tick_process(CURRENT.get_ref(), frame.is_user());
+	tick_cpu_limit(CURRENT.get_ref());
+	tick_real();
+	run_timers();
+
 	ALARM.lock().wake_up();
+	balance_tick(*JIFFIES.get_ref());
 
 	if !preemptable() {
 		return;
 	}
 
-	yield_now();
+	if need_resched() {
+		yield_now();
+	}
 
 	if frame.is_user() {
 		CURRENT
@@ -0,0 +1,119 @@
+use core::hint::spin_loop;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use kfs_macro::interrupt_handler;
+
+use crate::config::NR_CPUS;
+use crate::driver::apic::local::LOCAL_APIC;
+use crate::interrupt::InterruptFrame;
+use crate::mm::util::invlpg;
+use crate::process::task::CURRENT;
+use crate::scheduler::context::yield_now;
+use crate::scheduler::need_resched;
+use crate::scheduler::preempt::preemptable;
+use crate::smp::{cpus_in, online_cpus, smp_id};
+
+pub const RESCHEDULE_VECTOR: usize = 0x26;
+pub const TLB_SHOOTDOWN_VECTOR: usize = 0x27;
+
+/// TLB invalidation requested by a cpu.
+struct Shootdown {
+	vaddr: AtomicUsize,
+	/// cpus that have not invalidated `vaddr` yet.
+	pending: AtomicUsize,
+}
+
+#[allow(clippy::declare_interior_mutable_const)]
+const EMPTY_SHOOTDOWN: Shootdown = Shootdown {
+	vaddr: AtomicUsize::new(0),
+	pending: AtomicUsize::new(0),
+};
+
+/// request of each cpu, indexed by cpu id.
+static SHOOTDOWNS: [Shootdown; NR_CPUS] = [EMPTY_SHOOTDOWN; NR_CPUS];
+
+/// ask `cpu` to check its run queue. (e.g. a task was queued there)
+pub fn send_reschedule(cpu: usize) {
+	LOCAL_APIC.send_ipi(cpu, RESCHEDULE_VECTOR);
+}
+
+#[interrupt_handler]
+pub unsafe extern "C" fn handle_reschedule_impl(frame: InterruptFrame) {
+	LOCAL_APIC.end_of_interrupt();
+
+	if !preemptable() {
+		return;
+	}
+
+	if need_resched() {
+		yield_now();
+	}
+
+	if frame.is_user() {
+		CURRENT
+			.get_mut()
+			.get_user_ext()
+			.expect("user task")
+			.signal
+			.do_signal(&frame, 0);
+	}
+}
+
+/// invalidate TLB entry of `vaddr` on `cpus` other than this one, and wait until done.
+///
+/// interrupts must be disabled, so that the request of this cpu is not reused meanwhile.
+pub fn shootdown_tlb(cpus: usize, vaddr: usize) {
+	// nothing to do before scheduler starts. (local apic may not be ready)
+	if cpus & online_cpus() == 0 {
+		return;
+	}
+
+	let cpu = smp_id();
+	let cpus = cpus & online_cpus() & !(1 << cpu);
+
+	if cpus == 0 {
+		return;
+	}
+
+	let request = &SHOOTDOWNS[cpu];
+	request.vaddr.store(vaddr, Ordering::Relaxed);
+	request.pending.store(cpus, Ordering::Release);
+
+	for other in cpus_in(cpus) {
+		LOCAL_APIC.send_ipi(other, TLB_SHOOTDOWN_VECTOR);
+	}
+
+	// others may be waiting for this cpu as well.
+	while request.pending.load(Ordering::Acquire) != 0 {
+		flush_tlb_requests();
+		spin_loop();
+	}
+}
+
+/// do invalidations requested to this cpu.
+///
+/// also called while spinning with interrupts disabled, where the IPI can't be taken.
+pub fn flush_tlb_requests() {
+	if SHOOTDOWNS
+		.iter()
+		.all(|request| request.pending.load(Ordering::Acquire) == 0)
+	{
+		return;
+	}
+
+	let bit = 1 << smp_id();
+
+	for request in SHOOTDOWNS.iter() {
+		if request.pending.load(Ordering::Acquire) & bit != 0 {
+			invlpg(request.vaddr.load(Ordering::Relaxed));
+			request.pending.fetch_and(!bit, Ordering::Release);
+		}
+	}
+}
+
+#[interrupt_handler]
+pub unsafe extern "C" fn handle_tlb_shootdown_impl(_frame: InterruptFrame) {
+	LOCAL_APIC.end_of_interrupt();
+
+	flush_tlb_requests();
+}
@@ -77,6 +77,20 @@ impl LocalAPIC {
 		unsafe { lapic_register!(end_of_interrupt).write_volatile(0) }
 		// unsafe { addr_of_mut!((*self.reg_ptr()).end_of_interrupt).write_volatile(0) }
 	}
+
+	/// send fixed interrupt `vector` to the cpu which local apic id is `dest`.
+	pub fn send_ipi(&self, dest: usize, vector: usize) {
+		const DELIVERY_PENDING: u32 = 1 << 12;
+		const LEVEL_ASSERT: u32 = 1 << 14;
+
+		unsafe {
+			// writing low dword issues the interrupt, so destination goes first.
+			lapic_register!(interrupt_command1).write_volatile((dest as u32) << 24);
+			lapic_register!(interrupt_command0).write_volatile(LEVEL_ASSERT | vector as u32);
+
+			while lapic_register!(interrupt_command0).read_volatile() & DELIVERY_PENDING != 0 {}
+		}
+	}
 }
 
 #[macro_use]
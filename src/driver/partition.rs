@@ -127,6 +127,13 @@ impl Partition {
 		Ok(block.into())
 	}
 
+	/// number of blocks in partition with current block size.
+	pub fn nr_blocks(&self) -> usize {
+		let entry = unsafe { self.entry.get_unchecked() };
+
+		(entry.end() - entry.begin()) / self.block_size().sector_count()
+	}
+
 	pub fn entry_begin(&self) -> LBA28 {
 		unsafe { self.entry.get_unchecked().begin() }
 	}
@@ -2,8 +2,10 @@ use core::marker::PhantomData;
 use core::mem::{self, size_of};
 
 use crate::collection::WrapQueue;
-use crate::fs::vfs::{AccessFlag, FileHandle, IOFlag, VfsFileHandle, VfsHandle};
+use crate::fs::vfs::{AccessFlag, CreationFlag, FileHandle, IOFlag, VfsFileHandle, VfsHandle};
+use crate::mm::user::verify::verify_region;
 use crate::mm::user::vma::AreaFlag;
+use crate::process::fd_table::FdFlag;
 use crate::process::signal::send_signal_to;
 use crate::process::signal::sig_code::SigCode;
 use crate::process::signal::sig_info::SigInfo;
@@ -118,6 +120,7 @@ impl FileHandle for Pipe<WriteEnd> {
 					pid: 0,
 					uid: 0,
 					code: SigCode::SI_KERNEL,
+					value: 0,
 				};
 
 				let current = unsafe { CURRENT.get_mut() };
@@ -180,23 +183,31 @@ pub fn open_pipe() -> (VfsHandle, VfsHandle) {
 }
 
 pub fn sys_pipe(pipe_ptr: usize) -> Result<usize, Errno> {
+	sys_pipe2(pipe_ptr, 0)
+}
+
+/// `flags` accepts `O_CLOEXEC` and `O_NONBLOCK`.
+pub fn sys_pipe2(pipe_ptr: usize, flags: i32) -> Result<usize, Errno> {
 	let current = unsafe { CURRENT.get_mut() };
 
+	if flags & !(CreationFlag::O_CLOEXEC.bits() | IOFlag::O_NONBLOCK.bits()) != 0 {
+		return Err(Errno::EINVAL);
+	}
+	let fd_flags = FdFlag::from(CreationFlag::from_bits_truncate(flags));
+	let io_flags = IOFlag::from_bits_truncate(flags);
+
 	let user_ext = current.get_user_ext().expect("must be user task");
 
-	if !user_ext
-		.lock_memory()
-		.query_flags_range(pipe_ptr, 2 * size_of::<i32>(), AreaFlag::Writable)
-	{
-		return Err(Errno::EFAULT);
-	}
+	verify_region(pipe_ptr, 2 * size_of::<i32>(), current, AreaFlag::Writable)?;
 
 	let (read_handle, write_handle) = open_pipe();
+	read_handle.set_io_flags(io_flags)?;
+	write_handle.set_io_flags(io_flags)?;
 
 	let mut fd_table = user_ext.lock_fd_table();
 
-	let read_end = fd_table.alloc_fd(read_handle);
-	let write_end = fd_table.alloc_fd(write_handle);
+	let read_end = fd_table.alloc_fd(read_handle, fd_flags);
+	let write_end = fd_table.alloc_fd(write_handle, fd_flags);
 
 	match (read_end, write_end) {
 		(Some(x), Some(y)) => {
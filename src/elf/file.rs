@@ -34,7 +34,7 @@ impl ElfType {
 	const REL: u16 = 0x01;
 	const EXEC: u16 = 0x02;
 	const DYN: u16 = 0x03;
-	const CORE: u16 = 0x04;
+	pub const CORE: u16 = 0x04;
 	const LOOS: u16 = 0xFE00;
 	const HIOS: u16 = 0xFEFF;
 	const LOPROC: u16 = 0xFF00;
@@ -31,10 +31,10 @@ pub enum SegmentType {
 
 impl SegmentType {
 	const NULL: u32 = 0x00000000;
-	const LOAD: u32 = 0x00000001;
+	pub const LOAD: u32 = 0x00000001;
 	const DYNAMIC: u32 = 0x00000002;
 	const INTERP: u32 = 0x00000003;
-	const NOTE: u32 = 0x00000004;
+	pub const NOTE: u32 = 0x00000004;
 	const SHLIB: u32 = 0x00000005;
 	const PHDR: u32 = 0x00000006;
 	const TLS: u32 = 0x00000007;
@@ -85,6 +85,10 @@ impl Into<AreaFlag> for SegmentFlag {
 			result |= AreaFlag::Readable;
 		}
 
+		if self.contains(Self::EXECUTE) {
+			result |= AreaFlag::Executable;
+		}
+
 		result
 	}
 }
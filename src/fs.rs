@@ -2,6 +2,7 @@ pub mod devfs;
 pub mod ext2;
 pub mod path;
 pub mod syscall;
+pub mod timerfd;
 pub mod vfs;
 
 mod procfs;
@@ -20,7 +21,7 @@ use alloc::rc::Rc;
 use alloc::sync::Arc;
 use alloc::vec::Vec;
 use tmpfs::TmpFs;
-use vfs::{VfsDirEntry, ROOT_DIR_ENTRY};
+use vfs::{MountFlag, VfsDirEntry, ROOT_DIR_ENTRY};
 
 pub use devfs::init as init_devfs;
 pub use procfs::init as init_procfs;
@@ -72,7 +73,12 @@ pub fn mount_root() {
 		VfsDirEntry::new(name, inode, w.clone(), sb, true)
 	}));
 
-	create_mount_entry(format!("/dev/part{}", idx + 1).as_bytes(), b"/", b"ext2");
+	create_mount_entry(
+		format!("/dev/part{}", idx + 1).as_bytes(),
+		b"/",
+		b"ext2",
+		MountFlag::empty(),
+	);
 
 	do_chdir(
 		&get_init_task(),
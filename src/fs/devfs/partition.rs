@@ -82,6 +82,12 @@ pub struct PartBorrow {
 	dev: Arc<DevPart>,
 }
 
+impl PartBorrow {
+	pub fn is_borrowed_from(&self, dev: &Arc<DevPart>) -> bool {
+		Arc::ptr_eq(&self.dev, dev)
+	}
+}
+
 impl Deref for PartBorrow {
 	type Target = Arc<Partition>;
 	fn deref(&self) -> &Self::Target {
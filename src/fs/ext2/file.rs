@@ -116,6 +116,10 @@ impl vfs::FileHandle for File {
 	fn close(&self) -> Result<(), Errno> {
 		self.inode.inner().sync()
 	}
+
+	fn sync(&self) -> Result<(), Errno> {
+		self.inode.inner().sync()
+	}
 }
 
 fn write_to_user(u_buf: &mut [u8], k_buf: &[u8], read_sum: usize) -> usize {
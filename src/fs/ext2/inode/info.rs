@@ -42,10 +42,10 @@ pub struct InodeInfo {
 
 impl InodeInfo {
 	pub fn new(file_type: FileType, perm: Permission) -> Self {
-		let current = unsafe { CURRENT.get_mut() };
+		let cred = unsafe { CURRENT.get_ref() }.get_cred();
 
-		let gid = current.get_gid() as u16;
-		let uid = current.get_uid() as u16;
+		let gid = cred.fsgid as u16;
+		let uid = cred.fsuid as u16;
 		let timestamp = get_timestamp_second() as u32;
 		let mode = file_type.mode() | perm.bits() as u16;
 		let links_count = match file_type {
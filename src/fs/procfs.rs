@@ -1,4 +1,5 @@
 mod mounts;
+mod sys;
 mod task;
 
 pub use mounts::{create_mount_entry, delete_mount_entry};
@@ -167,6 +168,7 @@ impl DirInode for Locked<ProcRootDirInode> {
 			.keys()
 			.map(|x| (2, x.as_raw().to_string().into()))
 			.chain(Some((1, String::from("mounts").into())))
+			.chain(Some((2, String::from("sys").into())))
 			.collect();
 
 		v.push((2, b".".to_vec()));
@@ -179,6 +181,9 @@ impl DirInode for Locked<ProcRootDirInode> {
 		if name == b"mounts" {
 			return Ok(VfsInode::File(self.get_mounts()));
 		}
+		if name == b"sys" {
+			return Ok(sys::sys_dir());
+		}
 		let pid = core::str::from_utf8(name).map_err(|_| Errno::ESRCH)?;
 		let pid: usize = pid.to_string().parse().map_err(|_| Errno::ESRCH)?;
 		let pid = Pid::from_raw(pid);
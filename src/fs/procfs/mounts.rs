@@ -1,7 +1,9 @@
 use alloc::{boxed::Box, string::String, vec::Vec};
 
 use crate::{
-	fs::vfs::{FileHandle, FileInode, Inode, Permission, Statx, StatxMode, StatxTimeStamp},
+	fs::vfs::{
+		FileHandle, FileInode, Inode, MountFlag, Permission, Statx, StatxMode, StatxTimeStamp,
+	},
 	sync::LocalLocked,
 	syscall::errno::Errno,
 	util::from_utf8_or,
@@ -9,7 +11,7 @@ use crate::{
 
 use super::{ProcFileHandle, PROCFS_ROOT_DIR};
 
-pub fn create_mount_entry(dev_path: &[u8], mount_point: &[u8], fs_name: &[u8]) {
+pub fn create_mount_entry(dev_path: &[u8], mount_point: &[u8], fs_name: &[u8], flags: MountFlag) {
 	let procfs = unsafe { PROCFS_ROOT_DIR.assume_init_ref() };
 
 	let mounts = procfs.get_mounts();
@@ -17,7 +19,7 @@ pub fn create_mount_entry(dev_path: &[u8], mount_point: &[u8], fs_name: &[u8]) {
 	mounts
 		.lock()
 		.entries
-		.push(MountInfo::new(dev_path, mount_point, fs_name));
+		.push(MountInfo::new(dev_path, mount_point, fs_name, flags));
 }
 
 pub fn delete_mount_entry(mount_point: &[u8]) -> Result<(), Errno> {
@@ -46,7 +48,7 @@ struct MountInfo {
 }
 
 impl MountInfo {
-	pub fn new(dev_path: &[u8], mount_point: &[u8], fs_name: &[u8]) -> Self {
+	pub fn new(dev_path: &[u8], mount_point: &[u8], fs_name: &[u8], flags: MountFlag) -> Self {
 		let dev_path = from_utf8_or(dev_path, "none");
 		let mount_point = from_utf8_or(mount_point, "none");
 		let fs_name = from_utf8_or(fs_name, "none");
@@ -69,7 +71,10 @@ impl MountInfo {
 		let fs_name = (offset, contents.len());
 		contents.push(' ');
 
-		contents.push_str("defaults 0 0\n");
+		match flags.contains(MountFlag::MS_NOSUID) {
+			true => contents.push_str("nosuid 0 0\n"),
+			false => contents.push_str("defaults 0 0\n"),
+		}
 
 		Self {
 			dev_path,
@@ -0,0 +1,241 @@
+use core::sync::atomic::Ordering;
+
+use alloc::{boxed::Box, format, sync::Arc, vec::Vec};
+
+use crate::fs::tmpfs::TmpDir;
+use crate::fs::vfs::{
+	DirHandle, DirInode, FileHandle, FileInode, IOFlag, Inode, Permission, Statx, StatxMode,
+	StatxTimeStamp, SymLinkInode, VfsEntry, VfsInode, Whence,
+};
+use crate::mm::user::aslr::{RANDOMIZE_VA_SPACE, RANDOMIZE_VA_SPACE_MAX};
+use crate::process::coredump::{get_core_pattern, set_core_pattern};
+use crate::process::task::CURRENT;
+use crate::{sync::LocalLocked, syscall::errno::Errno};
+
+use super::ProcFileHandle;
+
+/// (name, type of directory entry, inode)
+type SysEntry = (&'static [u8], u8, fn() -> VfsInode);
+
+const SYS_ENTRIES: &[SysEntry] = &[(b"kernel", 2, || {
+	VfsInode::Dir(Arc::new(ProcSysDirInode(KERNEL_ENTRIES)))
+})];
+
+const KERNEL_ENTRIES: &[SysEntry] = &[
+	(b"randomize_va_space", 1, || {
+		VfsInode::File(Arc::new(RandomizeVaSpaceInode))
+	}),
+	(b"core_pattern", 1, || {
+		VfsInode::File(Arc::new(CorePatternInode))
+	}),
+];
+
+pub(super) fn sys_dir() -> VfsInode {
+	VfsInode::Dir(Arc::new(ProcSysDirInode(SYS_ENTRIES)))
+}
+
+fn stat(kind: u16, perm: u16) -> Statx {
+	Statx {
+		mask: Statx::MASK_ALL,
+		blksize: 0,
+		attributes: 0,
+		nlink: 0,
+		uid: 0,
+		gid: 0,
+		mode: StatxMode::new(kind, perm),
+		pad1: 0,
+		ino: 0,
+		size: 0,
+		blocks: 0,
+		attributes_mask: 0,
+		atime: StatxTimeStamp::default(),
+		btime: StatxTimeStamp::default(),
+		ctime: StatxTimeStamp::default(),
+		mtime: StatxTimeStamp::default(),
+		rdev_major: 0,
+		rdev_minor: 0,
+		dev_major: 0,
+		dev_minor: 0,
+	}
+}
+
+/// directory under `/proc/sys` with fixed entries.
+struct ProcSysDirInode(&'static [SysEntry]);
+
+impl Inode for ProcSysDirInode {
+	fn stat(&self) -> Result<Statx, Errno> {
+		Ok(stat(StatxMode::DIRECTORY, 0o555))
+	}
+
+	fn chown(&self, _owner: usize, _group: usize) -> Result<(), Errno> {
+		Err(Errno::EPERM)
+	}
+
+	fn chmod(&self, _perm: Permission) -> Result<(), Errno> {
+		Err(Errno::EPERM)
+	}
+}
+
+impl DirInode for ProcSysDirInode {
+	fn open(&self) -> Result<Box<dyn DirHandle>, Errno> {
+		let mut v: Vec<(u8, Vec<u8>)> = self
+			.0
+			.iter()
+			.map(|(name, kind, _)| (*kind, name.to_vec()))
+			.collect();
+
+		v.push((2, b".".to_vec()));
+		v.push((2, b"..".to_vec()));
+
+		Ok(Box::new(TmpDir::new(v)))
+	}
+
+	fn lookup(&self, name: &[u8]) -> Result<VfsInode, Errno> {
+		self.0
+			.iter()
+			.find(|(entry, _, _)| *entry == name)
+			.map(|(_, _, inode)| inode())
+			.ok_or(Errno::ENOENT)
+	}
+
+	fn mkdir(&self, _name: &[u8], _perm: Permission) -> Result<Arc<dyn DirInode>, Errno> {
+		Err(Errno::EPERM)
+	}
+
+	fn rmdir(&self, _name: &[u8]) -> Result<(), Errno> {
+		Err(Errno::EPERM)
+	}
+
+	fn create(&self, _name: &[u8], _perm: Permission) -> Result<Arc<dyn FileInode>, Errno> {
+		Err(Errno::EPERM)
+	}
+
+	fn unlink(&self, _name: &[u8]) -> Result<(), Errno> {
+		Err(Errno::EPERM)
+	}
+
+	fn symlink(&self, _target: &[u8], _name: &[u8]) -> Result<Arc<dyn SymLinkInode>, Errno> {
+		Err(Errno::EPERM)
+	}
+
+	fn link(&self, _src: &VfsEntry, _link_name: &[u8]) -> Result<VfsInode, Errno> {
+		Err(Errno::EPERM)
+	}
+
+	fn overwrite(&self, _src: &VfsEntry, _link_name: &[u8]) -> Result<VfsInode, Errno> {
+		Err(Errno::EPERM)
+	}
+}
+
+struct RandomizeVaSpaceInode;
+
+impl Inode for RandomizeVaSpaceInode {
+	fn stat(&self) -> Result<Statx, Errno> {
+		Ok(stat(StatxMode::REGULAR, 0o644))
+	}
+
+	fn chown(&self, _owner: usize, _group: usize) -> Result<(), Errno> {
+		Err(Errno::EPERM)
+	}
+
+	fn chmod(&self, _perm: Permission) -> Result<(), Errno> {
+		Err(Errno::EPERM)
+	}
+}
+
+impl FileInode for RandomizeVaSpaceInode {
+	fn open(&self) -> Result<Box<dyn FileHandle>, Errno> {
+		let value = RANDOMIZE_VA_SPACE.load(Ordering::Relaxed);
+
+		Ok(Box::new(RandomizeVaSpaceHandle(LocalLocked::new(
+			ProcFileHandle::new(format!("{}\n", value).into_bytes()),
+		))))
+	}
+
+	// allow `echo 0 > randomize_va_space`.
+	fn truncate(&self, _length: isize) -> Result<(), Errno> {
+		Ok(())
+	}
+}
+
+struct RandomizeVaSpaceHandle(LocalLocked<ProcFileHandle>);
+
+impl FileHandle for RandomizeVaSpaceHandle {
+	fn read(&self, buf: &mut [u8], flags: IOFlag) -> Result<usize, Errno> {
+		self.0.read(buf, flags)
+	}
+
+	fn write(&self, buf: &[u8], _flags: IOFlag) -> Result<usize, Errno> {
+		if !unsafe { CURRENT.get_ref() }.is_privileged() {
+			return Err(Errno::EACCES);
+		}
+
+		let value = core::str::from_utf8(buf)
+			.ok()
+			.and_then(|s| s.trim().parse::<usize>().ok())
+			.filter(|value| *value <= RANDOMIZE_VA_SPACE_MAX)
+			.ok_or(Errno::EINVAL)?;
+
+		RANDOMIZE_VA_SPACE.store(value, Ordering::Relaxed);
+
+		Ok(buf.len())
+	}
+
+	fn lseek(&self, _offset: isize, _whence: Whence) -> Result<usize, Errno> {
+		Err(Errno::EBADF)
+	}
+}
+
+struct CorePatternInode;
+
+impl Inode for CorePatternInode {
+	fn stat(&self) -> Result<Statx, Errno> {
+		Ok(stat(StatxMode::REGULAR, 0o644))
+	}
+
+	fn chown(&self, _owner: usize, _group: usize) -> Result<(), Errno> {
+		Err(Errno::EPERM)
+	}
+
+	fn chmod(&self, _perm: Permission) -> Result<(), Errno> {
+		Err(Errno::EPERM)
+	}
+}
+
+impl FileInode for CorePatternInode {
+	fn open(&self) -> Result<Box<dyn FileHandle>, Errno> {
+		let mut value = get_core_pattern();
+		value.push(b'\n');
+
+		Ok(Box::new(CorePatternHandle(LocalLocked::new(
+			ProcFileHandle::new(value),
+		))))
+	}
+
+	fn truncate(&self, _length: isize) -> Result<(), Errno> {
+		Ok(())
+	}
+}
+
+struct CorePatternHandle(LocalLocked<ProcFileHandle>);
+
+impl FileHandle for CorePatternHandle {
+	fn read(&self, buf: &mut [u8], flags: IOFlag) -> Result<usize, Errno> {
+		self.0.read(buf, flags)
+	}
+
+	fn write(&self, buf: &[u8], _flags: IOFlag) -> Result<usize, Errno> {
+		if !unsafe { CURRENT.get_ref() }.is_privileged() {
+			return Err(Errno::EACCES);
+		}
+
+		let pattern = buf.strip_suffix(b"\n").unwrap_or(buf);
+		set_core_pattern(pattern)?;
+
+		Ok(buf.len())
+	}
+
+	fn lseek(&self, _offset: isize, _whence: Whence) -> Result<usize, Errno> {
+		Err(Errno::EBADF)
+	}
+}
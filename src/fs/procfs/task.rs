@@ -1,5 +1,6 @@
 mod cwd;
 mod fd;
+mod oom;
 mod stat;
 
 pub use cwd::change_cwd;
@@ -17,6 +18,7 @@ use crate::process::{relation::Pid, task::Task};
 use crate::{sync::Locked, syscall::errno::Errno};
 
 use fd::ProcFdDirInode;
+use oom::{ProcOomScoreAdjInode, ProcOomScoreInode};
 use stat::ProcStatInode;
 
 use super::{PROCFS_ROOT_DIR, PROCFS_ROOT_DIR_ENTRY};
@@ -72,8 +74,8 @@ impl Inode for Locked<ProcDirInode> {
 			blksize: 0,
 			attributes: 0,
 			nlink: 0,
-			uid: this.task.get_uid(),
-			gid: this.task.get_gid(),
+			uid: this.task.get_euid(),
+			gid: this.task.get_egid(),
 			mode: StatxMode::new(StatxMode::DIRECTORY, 0o555),
 			pad1: 0,
 			ino: 0,
@@ -105,6 +107,8 @@ impl DirInode for Locked<ProcDirInode> {
 		let v = vec![
 			(7, b"cwd".to_vec()),
 			(2, b"fd".to_vec()),
+			(1, b"oom_score".to_vec()),
+			(1, b"oom_score_adj".to_vec()),
 			(1, b"stat".to_vec()),
 			(2, b".".to_vec()),
 			(2, b"..".to_vec()),
@@ -117,6 +121,12 @@ impl DirInode for Locked<ProcDirInode> {
 		match name {
 			b"cwd" => Ok(VfsInode::SymLink(self.lock().cwd.clone())),
 			b"fd" => Ok(VfsInode::Dir(self.lock().fds.clone())),
+			b"oom_score" => Ok(VfsInode::File(Arc::new(ProcOomScoreInode(
+				self.lock().task.clone(),
+			)))),
+			b"oom_score_adj" => Ok(VfsInode::File(Arc::new(ProcOomScoreAdjInode(
+				self.lock().task.clone(),
+			)))),
 			b"stat" => Ok(VfsInode::File(Arc::new(ProcStatInode(
 				self.lock().task.clone(),
 			)))),
@@ -91,8 +91,8 @@ impl Inode for Locked<ProcFdDirInode> {
 			blksize: 0,
 			attributes: 0,
 			nlink: 0,
-			uid: this.task.get_uid(),
-			gid: this.task.get_gid(),
+			uid: this.task.get_euid(),
+			gid: this.task.get_egid(),
 			mode: StatxMode::new(StatxMode::DIRECTORY, 0o500),
 			pad1: 0,
 			ino: 0,
@@ -0,0 +1,133 @@
+use alloc::{boxed::Box, format, sync::Arc};
+
+use crate::fs::procfs::ProcFileHandle;
+use crate::fs::vfs::{
+	FileHandle, FileInode, IOFlag, Inode, Permission, Statx, StatxMode, StatxTimeStamp, Whence,
+};
+use crate::mm::oom::{oom_score, OOM_SCORE_ADJ_MAX, OOM_SCORE_ADJ_MIN};
+use crate::process::task::{Task, CURRENT};
+use crate::{sync::LocalLocked, syscall::errno::Errno};
+
+fn stat(task: &Arc<Task>, perm: u16) -> Statx {
+	Statx {
+		mask: Statx::MASK_ALL,
+		blksize: 0,
+		attributes: 0,
+		nlink: 0,
+		uid: task.get_euid(),
+		gid: task.get_egid(),
+		mode: StatxMode::new(StatxMode::REGULAR, perm),
+		pad1: 0,
+		ino: 0,
+		size: 0,
+		blocks: 0,
+		attributes_mask: 0,
+		atime: StatxTimeStamp::default(),
+		btime: StatxTimeStamp::default(),
+		ctime: StatxTimeStamp::default(),
+		mtime: StatxTimeStamp::default(),
+		rdev_major: 0,
+		rdev_minor: 0,
+		dev_major: 0,
+		dev_minor: 0,
+	}
+}
+
+pub(super) struct ProcOomScoreInode(pub Arc<Task>);
+
+impl Inode for ProcOomScoreInode {
+	fn stat(&self) -> Result<Statx, Errno> {
+		Ok(stat(&self.0, 0o444))
+	}
+
+	fn chown(&self, _owner: usize, _group: usize) -> Result<(), Errno> {
+		Err(Errno::EPERM)
+	}
+
+	fn chmod(&self, _perm: Permission) -> Result<(), Errno> {
+		Err(Errno::EPERM)
+	}
+}
+
+impl FileInode for ProcOomScoreInode {
+	fn open(&self) -> Result<Box<dyn FileHandle>, Errno> {
+		Ok(Box::new(LocalLocked::new(ProcFileHandle::new(
+			format!("{}\n", oom_score(&self.0)).into_bytes(),
+		))))
+	}
+
+	fn truncate(&self, _length: isize) -> Result<(), Errno> {
+		Err(Errno::EPERM)
+	}
+}
+
+pub(super) struct ProcOomScoreAdjInode(pub Arc<Task>);
+
+impl Inode for ProcOomScoreAdjInode {
+	fn stat(&self) -> Result<Statx, Errno> {
+		Ok(stat(&self.0, 0o644))
+	}
+
+	fn chown(&self, _owner: usize, _group: usize) -> Result<(), Errno> {
+		Err(Errno::EPERM)
+	}
+
+	fn chmod(&self, _perm: Permission) -> Result<(), Errno> {
+		Err(Errno::EPERM)
+	}
+}
+
+impl FileInode for ProcOomScoreAdjInode {
+	fn open(&self) -> Result<Box<dyn FileHandle>, Errno> {
+		let adj = self
+			.0
+			.get_user_ext()
+			.map(|ext| ext.get_oom_score_adj())
+			.unwrap_or_default();
+
+		Ok(Box::new(OomScoreAdjHandle {
+			task: self.0.clone(),
+			contents: LocalLocked::new(ProcFileHandle::new(format!("{}\n", adj).into_bytes())),
+		}))
+	}
+
+	// allow `echo 100 > oom_score_adj`.
+	fn truncate(&self, _length: isize) -> Result<(), Errno> {
+		Ok(())
+	}
+}
+
+struct OomScoreAdjHandle {
+	task: Arc<Task>,
+	contents: LocalLocked<ProcFileHandle>,
+}
+
+impl FileHandle for OomScoreAdjHandle {
+	fn read(&self, buf: &mut [u8], flags: IOFlag) -> Result<usize, Errno> {
+		self.contents.read(buf, flags)
+	}
+
+	fn write(&self, buf: &[u8], _flags: IOFlag) -> Result<usize, Errno> {
+		let ext = self.task.get_user_ext().ok_or(Errno::EINVAL)?;
+
+		let adj = core::str::from_utf8(buf)
+			.ok()
+			.and_then(|s| s.trim().parse::<isize>().ok())
+			.filter(|adj| (OOM_SCORE_ADJ_MIN..=OOM_SCORE_ADJ_MAX).contains(adj))
+			.ok_or(Errno::EINVAL)?;
+
+		// only root can make a task less likely to be killed.
+		let current = unsafe { CURRENT.get_ref() };
+		if adj < ext.get_oom_score_adj() && !current.is_privileged() {
+			return Err(Errno::EACCES);
+		}
+
+		ext.set_oom_score_adj(adj);
+
+		Ok(buf.len())
+	}
+
+	fn lseek(&self, _offset: isize, _whence: Whence) -> Result<usize, Errno> {
+		Err(Errno::EBADF)
+	}
+}
@@ -14,8 +14,8 @@ impl Inode for ProcStatInode {
 			blksize: 0,
 			attributes: 0,
 			nlink: 0,
-			uid: self.0.get_uid(),
-			gid: self.0.get_gid(),
+			uid: self.0.get_euid(),
+			gid: self.0.get_egid(),
 			mode: StatxMode::new(StatxMode::REGULAR, 0o444),
 			pad1: 0,
 			ino: 0,
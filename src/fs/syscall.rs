@@ -23,7 +23,7 @@ mod write;
 
 pub use chmod::sys_chmod;
 pub use chown::sys_chown;
-pub use close::sys_close;
+pub use close::{sys_close, sys_close_range};
 pub use cwd::*;
 pub use fcntl::sys_fcntl;
 pub use getcwd::sys_getcwd;
@@ -33,7 +33,7 @@ pub use link::{sys_link, sys_rename};
 pub use lseek::{sys_llseek, sys_lseek};
 pub use mkdir::sys_mkdir;
 pub use mount::{sys_mount, sys_umount};
-pub use open::{sys_creat, sys_open};
+pub use open::{lookup_or_create, sys_creat, sys_open};
 pub use read::{sys_read, sys_readv};
 pub use readlink::sys_readlink;
 pub use statfs::{sys_statfs64, FsMagic, StatFs};
@@ -1,3 +1,5 @@
+use bitflags::bitflags;
+
 use crate::fs::delete_fd_node;
 use crate::process::{fd_table::Fd, task::CURRENT};
 use crate::syscall::errno::Errno;
@@ -16,3 +18,42 @@ pub fn sys_close(fildes: isize) -> Result<usize, Errno> {
 
 	Ok(0)
 }
+
+bitflags! {
+	#[derive(Clone, Copy, Debug)]
+	struct CloseRangeFlag: usize {
+		const CLOSE_RANGE_UNSHARE = 2;
+		const CLOSE_RANGE_CLOEXEC = 4;
+	}
+}
+
+/// with `CLOSE_RANGE_UNSHARE`, descriptors are closed in a private copy of the table,
+/// so other tasks sharing it are not affected.
+pub fn sys_close_range(first: usize, last: usize, flags: usize) -> Result<usize, Errno> {
+	let current = unsafe { CURRENT.get_ref() };
+	let ext = current.user_ext_ok_or(Errno::EPERM)?;
+
+	let flags = CloseRangeFlag::from_bits(flags).ok_or(Errno::EINVAL)?;
+
+	if first > last {
+		return Err(Errno::EINVAL);
+	}
+
+	if flags.contains(CloseRangeFlag::CLOSE_RANGE_UNSHARE) {
+		ext.unshare_fd_table();
+	}
+
+	if flags.contains(CloseRangeFlag::CLOSE_RANGE_CLOEXEC) {
+		ext.lock_fd_table().set_cloexec_range(first, last);
+		return Ok(0);
+	}
+
+	let closed = ext.lock_fd_table().close_range(first, last);
+
+	for (fd, handle) in closed {
+		let _ = handle.close();
+		let _ = delete_fd_node(current.get_pid(), fd);
+	}
+
+	Ok(0)
+}
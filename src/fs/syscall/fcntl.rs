@@ -3,7 +3,7 @@ use core::mem::transmute;
 use crate::{
 	fs::vfs::IOFlag,
 	process::{
-		fd_table::{self, Fd},
+		fd_table::{Fd, FdFlag},
 		task::CURRENT,
 	},
 	syscall::errno::Errno,
@@ -34,30 +34,39 @@ pub fn sys_fcntl(fd: isize, cmd: usize, arg: usize) -> Result<usize, Errno> {
 
 	use Cmd::*;
 	match cmd {
-		DupFd => dup_fd(fd, arg),
-		GetFd => Ok(0),
-		SetFd => Ok(0),
+		DupFd => dup_fd(fd, arg, FdFlag::empty()),
+		GetFd => get_fd(fd),
+		SetFd => set_fd(fd, arg),
 		GetFl => get_fl(fd),
 		SetFl => set_fl(fd, arg),
-		DupFdCloExec => dup_fd_clo_exec(fd, arg),
+		DupFdCloExec => dup_fd(fd, arg, FdFlag::FD_CLOEXEC),
 	}
 }
 
-fn dup_fd_clo_exec(src: Fd, start: usize) -> Result<usize, Errno> {
-	// TODO CLOEXEC
-	dup_fd(src, start)
+fn dup_fd(src: Fd, start: usize, flags: FdFlag) -> Result<usize, Errno> {
+	let mut fd_table = unsafe { CURRENT.get_ref() }
+		.user_ext_ok_or(Errno::EINVAL)?
+		.lock_fd_table();
+
+	fd_table.dup_start(src, start, flags).map(|fd| fd.index())
 }
 
-fn dup_fd(src: Fd, start: usize) -> Result<usize, Errno> {
-	if start > fd_table::FDTABLE_SIZE {
-		return Err(Errno::EINVAL);
-	}
+fn get_fd(fd: Fd) -> Result<usize, Errno> {
+	let fd_table = unsafe { CURRENT.get_ref() }
+		.user_ext_ok_or(Errno::EINVAL)?
+		.lock_fd_table();
+
+	fd_table.get_flags(fd).map(|flags| flags.bits())
+}
 
+fn set_fd(fd: Fd, arg: usize) -> Result<usize, Errno> {
 	let mut fd_table = unsafe { CURRENT.get_ref() }
 		.user_ext_ok_or(Errno::EINVAL)?
 		.lock_fd_table();
 
-	fd_table.dup_start(src, start).map(|fd| fd.index())
+	fd_table
+		.set_flags(fd, FdFlag::from_bits_truncate(arg))
+		.map(|_| 0)
 }
 
 fn get_fl(fd: Fd) -> Result<usize, Errno> {
@@ -12,33 +12,35 @@ use crate::{
 		path::Path,
 		procfs::ProcFs,
 		tmpfs::TmpFs,
-		vfs::{lookup_entry_nofollow, MemoryFileSystem, PhysicalFileSystem, VfsDirEntry},
+		vfs::{
+			lookup_entry_nofollow, MemoryFileSystem, MountFlag, PhysicalFileSystem, VfsDirEntry,
+		},
 	},
 	process::task::Task,
 };
 
 macro_rules! mount_arm {
-	(MEMFS $blk:ident | $mount_point:ident | $task:ident | $fs:ty) => {{
+	(MEMFS $blk:ident | $mount_point:ident | $flags:ident | $task:ident | $fs:ty) => {{
 		let (sb, inode) = <$fs>::mount()?;
-		let new_dentry = $mount_point.mount(inode, sb, $task)?;
+		let new_dentry = $mount_point.mount(inode, sb, $flags, $task)?;
 		<$fs>::finish_mount(&new_dentry);
 
 		return Ok(0);
 	}};
 
-	(PHYFS $blk:ident | $mount_point:ident | $task:ident | $fs:ty) => {{
+	(PHYFS $blk:ident | $mount_point:ident | $flags:ident | $task:ident | $fs:ty) => {{
 		let (sb, inode) = <$fs>::mount($blk?)?;
-		_ = $mount_point.mount(inode, sb, $task)?;
+		_ = $mount_point.mount(inode, sb, $flags, $task)?;
 
 		return Ok(0);
 	}};
 }
 
 macro_rules! mount_fs {
-	($blk:ident, $fs_name:ident, $mount_point:ident, $task:ident {$($typ:ident $name:literal => $fs:ty),* $(,)?}) => {
+	($blk:ident, $fs_name:ident, $mount_point:ident, $flags:ident, $task:ident {$($typ:ident $name:literal => $fs:ty),* $(,)?}) => {
 		match $fs_name {
 			$(
-				$name => mount_arm!($typ $blk | $mount_point | $task | $fs),
+				$name => mount_arm!($typ $blk | $mount_point | $flags | $task | $fs),
 			)*
 			_ => return Err(Errno::EINVAL),
 		}
@@ -49,9 +51,10 @@ fn do_mount(
 	block_device: Result<PartBorrow, Errno>,
 	fs_name: &[u8],
 	mount_point_entry: Arc<VfsDirEntry>,
+	flags: MountFlag,
 	task: &Arc<Task>,
 ) -> Result<usize, Errno> {
-	mount_fs!(block_device, fs_name, mount_point_entry, task {
+	mount_fs!(block_device, fs_name, mount_point_entry, flags, task {
 		MEMFS b"tmpfs" => TmpFs,
 		MEMFS b"procfs" => ProcFs,
 		MEMFS b"devfs" => DevFs,
@@ -60,8 +63,14 @@ fn do_mount(
 	})
 }
 
-pub fn sys_mount(dev_path: usize, mount_point: usize, fs_name: usize) -> Result<usize, Errno> {
+pub fn sys_mount(
+	dev_path: usize,
+	mount_point: usize,
+	fs_name: usize,
+	flags: usize,
+) -> Result<usize, Errno> {
 	let current = unsafe { CURRENT.get_mut() };
+	let flags = MountFlag::from_bits_truncate(flags);
 
 	let dev_path_buf = verify_path(dev_path, current)?;
 	let dev_path = Path::new(dev_path_buf);
@@ -74,9 +83,9 @@ pub fn sys_mount(dev_path: usize, mount_point: usize, fs_name: usize) -> Result<
 		.and_then(|x| x.downcast_block())
 		.and_then(|x| x.get_device());
 
-	let ret = do_mount(block_device, fs_name, entry, current)?;
+	let ret = do_mount(block_device, fs_name, entry, flags, current)?;
 
-	create_mount_entry(dev_path_buf, mount_point_buf, fs_name);
+	create_mount_entry(dev_path_buf, mount_point_buf, fs_name, flags);
 
 	Ok(ret)
 }
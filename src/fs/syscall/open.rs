@@ -3,8 +3,8 @@ use alloc::sync::Arc;
 use crate::fs::create_fd_node;
 use crate::fs::path::Path;
 use crate::fs::vfs::{
-	lookup_entry_at_follow, lookup_entry_follow, AccessFlag, CreationFlag, IOFlag, Permission,
-	VfsEntry,
+	lookup_entry_at_follow, lookup_entry_at_follow_except_last, lookup_entry_follow, AccessFlag,
+	CreationFlag, IOFlag, Permission, VfsEntry,
 };
 
 use crate::mm::user::verify::verify_path;
@@ -12,7 +12,7 @@ use crate::process::task::{Task, CURRENT};
 use crate::syscall::errno::Errno;
 use crate::trace_feature;
 
-fn lookup_or_create(
+pub fn lookup_or_create(
 	mut path: Path,
 	creation_flags: CreationFlag,
 	perm: Permission,
@@ -22,14 +22,23 @@ fn lookup_or_create(
 
 	let base_entry = lookup_entry_follow(&path, task)?;
 
+	let nofollow = creation_flags.contains(CreationFlag::O_NOFOLLOW);
 	let entry = match file {
-		Some(ref name) => base_entry
-			.clone()
-			.downcast_dir()
-			.and_then(|dir| lookup_entry_at_follow(dir, &Path::new(name), task)),
+		Some(ref name) => base_entry.clone().downcast_dir().and_then(|dir| {
+			let path = Path::new(name);
+			match nofollow {
+				true => lookup_entry_at_follow_except_last(dir, &path, task),
+				false => lookup_entry_at_follow(dir, &path, task),
+			}
+		}),
 		None => Ok(base_entry.clone()),
 	};
 
+	// last component must not be a symbolic link with `O_NOFOLLOW`.
+	if let (true, Ok(VfsEntry::SymLink(_))) = (nofollow, &entry) {
+		return Err(Errno::ELOOP);
+	}
+
 	match entry {
 		// directory / file already exists.
 		Ok(ent) => match creation_flags.contains(CreationFlag::O_EXCL) {
@@ -91,7 +100,7 @@ pub fn sys_open(path: usize, flags: i32, perm: u32) -> Result<usize, Errno> {
 		.get_user_ext()
 		.expect("must be user process")
 		.lock_fd_table()
-		.alloc_fd(file.clone())
+		.alloc_fd(file.clone(), creation_flags.into())
 		.ok_or(Errno::EMFILE)?;
 
 	let _ = create_fd_node(current.get_pid(), fd.clone(), file);
@@ -1,7 +1,7 @@
 use crate::{
 	fs::{path::Path, vfs::lookup_entry_follow},
 	mm::user::verify::verify_path,
-	process::task::CURRENT,
+	process::{rlimit::limit_file_size, task::CURRENT},
 	syscall::errno::Errno,
 };
 
@@ -13,5 +13,10 @@ pub fn sys_truncate(path: usize, length: isize) -> Result<usize, Errno> {
 
 	let entry = lookup_entry_follow(&path, current).and_then(|x| x.downcast_file())?;
 
+	// last byte must be writable under `RLIMIT_FSIZE`.
+	if length > 0 {
+		limit_file_size(length as usize - 1, 1)?;
+	}
+
 	entry.truncate(length, current).map(|_| 0)
 }
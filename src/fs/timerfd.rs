@@ -0,0 +1,228 @@
+//! timer notification through a file descriptor. (`timerfd_create`)
+//!
+//! reading returns the number of expirations since the last read
+//! or settime, blocking until there is one.
+
+use core::mem::{size_of, take};
+
+use alloc::sync::{Arc, Weak};
+use bitflags::bitflags;
+
+use crate::{
+	mm::user::verify::{verify_ptr, verify_ptr_mut},
+	process::{
+		fd_table::{Fd, FdFlag},
+		posix_timer::ITimerSpec,
+		signal::poll_signal_queue,
+		task::CURRENT,
+		wait_list::WaitList,
+	},
+	scheduler::{
+		sleep::{sleep_and_yield_lock, Sleep},
+		timer::{Expire, IntervalTimer},
+	},
+	sync::Locked,
+	syscall::{clock::ClockId, errno::Errno},
+};
+
+use super::vfs::{AccessFlag, IOFlag, VfsHandle};
+
+bitflags! {
+	#[repr(transparent)]
+	#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+	struct TimerFdFlag: usize {
+		const Nonblock = 0o4000;
+		const Cloexec = 0o2000000;
+	}
+}
+
+const TFD_TIMER_ABSTIME: usize = 1;
+
+struct TimerFdState {
+	timer: IntervalTimer,
+	/// expirations not read yet.
+	ticks: u64,
+}
+
+pub struct TimerFd {
+	clock: ClockId,
+	state: Locked<TimerFdState>,
+	waiters: Locked<WaitList>,
+	io_flags: Locked<IOFlag>,
+	access_flags: AccessFlag,
+}
+
+impl Expire for TimerFd {
+	fn expire(&self, id: usize, now: u64) -> Option<u64> {
+		let (ticks, next) = {
+			let mut state = self.state.lock();
+			let (ticks, next) = state.timer.expire(id, now)?;
+			state.ticks += ticks as u64;
+
+			(ticks, next)
+		};
+
+		if ticks > 0 {
+			self.waiters.lock().wake_up_all();
+		}
+
+		next
+	}
+}
+
+impl TimerFd {
+	fn new(clock: ClockId, io_flags: IOFlag) -> Self {
+		Self {
+			clock,
+			state: Locked::new(TimerFdState {
+				timer: IntervalTimer::new(),
+				ticks: 0,
+			}),
+			waiters: Locked::new(WaitList::new()),
+			io_flags: Locked::new(io_flags),
+			access_flags: AccessFlag::O_RDONLY,
+		}
+	}
+
+	pub fn read(&self, buf: &mut [u8]) -> Result<usize, Errno> {
+		if buf.len() < size_of::<u64>() {
+			return Err(Errno::EINVAL);
+		}
+
+		loop {
+			// registered before checking, not to miss a wake up in between.
+			let mut waiters = self.waiters.lock();
+
+			let ticks = take(&mut self.state.lock().ticks);
+			if ticks > 0 {
+				buf[..size_of::<u64>()].copy_from_slice(&ticks.to_ne_bytes());
+				return Ok(size_of::<u64>());
+			}
+
+			if self.io_flags().contains(IOFlag::O_NONBLOCK) {
+				return Err(Errno::EAGAIN);
+			}
+
+			waiters.register();
+			sleep_and_yield_lock(Sleep::Light, waiters);
+
+			unsafe { poll_signal_queue() }?;
+		}
+	}
+
+	pub fn io_flags(&self) -> IOFlag {
+		*self.io_flags.lock()
+	}
+
+	pub fn set_io_flags(&self, flags: IOFlag) {
+		*self.io_flags.lock() = flags;
+	}
+
+	pub fn access_flags(&self) -> AccessFlag {
+		self.access_flags
+	}
+
+	fn settime(self: &Arc<Self>, spec: &ITimerSpec, absolute: bool) -> ITimerSpec {
+		let mut state = self.state.lock();
+		let old = Self::gettime(&state);
+
+		let weak: Weak<dyn Expire> = Arc::downgrade(self) as _;
+		state.ticks = 0;
+		state.timer.arm(
+			spec.expires(self.clock, absolute),
+			spec.interval.nano(),
+			weak,
+		);
+
+		old
+	}
+
+	fn gettime(state: &TimerFdState) -> ITimerSpec {
+		let (value, interval) = state.timer.remaining(ClockId::Realtime.now());
+
+		ITimerSpec::new(value, interval)
+	}
+}
+
+fn get_timerfd(fd: usize) -> Result<Arc<TimerFd>, Errno> {
+	let current = unsafe { CURRENT.get_ref() };
+	let fd = Fd::from(fd).ok_or(Errno::EBADF)?;
+
+	let handle = current
+		.user_ext_ok_or(Errno::EBADF)?
+		.lock_fd_table()
+		.get_file(fd)
+		.ok_or(Errno::EBADF)?;
+
+	match handle {
+		VfsHandle::TimerFd(t) => Ok(t),
+		_ => Err(Errno::EINVAL),
+	}
+}
+
+pub fn sys_timerfd_create(clockid: usize, flags: usize) -> Result<usize, Errno> {
+	let current = unsafe { CURRENT.get_ref() };
+
+	let clock = ClockId::from_usize(clockid).ok_or(Errno::EINVAL)?;
+	let flags = TimerFdFlag::from_bits(flags).ok_or(Errno::EINVAL)?;
+
+	let io_flags = match flags.contains(TimerFdFlag::Nonblock) {
+		true => IOFlag::O_NONBLOCK,
+		false => IOFlag::empty(),
+	};
+
+	let fd_flags = match flags.contains(TimerFdFlag::Cloexec) {
+		true => FdFlag::FD_CLOEXEC,
+		false => FdFlag::empty(),
+	};
+
+	let handle = VfsHandle::TimerFd(Arc::new(TimerFd::new(clock, io_flags)));
+
+	let fd = current
+		.user_ext_ok_or(Errno::EPERM)?
+		.lock_fd_table()
+		.alloc_fd(handle, fd_flags)
+		.ok_or(Errno::EMFILE)?;
+
+	Ok(fd.index())
+}
+
+pub fn sys_timerfd_settime(
+	fd: usize,
+	flags: usize,
+	new_value: usize,
+	old_value: usize,
+) -> Result<usize, Errno> {
+	let current = unsafe { CURRENT.get_ref() };
+
+	let timerfd = get_timerfd(fd)?;
+	let new = verify_ptr::<ITimerSpec>(new_value, current)?;
+
+	if !new.is_valid() {
+		return Err(Errno::EINVAL);
+	}
+
+	let old_value = match old_value {
+		0 => None,
+		x => Some(verify_ptr_mut::<ITimerSpec>(x, current)?),
+	};
+
+	let old = timerfd.settime(new, flags & TFD_TIMER_ABSTIME != 0);
+
+	if let Some(old_value) = old_value {
+		*old_value = old;
+	}
+
+	Ok(0)
+}
+
+pub fn sys_timerfd_gettime(fd: usize, curr_value: usize) -> Result<usize, Errno> {
+	let current = unsafe { CURRENT.get_ref() };
+
+	let timerfd = get_timerfd(fd)?;
+	let curr_value = verify_ptr_mut::<ITimerSpec>(curr_value, current)?;
+
+	*curr_value = TimerFd::gettime(&timerfd.state.lock());
+
+	Ok(0)
+}
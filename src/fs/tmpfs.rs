@@ -341,8 +341,8 @@ impl DirInode for Locked<TmpDirInode> {
 		use alloc::collections::btree_map::Entry::*;
 		match this.sub_files.entry(ident) {
 			Vacant(v) => {
-				let current = unsafe { CURRENT.get_mut() };
-				let new_dir = TmpDirInode::new_shared(perm, current.get_uid(), current.get_gid());
+				let cred = unsafe { CURRENT.get_ref() }.get_cred();
+				let new_dir = TmpDirInode::new_shared(perm, cred.fsuid, cred.fsgid);
 
 				v.insert(TmpInode::Dir(new_dir.clone()));
 
@@ -393,8 +393,8 @@ impl DirInode for Locked<TmpDirInode> {
 		let ident = Ident::new(name);
 		match this.sub_files.entry(ident) {
 			Vacant(v) => {
-				let current = unsafe { CURRENT.get_ref() };
-				let new_file = TmpFileInode::new(perm, current.get_uid(), current.get_gid());
+				let cred = unsafe { CURRENT.get_ref() }.get_cred();
+				let new_file = TmpFileInode::new(perm, cred.fsuid, cred.fsgid);
 
 				v.insert(TmpInode::File(new_file.clone()));
 
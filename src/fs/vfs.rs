@@ -7,6 +7,7 @@ mod walk;
 use alloc::boxed::Box;
 use alloc::sync::Arc;
 use alloc::vec::Vec;
+use bitflags::bitflags;
 pub use entry::*;
 pub use handle::*;
 pub use inode::*;
@@ -19,6 +20,14 @@ use crate::syscall::errno::Errno;
 use super::devfs::partition::PartBorrow;
 use super::syscall::StatFs;
 
+bitflags! {
+	#[derive(Clone, Copy, Debug)]
+	pub struct MountFlag: usize {
+		/// set-user-ID and set-group-ID bits are ignored on exec.
+		const MS_NOSUID = 2;
+	}
+}
+
 pub trait FileSystem {
 	fn unmount(&self, sb: &Arc<dyn SuperBlock>) -> Result<(), Errno> {
 		sb.unmount()
@@ -4,6 +4,7 @@ mod file;
 mod socket;
 mod symlink;
 
+pub use block::VfsBlockEntry;
 pub use dir::VfsDirEntry;
 pub use file::VfsFileEntry;
 pub use socket::VfsSocketEntry;
@@ -23,8 +24,6 @@ use crate::{
 	syscall::errno::Errno,
 };
 
-use self::block::VfsBlockEntry;
-
 use super::{
 	AccessFlag, DirInode, FileInode, IOFlag, Inode, Permission, SocketInode, Statx, SuperBlock,
 	SymLinkInode, VfsDirHandle, VfsFileHandle, VfsHandle, VfsInode, VfsSocketHandle,
@@ -174,15 +173,14 @@ pub trait Entry {
 	}
 
 	fn access(&self, perm: Permission, task: &Arc<Task>) -> Result<(), Errno> {
-		self.get_inode()
-			.access(task.get_uid(), task.get_gid(), perm)
+		self.get_inode().access(&task.get_cred(), perm)
 	}
 
 	fn chmod(&self, perm: Permission, task: &Arc<Task>) -> Result<(), Errno> {
 		let owner = self.statx()?.uid;
 
-		let uid = task.get_uid();
-		if uid != 0 && uid != owner {
+		let cred = task.get_cred();
+		if !cred.is_privileged() && cred.fsuid != owner {
 			return Err(Errno::EPERM);
 		}
 
@@ -190,7 +188,7 @@ pub trait Entry {
 	}
 
 	fn chown(&self, owner: usize, group: usize, task: &Arc<Task>) -> Result<(), Errno> {
-		if task.get_uid() != 0 {
+		if !task.lock_cred().is_privileged() {
 			return Err(Errno::EPERM);
 		}
 
@@ -206,7 +204,7 @@ pub trait Entry {
 
 		parent
 			.inode
-			.access(task.get_uid(), task.get_gid(), Permission::ANY_EXECUTE)?;
+			.access(&task.get_cred(), Permission::ANY_EXECUTE)?;
 
 		Ok(parent)
 	}
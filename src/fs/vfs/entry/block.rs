@@ -28,6 +28,10 @@ impl VfsBlockEntry {
 	pub fn get_device(&self) -> Result<PartBorrow, Errno> {
 		self.dev.get()
 	}
+
+	pub fn is_device_of(&self, part: &PartBorrow) -> bool {
+		part.is_borrowed_from(&self.dev)
+	}
 }
 
 impl Entry for Arc<VfsBlockEntry> {
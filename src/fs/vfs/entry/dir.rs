@@ -8,7 +8,7 @@ use alloc::{
 };
 
 use crate::{
-	fs::vfs::{entry::block::VfsBlockEntry, Inode},
+	fs::vfs::{entry::block::VfsBlockEntry, Inode, MountFlag},
 	process::task::Task,
 	sync::{LocalLocked, Locked},
 	syscall::errno::Errno,
@@ -28,6 +28,7 @@ pub struct VfsDirEntry {
 	sub_mount: LocalLocked<BTreeMap<Ident, VfsEntry>>,
 	next_mount: Option<Arc<VfsDirEntry>>,
 	is_mount_point: bool,
+	mount_flags: MountFlag,
 }
 
 impl VfsDirEntry {
@@ -47,6 +48,7 @@ impl VfsDirEntry {
 			super_block,
 			next_mount: None,
 			is_mount_point,
+			mount_flags: MountFlag::empty(),
 		}
 	}
 
@@ -205,7 +207,7 @@ impl VfsDirEntry {
 
 	pub fn lookup(self: &Arc<Self>, name: &[u8], task: &Arc<Task>) -> Result<VfsEntry, Errno> {
 		self.inode
-			.access(task.get_uid(), task.get_gid(), Permission::ANY_EXECUTE)?;
+			.access(&task.get_cred(), Permission::ANY_EXECUTE)?;
 
 		if let Some(x) = self.sub_mount.lock().get(name) {
 			return Ok(x.clone());
@@ -232,6 +234,20 @@ impl VfsDirEntry {
 		&self.super_block
 	}
 
+	/// flags of the mount this directory belongs to.
+	pub fn mount_flags(self: &Arc<Self>) -> MountFlag {
+		let mut dir = self.clone();
+
+		while !dir.is_mount_point {
+			match dir.parent.upgrade() {
+				Some(parent) => dir = parent,
+				None => break,
+			}
+		}
+
+		dir.mount_flags
+	}
+
 	fn do_absolute_root_mount(mut self) -> Arc<VfsDirEntry> {
 		let new_dentry = Arc::new_cyclic(|parent| {
 			self.parent = parent.clone();
@@ -262,6 +278,7 @@ impl VfsDirEntry {
 		self: &Arc<Self>,
 		inode: Arc<dyn DirInode>,
 		super_block: Arc<dyn SuperBlock>,
+		flags: MountFlag,
 		task: &Arc<Task>,
 	) -> Result<Arc<VfsDirEntry>, Errno> {
 		if !task.is_privileged() {
@@ -278,6 +295,7 @@ impl VfsDirEntry {
 			next_mount: Some(self.clone()),
 			super_block,
 			is_mount_point: true,
+			mount_flags: flags,
 		};
 
 		let new_dentry = match Arc::ptr_eq(self, &parent) {
@@ -3,8 +3,10 @@ use core::mem::{offset_of, size_of, transmute};
 use alloc::{boxed::Box, sync::Arc};
 
 use crate::fs::path::Path;
+use crate::fs::timerfd::TimerFd;
 use crate::net::address::{ReadOnly, UnknownSocketAddress, WriteOnly};
 use crate::net::socket::{Socket, SocketHandle};
+use crate::process::rlimit::limit_file_size;
 use crate::process::task::{Task, CURRENT};
 use crate::sync::LocalLocked;
 use crate::syscall::errno::Errno;
@@ -16,6 +18,7 @@ pub enum VfsHandle {
 	File(Arc<VfsFileHandle>),
 	Socket(Arc<VfsSocketHandle>),
 	Dir(Arc<VfsDirHandle>),
+	TimerFd(Arc<TimerFd>),
 }
 
 impl VfsHandle {
@@ -25,6 +28,7 @@ impl VfsHandle {
 			File(f) => f.read(buf),
 			Socket(s) => s.recv_from(&mut None, buf),
 			Dir(_) => Err(Errno::EISDIR),
+			TimerFd(t) => t.read(buf),
 		}
 	}
 
@@ -34,6 +38,7 @@ impl VfsHandle {
 			File(f) => f.write(buf),
 			Socket(s) => s.send_to(&None, buf),
 			Dir(_) => Err(Errno::EISDIR),
+			TimerFd(_) => Err(Errno::EINVAL),
 		}
 	}
 
@@ -42,14 +47,22 @@ impl VfsHandle {
 		match self {
 			File(f) => f.close(),
 			Dir(d) => d.close(),
-			Socket(_) => Ok(()),
+			Socket(_) | TimerFd(_) => Ok(()),
+		}
+	}
+
+	pub fn sync(&self) -> Result<(), Errno> {
+		use VfsHandle::*;
+		match self {
+			File(f) => f.sync(),
+			Dir(_) | Socket(_) | TimerFd(_) => Ok(()),
 		}
 	}
 
 	pub fn getdents(&self, buf: &mut [u8]) -> Result<usize, Errno> {
 		use VfsHandle::*;
 		match self {
-			File(_) | Socket(_) => Err(Errno::ENOTDIR),
+			File(_) | Socket(_) | TimerFd(_) => Err(Errno::ENOTDIR),
 			Dir(d) => d.getdents(buf),
 		}
 	}
@@ -58,7 +71,7 @@ impl VfsHandle {
 		use VfsHandle::*;
 		match self {
 			File(f) => f.lseek(offset, whence),
-			Socket(_) => Err(Errno::ESPIPE),
+			Socket(_) | TimerFd(_) => Err(Errno::ESPIPE),
 			Dir(_) => Err(Errno::EISDIR),
 		}
 	}
@@ -67,7 +80,7 @@ impl VfsHandle {
 		use VfsHandle::*;
 		match self {
 			File(f) => f.ioctl(request, argp),
-			Socket(_) | Dir(_) => Err(Errno::ENOTTY),
+			Socket(_) | Dir(_) | TimerFd(_) => Err(Errno::ENOTTY),
 		}
 	}
 
@@ -77,6 +90,7 @@ impl VfsHandle {
 			File(f) => f.entry.clone().map(|ent| VfsEntry::new_file(ent)),
 			Socket(s) => s.entry.clone().map(|ent| VfsEntry::new_socket(ent)),
 			Dir(d) => d.entry.clone().map(|ent| VfsEntry::new_dir(ent)),
+			TimerFd(_) => None,
 		}
 	}
 
@@ -86,17 +100,11 @@ impl VfsHandle {
 		ent.get_abs_path()
 	}
 
-	pub fn deep_copy(&self) -> Result<Self, Errno> {
+	/// open the entry again with the same access mode, but with its own offset and `io_flags`.
+	pub fn reopen(&self, io_flags: IOFlag) -> Result<Self, Errno> {
 		let ent = self.as_entry().ok_or(Errno::EINVAL)?;
 
-		use VfsHandle::*;
-		let (io_flags, access_flags) = match self {
-			File(f) => (*f.io_flags.lock(), f.access_flags),
-			Socket(s) => (s.io_flags, s.access_flags),
-			Dir(d) => (d.io_flags, d.access_flags),
-		};
-
-		ent.open(io_flags, access_flags, unsafe { CURRENT.get_ref() })
+		ent.open(io_flags, self.access_flags(), unsafe { CURRENT.get_ref() })
 	}
 
 	pub fn set_io_flags(&self, new_flags: IOFlag) -> Result<(), Errno> {
@@ -106,6 +114,10 @@ impl VfsHandle {
 				*f.io_flags.lock() = new_flags;
 				Ok(())
 			}
+			TimerFd(t) => {
+				t.set_io_flags(new_flags);
+				Ok(())
+			}
 			Socket(_) | Dir(_) => Err(Errno::EPERM),
 		}
 	}
@@ -116,6 +128,7 @@ impl VfsHandle {
 			File(f) => *f.io_flags.lock(),
 			Socket(s) => s.io_flags,
 			Dir(d) => d.io_flags,
+			TimerFd(t) => t.io_flags(),
 		}
 	}
 
@@ -125,6 +138,7 @@ impl VfsHandle {
 			File(f) => f.access_flags,
 			Socket(s) => s.access_flags,
 			Dir(d) => d.access_flags,
+			TimerFd(t) => t.access_flags(),
 		}
 	}
 }
@@ -168,7 +182,16 @@ impl VfsFileHandle {
 		if io_flags.contains(IOFlag::O_APPEND) {
 			self.inner.lseek(0, Whence::End)?;
 		}
-		self.inner.write(buf, *io_flags)
+
+		// regular file cannot grow over `RLIMIT_FSIZE`.
+		let len = match &self.entry {
+			Some(entry) if entry.statx()?.get_type() == FileType::Regular => {
+				limit_file_size(self.inner.lseek(0, Whence::Current)?, buf.len())?
+			}
+			_ => buf.len(),
+		};
+
+		self.inner.write(&buf[..len], *io_flags)
 	}
 
 	pub fn lseek(&self, offset: isize, whence: Whence) -> Result<usize, Errno> {
@@ -182,6 +205,10 @@ impl VfsFileHandle {
 	pub fn close(&self) -> Result<(), Errno> {
 		self.inner.close()
 	}
+
+	pub fn sync(&self) -> Result<(), Errno> {
+		self.inner.sync()
+	}
 }
 
 pub struct VfsDirHandle {
@@ -323,6 +350,10 @@ pub trait FileHandle {
 	fn close(&self) -> Result<(), Errno> {
 		Ok(())
 	}
+	/// flush written data to underlying device.
+	fn sync(&self) -> Result<(), Errno> {
+		Ok(())
+	}
 }
 
 #[repr(C)]
@@ -3,6 +3,7 @@ use bitflags::bitflags;
 
 use crate::{
 	fs::{devfs::partition::DevPart, path::Path},
+	process::cred::Credentials,
 	sync::Locked,
 	syscall::errno::Errno,
 };
@@ -128,6 +129,10 @@ impl TimeSpec {
 	pub fn nano(&self) -> u64 {
 		self.seconds as u64 * 1_000_000_000 + self.nanoseconds as u64
 	}
+
+	pub fn is_valid(&self) -> bool {
+		self.seconds >= 0 && (0..1_000_000_000).contains(&self.nanoseconds)
+	}
 }
 
 impl From<u64> for TimeSpec {
@@ -153,15 +158,14 @@ fn default_access(
 	file_uid: usize,
 	file_gid: usize,
 	file_perm: Permission,
-	req_uid: usize,
-	req_gid: usize,
+	cred: &Credentials,
 	req_perm: Permission,
 ) -> bool {
-	if file_uid == req_uid && file_perm.owner_ok(req_perm) {
+	if file_uid == cred.fsuid && file_perm.owner_ok(req_perm) {
 		return true;
 	}
 
-	if file_gid == req_gid && file_perm.group_ok(req_perm) {
+	if cred.in_group(file_gid) && file_perm.group_ok(req_perm) {
 		return true;
 	}
 
@@ -176,11 +180,11 @@ pub trait Inode {
 	fn stat(&self) -> Result<Statx, Errno>;
 	fn chown(&self, owner: usize, group: usize) -> Result<(), Errno>;
 	fn chmod(&self, perm: Permission) -> Result<(), Errno>;
-	fn access(&self, uid: usize, gid: usize, perm: Permission) -> Result<(), Errno> {
+	fn access(&self, cred: &Credentials, perm: Permission) -> Result<(), Errno> {
 		let stat = self.stat()?;
 		let file_perm = stat.get_perm();
 
-		if !default_access(stat.uid, stat.gid, file_perm, uid, gid, perm) {
+		if !default_access(stat.uid, stat.gid, file_perm, cred, perm) {
 			return Err(Errno::EACCES);
 		}
 
@@ -1,5 +1,7 @@
 use crate::interrupt::InterruptFrame;
 use crate::process::exit::exit_with_signal;
+use crate::process::signal::force_fault_signal;
+use crate::process::signal::sig_code::SigCode;
 use crate::process::signal::sig_num::SigNum;
 use crate::{pr_err, pr_info};
 
@@ -9,7 +11,8 @@ pub extern "C" fn handle_divide_error_impl(frame: InterruptFrame) {
 	pr_info!("{}", frame);
 
 	if frame.is_user() {
-		exit_with_signal(SigNum::FPE);
+		force_fault_signal(&frame, SigNum::FPE, SigCode::FPE_INTDIV, frame.eip);
+		return;
 	}
 
 	loop {}
@@ -21,12 +24,28 @@ pub extern "C" fn handle_invalid_opcode_impl(frame: InterruptFrame) {
 	pr_info!("{}", frame);
 
 	if frame.is_user() {
-		exit_with_signal(SigNum::ILL);
+		force_fault_signal(&frame, SigNum::ILL, SigCode::ILL_ILLOPN, frame.eip);
+		return;
 	}
 
 	loop {}
 }
 
+/// single step of a traced task.
+#[no_mangle]
+pub extern "C" fn handle_debug_impl(frame: InterruptFrame) {
+	if frame.is_user() {
+		force_fault_signal(&frame, SigNum::TRAP, SigCode::TRAP_TRACE, frame.eip);
+	}
+}
+
+#[no_mangle]
+pub extern "C" fn handle_breakpoint_impl(frame: InterruptFrame) {
+	if frame.is_user() {
+		force_fault_signal(&frame, SigNum::TRAP, SigCode::TRAP_BRKPT, frame.eip);
+	}
+}
+
 #[no_mangle]
 pub extern "C" fn handle_general_protection_impl(frame: InterruptFrame) {
 	pr_err!("Exception(fault): GENERAL PROTECTION");
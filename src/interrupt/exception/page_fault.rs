@@ -5,16 +5,12 @@ use kfs_macro::interrupt_handler;
 
 use crate::driver::terminal::sys_attach_tty;
 use crate::interrupt::InterruptFrame;
-use crate::mm::alloc::virt::{kmap, kunmap};
-use crate::mm::alloc::Zone;
-use crate::mm::constant::PAGE_MASK;
-use crate::mm::page::PageFlag;
-use crate::mm::user::vma::{AreaFlag, UserAddressSpace};
-use crate::process::exit::exit_with_signal;
+use crate::mm::user::memory::handle_fault;
+use crate::process::signal::force_fault_signal;
+use crate::process::signal::sig_code::SigCode;
 use crate::process::signal::sig_num::SigNum;
 use crate::process::task::CURRENT;
-use crate::ptr::PageBox;
-use crate::PAGE_SIZE;
+use crate::syscall::errno::Errno;
 use crate::{pr_err, pr_info, register};
 
 bitflags! {
@@ -62,57 +58,18 @@ impl Display for ErrorCode {
 	}
 }
 
-fn lookup_page_info(
-	vma: &UserAddressSpace,
-	vaddr: usize,
-	flags: AreaFlag,
-) -> Result<(usize, PageFlag), ()> {
-	let area = vma.find_area(vaddr).ok_or(())?;
-
-	if !area.flags.contains(flags) {
-		return Err(());
-	}
-
-	let base = vaddr & PAGE_MASK;
-	let extra_flag = if area.flags.contains(AreaFlag::Writable) {
-		PageFlag::Write
-	} else {
-		PageFlag::empty()
-	};
-
-	Ok((base, PageFlag::Present | PageFlag::User | extra_flag))
-}
-
-fn handle_user_page_fault(vaddr: usize, error_code: ErrorCode) -> Result<(), ()> {
+/// kernel accesses user memory after `prefault`, which returns `EINTR` to the syscall.
+/// so only fault raised from user mode can be interrupted.
+fn handle_user_page_fault(vaddr: usize, error_code: ErrorCode, user: bool) -> Result<(), ()> {
 	let current = unsafe { CURRENT.get_mut() };
+	let user_ext = current.get_user_ext().expect("must be user task");
 
-	let flags = if error_code.contains(ErrorCode::Write) {
-		AreaFlag::Writable
-	} else {
-		AreaFlag::Readable
-	};
-
-	let mut memory = current
-		.get_user_ext()
-		.expect("must be user task")
-		.lock_memory();
-
-	let page = PageBox::new(Zone::High).map_err(|_| ())?;
-
-	let temp = kmap(page.as_phys_addr()).map_err(|_| ())?;
-	unsafe { temp.as_ptr().write_bytes(0, PAGE_SIZE) };
-	kunmap(temp.as_ptr() as usize);
-
-	let (base, page_flags) = lookup_page_info(memory.get_vma(), vaddr, flags)?;
-
-	memory
-		.get_pd()
-		.map_user(base, page.as_phys_addr(), page_flags)
-		.map_err(|_| ())?;
-
-	page.forget();
-
-	Ok(())
+	match handle_fault(user_ext, vaddr, error_code.contains(ErrorCode::Write)) {
+		Ok(_) => Ok(()),
+		// interrupted by signal. it is handled on return, then the fault is retried.
+		Err(Errno::EINTR) if user => Ok(()),
+		Err(_) => Err(()),
+	}
 }
 
 #[interrupt_handler]
@@ -120,7 +77,7 @@ pub extern "C" fn handle_page_fault_impl(frame: InterruptFrame) {
 	let addr = register!("cr2");
 	let error_code = ErrorCode::from_bits_truncate(frame.error_code as u32);
 
-	if let Ok(_) = handle_user_page_fault(addr, error_code) {
+	if let Ok(_) = handle_user_page_fault(addr, error_code, frame.is_user()) {
 		return;
 	}
 
@@ -131,7 +88,15 @@ pub extern "C" fn handle_page_fault_impl(frame: InterruptFrame) {
 
 	if frame.is_user() {
 		_ = sys_attach_tty();
-		exit_with_signal(SigNum::SEGV);
+
+		let code = match error_code.contains(ErrorCode::Present) {
+			true => SigCode::SEGV_ACCERR,
+			false => SigCode::SEGV_MAPERR,
+		};
+		force_fault_signal(&frame, SigNum::SEGV, code, addr);
+
+		// another signal was handled first. the fault is raised again.
+		return;
 	}
 
 	// BUG
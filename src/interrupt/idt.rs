@@ -1,5 +1,6 @@
 use core::{arch::asm, mem::size_of};
 
+use crate::driver::apic::ipi::{RESCHEDULE_VECTOR, TLB_SHOOTDOWN_VECTOR};
 use crate::interrupt::exception::CpuException;
 use crate::sync::Locked;
 use crate::x86::{SystemDesc, DPL_USER, GDT};
@@ -65,9 +66,13 @@ extern "C" {
 	fn handle_serial();
 	fn handle_ide_ch0();
 	fn handle_ide_ch1();
+	fn handle_reschedule();
+	fn handle_tlb_shootdown();
 	fn handle_syscall();
 	fn handle_divide_error();
 	fn handle_invalid_opcode();
+	fn handle_debug();
+	fn handle_breakpoint();
 	fn handle_general_protection();
 	fn handle_control_protection();
 	fn handle_not_present();
@@ -80,6 +85,8 @@ extern "C" {
 pub fn init() {
 	let de = SystemDesc::new_interrupt(handle_divide_error as usize, GDT::KERNEL_CODE, DPL_USER);
 	let ud = SystemDesc::new_interrupt(handle_invalid_opcode as usize, GDT::KERNEL_CODE, DPL_USER);
+	let db = SystemDesc::new_interrupt(handle_debug as usize, GDT::KERNEL_CODE, DPL_USER);
+	let bp = SystemDesc::new_interrupt(handle_breakpoint as usize, GDT::KERNEL_CODE, DPL_USER);
 	let gp = SystemDesc::new_interrupt(
 		handle_general_protection as usize,
 		GDT::KERNEL_CODE,
@@ -100,11 +107,17 @@ pub fn init() {
 	let serial_com1 = SystemDesc::new_interrupt(handle_serial as usize, GDT::KERNEL_CODE, DPL_USER);
 	let ide_ch0 = SystemDesc::new_interrupt(handle_ide_ch0 as usize, GDT::KERNEL_CODE, DPL_USER);
 	let ide_ch1 = SystemDesc::new_interrupt(handle_ide_ch1 as usize, GDT::KERNEL_CODE, DPL_USER);
+	let reschedule =
+		SystemDesc::new_interrupt(handle_reschedule as usize, GDT::KERNEL_CODE, DPL_USER);
+	let tlb_shootdown =
+		SystemDesc::new_interrupt(handle_tlb_shootdown as usize, GDT::KERNEL_CODE, DPL_USER);
 	let syscall = SystemDesc::new_trap(handle_syscall as usize, GDT::KERNEL_CODE, DPL_USER);
 
 	let mut idt = IDT.lock();
 	idt.write_exception(CpuException::DE, de);
 	idt.write_exception(CpuException::UD, ud);
+	idt.write_exception(CpuException::DB, db);
+	idt.write_exception(CpuException::BP, bp);
 	idt.write_exception(CpuException::TS, ts);
 	idt.write_exception(CpuException::NP, np);
 	idt.write_exception(CpuException::SS, ss);
@@ -117,6 +130,8 @@ pub fn init() {
 	idt.write_interrupt(0x23, serial_com1);
 	idt.write_interrupt(0x24, ide_ch0);
 	idt.write_interrupt(0x25, ide_ch1);
+	idt.write_interrupt(RESCHEDULE_VECTOR, reschedule);
+	idt.write_interrupt(TLB_SHOOTDOWN_VECTOR, tlb_shootdown);
 	idt.write_interrupt(0x80, syscall);
 
 	idt.load();
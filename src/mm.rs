@@ -2,5 +2,6 @@ pub mod alloc;
 pub mod constant;
 pub mod oom;
 pub mod page;
+pub mod swap;
 pub mod user;
 pub mod util;
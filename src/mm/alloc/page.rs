@@ -2,7 +2,9 @@ mod buddy_allocator;
 mod free_list;
 mod page_allocator;
 
-use crate::{mm::page::ptr_to_meta, trace_feature};
+use crate::mm::page::{index_to_meta, ptr_to_meta};
+use crate::mm::util::addr_to_pfn;
+use crate::trace_feature;
 
 use super::Zone;
 use core::{alloc::AllocError, ptr::NonNull};
@@ -33,6 +35,25 @@ pub fn free_pages(page: NonNull<u8>) {
 	}
 }
 
+/// take extra reference of allocated page at physical address `paddr`.
+///
+/// reference count is only changed or read under `PAGE_ALLOC` lock,
+/// so it is dropped by `free_pages` consistently.
+pub fn get_page(paddr: usize) {
+	let _page_alloc = PAGE_ALLOC.lock();
+
+	let mut meta = index_to_meta(addr_to_pfn(paddr));
+	unsafe { meta.as_mut().inc_inuse() };
+}
+
+/// number of references of allocated page at physical address `paddr`.
+pub fn page_count(paddr: usize) -> usize {
+	let _page_alloc = PAGE_ALLOC.lock();
+
+	let meta = index_to_meta(addr_to_pfn(paddr));
+	unsafe { meta.as_ref().inuse() }
+}
+
 pub fn get_available_pages() -> usize {
 	let page_alloc = PAGE_ALLOC.lock();
 
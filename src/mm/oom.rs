@@ -1,25 +1,42 @@
 use core::{alloc::AllocError, mem::MaybeUninit};
 
-use alloc::sync::Arc;
+use alloc::{
+	sync::{Arc, Weak},
+	vec::Vec,
+};
 
 use crate::{
+	boot::MEM_INFO,
 	fs::ext2,
-	pr_warn,
-	process::task::Task,
+	pr_err, pr_warn,
+	process::{
+		process_tree::PROCESS_TREE,
+		signal::{send_signal_to_process, sig_code::SigCode, sig_info::SigInfo, sig_num::SigNum},
+		task::{State, Task},
+	},
 	scheduler::{
+		context::yield_now,
 		schedule_last,
 		sleep::{sleep_and_yield, wake_up_deep_sleep, Sleep},
 	},
+	sync::Locked,
 	trace_feature,
 };
 
 use super::{
 	alloc::{cache, page::get_available_pages},
 	constant::OOM_WATER_MARK,
+	swap,
 };
 
+pub const OOM_SCORE_ADJ_MIN: isize = -1000;
+pub const OOM_SCORE_ADJ_MAX: isize = 1000;
+
 static mut OOM_HANDLER: MaybeUninit<Arc<Task>> = MaybeUninit::uninit();
 
+/// task killed by oom_handler. its memory is freed when the task is dropped.
+static OOM_VICTIM: Locked<Option<Weak<Task>>> = Locked::new(None);
+
 pub fn init() -> Result<(), AllocError> {
 	let task = Task::new_kernel(oom_handler as usize, 0)?;
 
@@ -34,6 +51,90 @@ pub fn wake_up_oom_handler() {
 	wake_up_deep_sleep(unsafe { OOM_HANDLER.assume_init_ref() });
 }
 
+pub fn total_pages() -> usize {
+	unsafe { MEM_INFO.end_pfn - MEM_INFO.normal_start_pfn }
+}
+
+/// how much memory will be freed by killing `task`, adjusted by `oom_score_adj`.
+///
+/// returns `None` if the task must not be killed. (kernel task, init, exited task
+/// or task with `OOM_SCORE_ADJ_MIN`)
+pub fn badness(task: &Arc<Task>) -> Option<usize> {
+	let ext = task.get_user_ext()?;
+	let adj = ext.get_oom_score_adj();
+
+	if task.get_pid().as_raw() == 1
+		|| adj == OOM_SCORE_ADJ_MIN
+		|| *task.lock_state() == State::Exited
+	{
+		return None;
+	}
+
+	let rss = ext.lock_memory().nr_resident_pages() as isize;
+	let points = rss + adj * (total_pages() / 1000) as isize;
+
+	// killable task has at least 1 point.
+	Some(points.max(1) as usize)
+}
+
+/// badness normalized to [0, 1000] for `/proc/<pid>/oom_score`.
+pub fn oom_score(task: &Arc<Task>) -> usize {
+	badness(task)
+		.map(|points| (points * 1000 / total_pages()).min(1000))
+		.unwrap_or(0)
+}
+
+fn victim_in_progress() -> bool {
+	OOM_VICTIM
+		.lock()
+		.as_ref()
+		.is_some_and(|victim| victim.strong_count() > 0)
+}
+
+/// send SIGKILL to the task with highest badness.
+/// returns `false` if there is no task to kill.
+fn kill_victim() -> bool {
+	let tasks = PROCESS_TREE
+		.lock()
+		.members()
+		.values()
+		.cloned()
+		.collect::<Vec<_>>();
+
+	let victim = tasks
+		.iter()
+		.filter_map(|task| badness(task).map(|points| (points, task)))
+		.max_by_key(|(points, _)| *points);
+
+	let (points, victim) = match victim {
+		Some(x) => x,
+		None => return false,
+	};
+
+	let ext = victim.get_user_ext().expect("victim must be user task");
+	pr_err!(
+		"oom: killed {:?} ({}): badness {}, resident {} pages, oom_score_adj {}",
+		victim.get_pid(),
+		core::str::from_utf8(&victim.lock_cmd()).unwrap_or_default(),
+		points,
+		ext.lock_memory().nr_resident_pages(),
+		ext.get_oom_score_adj(),
+	);
+
+	let info = SigInfo {
+		num: SigNum::KILL,
+		pid: 0,
+		uid: 0,
+		code: SigCode::SI_KERNEL,
+		value: 0,
+	};
+	let _ = send_signal_to_process(victim, &info);
+
+	*OOM_VICTIM.lock() = Some(Arc::downgrade(victim));
+
+	true
+}
+
 pub fn oom_handler(_: usize) {
 	loop {
 		sleep_and_yield(Sleep::Deep);
@@ -41,8 +142,26 @@ pub fn oom_handler(_: usize) {
 		trace_feature!("oom", "oom_handler wake up!");
 
 		while get_available_pages() < OOM_WATER_MARK {
+			let before = get_available_pages();
+
 			ext2::oom_handler();
 			cache::oom_handler();
+			swap::oom_handler();
+
+			if before < get_available_pages() {
+				continue;
+			}
+
+			// nothing was reclaimed. give victim a chance to exit.
+			if victim_in_progress() {
+				yield_now();
+				continue;
+			}
+
+			if !kill_victim() {
+				pr_err!("oom: out of memory and no killable task");
+				break;
+			}
 		}
 	}
 }
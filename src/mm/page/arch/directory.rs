@@ -1,14 +1,18 @@
 use super::{PageFlag, PT, PTE};
+use crate::config::NR_CPUS;
+use crate::driver::apic::ipi::shootdown_tlb;
 use crate::mm::alloc::page::{alloc_pages, free_pages};
 use crate::mm::alloc::virt::AddressSpace;
 use crate::mm::alloc::Zone;
 use crate::mm::{constant::*, util::*};
+use crate::smp::smp_id;
 use crate::sync::Locked;
 
 use core::alloc::AllocError;
 use core::arch::asm;
 use core::cell::UnsafeCell;
 use core::ptr::NonNull;
+use core::sync::atomic::{AtomicUsize, Ordering};
 
 extern "C" {
 	pub static mut GLOBAL_PD_VIRT: [PDE; 1024];
@@ -22,6 +26,14 @@ pub static KMAP_PT: Locked<[PT; NR_KMAP_PT]> = Locked::new([PT::new(); NR_KMAP_P
 
 pub static KERNEL_PD: PD = PD::uninit();
 
+#[allow(clippy::declare_interior_mutable_const)]
+const NO_PD: AtomicUsize = AtomicUsize::new(0);
+
+/// physical address of user page directory last loaded on each cpu.
+///
+/// kernel tasks keep it loaded, so the cpu may still have TLB entries of it.
+static LOADED_PD: [AtomicUsize; NR_CPUS] = [NO_PD; NR_CPUS];
+
 #[repr(transparent)]
 pub struct PD {
 	inner: UnsafeCell<NonNull<[PDE; 1024]>>,
@@ -131,9 +143,13 @@ impl PD {
 			unsafe { (phys_to_virt(pde.addr()) as *mut PT).as_mut().unwrap() }
 		};
 
+		let present = pt[pt_idx].flag().contains(PageFlag::Present);
 		pt[pt_idx] = PTE::new(paddr, flags);
 
-		invlpg(vaddr);
+		match present {
+			true => self.flush_user(vaddr),
+			false => invlpg(vaddr),
+		};
 
 		Ok(())
 	}
@@ -155,11 +171,11 @@ impl PD {
 		let pt = unsafe { (phys_to_virt(pde.addr()) as *mut PT).as_mut().unwrap() };
 
 		let mut new_flag = pt[pt_idx].flag();
-		new_flag.remove(PageFlag::Present);
+		new_flag.remove(PageFlag::Present | PageFlag::Swapped);
 
 		pt[pt_idx].set_flag(new_flag);
 
-		invlpg(vaddr);
+		self.flush_user(vaddr);
 	}
 
 	fn lookup_arbitary(&self, vaddr: usize) -> Option<usize> {
@@ -180,6 +196,76 @@ impl PD {
 		}
 	}
 
+	/// lookup page table flags of present user page.
+	pub fn lookup_flag(&self, vaddr: usize) -> Option<PageFlag> {
+		match AddressSpace::identify(vaddr) {
+			AddressSpace::User => (),
+			_ => return None,
+		};
+
+		let (pd_idx, pt_idx) = Self::addr_to_index(vaddr);
+
+		let pde = &self.inner()[pd_idx];
+
+		let flag = match pde.as_pt() {
+			Some(pt) => pt[pt_idx].flag(),
+			None => pde.flag(),
+		};
+
+		flag.contains(PageFlag::Present).then_some(flag)
+	}
+
+	/// replace mapping of user page with swap entry that refers `slot`.
+	pub fn map_swap(&mut self, vaddr: usize, slot: usize) -> Result<(), AllocError> {
+		self.map_user(vaddr, slot << PAGE_SHIFT, PageFlag::Swapped)
+	}
+
+	/// lookup swap slot of swapped out user page.
+	pub fn lookup_swap(&self, vaddr: usize) -> Option<usize> {
+		match AddressSpace::identify(vaddr) {
+			AddressSpace::User => (),
+			_ => return None,
+		};
+
+		let (pd_idx, pt_idx) = Self::addr_to_index(vaddr);
+
+		let pte = self.inner()[pd_idx].as_pt()?[pt_idx];
+		let flag = pte.flag();
+
+		(flag.contains(PageFlag::Swapped) && !flag.contains(PageFlag::Present))
+			.then(|| pte.addr() >> PAGE_SHIFT)
+	}
+
+	/// clear `flag` (accessed or dirty bit) of present user page.
+	/// returns whether the flag was set.
+	pub fn test_and_clear(&mut self, vaddr: usize, flag: PageFlag) -> bool {
+		match AddressSpace::identify(vaddr) {
+			AddressSpace::User => (),
+			_ => return false,
+		};
+
+		let (pd_idx, pt_idx) = Self::addr_to_index(vaddr);
+
+		let pde = &mut self.inner_mut()[pd_idx];
+		if pde.is_4m() {
+			return false;
+		}
+
+		let pt = unsafe { (phys_to_virt(pde.addr()) as *mut PT).as_mut().unwrap() };
+
+		let mut new_flag = pt[pt_idx].flag();
+		if !new_flag.contains(PageFlag::Present | flag) {
+			return false;
+		}
+
+		new_flag.remove(flag);
+		pt[pt_idx] = PTE::new(pt[pt_idx].addr(), new_flag);
+
+		self.flush_user(vaddr);
+
+		true
+	}
+
 	pub fn lookup(&self, vaddr: usize) -> Option<usize> {
 		match AddressSpace::identify(vaddr) {
 			AddressSpace::Kernel => Some(virt_to_phys(vaddr)),
@@ -203,8 +289,32 @@ impl PD {
 		(pd_idx, pt_idx)
 	}
 
+	fn phys_addr(&self) -> usize {
+		virt_to_phys(self.inner() as *const _ as usize)
+	}
+
+	/// invalidate TLB entry of user page `vaddr` on every cpu that may have it.
+	/// (threads sharing this page directory run on other cpus)
+	fn flush_user(&self, vaddr: usize) {
+		invlpg(vaddr);
+
+		let addr = self.phys_addr();
+		let cpus = LOADED_PD
+			.iter()
+			.enumerate()
+			.filter(|(_, loaded)| loaded.load(Ordering::SeqCst) == addr)
+			.fold(0, |mask, (cpu, _)| mask | 1 << cpu);
+
+		shootdown_tlb(cpus, vaddr);
+	}
+
 	pub fn pick_up(&self) {
-		let addr = virt_to_phys(self.inner() as *const _ as usize);
+		let addr = self.phys_addr();
+
+		// local apic is not ready yet when kernel page directory is loaded.
+		if !core::ptr::eq(self, &KERNEL_PD) {
+			LOADED_PD[smp_id()].store(addr, Ordering::SeqCst);
+		}
 
 		unsafe { asm!("mov cr3, {pd}", pd = in(reg) addr) };
 	}
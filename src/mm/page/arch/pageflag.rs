@@ -13,6 +13,9 @@ bitflags! {
 		const Dirty = 64;
 		const PAT = 128;
 		const Global = 256;
+		/// (available to software) not present page which is stored in swap area.
+		/// address bits of the entry hold swap slot instead of physical address.
+		const Swapped = 512;
 	}
 }
 
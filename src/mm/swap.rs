@@ -0,0 +1,306 @@
+//! swap out private user pages to block device, when memory is running out.
+//!
+//! swapped out page is recorded in page table as not present entry with
+//! `PageFlag::Swapped`, which holds index of slot in swap area.
+//! (see `Memory::handle_fault`)
+
+mod syscall;
+
+pub use syscall::{sys_swapoff, sys_swapon};
+
+use core::ptr::copy_nonoverlapping;
+
+use alloc::{sync::Arc, vec::Vec};
+
+use crate::{
+	driver::{
+		ide::{
+			block::{Block, BlockSize},
+			dma::hook::WriteBack,
+		},
+		partition::Partition,
+	},
+	fs::{devfs::partition::PartBorrow, vfs::VfsBlockEntry},
+	process::{
+		process_tree::PROCESS_TREE,
+		task::{Task, UserTaskExt, CURRENT},
+	},
+	scheduler::sleep::{sleep_and_yield_lock, wake_up, Sleep},
+	sync::Locked,
+	syscall::errno::Errno,
+};
+
+use super::{
+	alloc::{
+		page::get_available_pages,
+		virt::{kmap, kunmap},
+	},
+	constant::{OOM_WATER_MARK, PAGE_SIZE},
+	user::memory::swap_in,
+};
+
+static SWAP: Locked<Option<SwapArea>> = Locked::new(None);
+
+struct SwapArea {
+	dev: PartBorrow,
+	/// reference count of each slot. (0: free)
+	slots: Vec<u16>,
+	nr_free: usize,
+	/// where to start looking for free slot.
+	cursor: usize,
+	/// whether new pages can be swapped out. (false while swapoff)
+	active: bool,
+}
+
+impl SwapArea {
+	fn new(dev: PartBorrow) -> Result<Self, Errno> {
+		let block_size = BlockSize::from_bytes(PAGE_SIZE).expect("valid block size");
+		dev.init(block_size);
+
+		let nr_slots = dev.nr_blocks();
+		if nr_slots == 0 {
+			return Err(Errno::EINVAL);
+		}
+
+		let mut slots = Vec::new();
+		slots
+			.try_reserve_exact(nr_slots)
+			.map_err(|_| Errno::ENOMEM)?;
+		slots.resize(nr_slots, 0);
+
+		Ok(Self {
+			dev,
+			slots,
+			nr_free: nr_slots,
+			cursor: 0,
+			active: true,
+		})
+	}
+
+	fn alloc(&mut self) -> Option<usize> {
+		if !self.active || self.nr_free == 0 {
+			return None;
+		}
+
+		let len = self.slots.len();
+		let slot = (0..len)
+			.map(|i| (self.cursor + i) % len)
+			.find(|i| self.slots[*i] == 0)?;
+
+		self.slots[slot] = 1;
+		self.nr_free -= 1;
+		self.cursor = slot + 1;
+
+		Some(slot)
+	}
+
+	fn free(&mut self, slot: usize) {
+		self.slots[slot] -= 1;
+
+		if self.slots[slot] == 0 {
+			self.nr_free += 1;
+		}
+	}
+}
+
+/// add reference to `slot`. (swapped out page is shared by `fork`)
+pub fn dup_slot(slot: usize) {
+	if let Some(area) = SWAP.lock().as_mut() {
+		area.slots[slot] += 1;
+	}
+}
+
+/// drop reference to `slot`.
+pub fn free_slot(slot: usize) {
+	if let Some(area) = SWAP.lock().as_mut() {
+		area.free(slot);
+	}
+}
+
+fn alloc_slot() -> Option<usize> {
+	SWAP.lock().as_mut().and_then(|area| area.alloc())
+}
+
+fn device() -> Option<Arc<Partition>> {
+	SWAP.lock().as_ref().map(|area| Arc::clone(&area.dev))
+}
+
+/// read contents of `slot` into physical page `paddr`. this may sleep.
+pub fn read_slot(slot: usize, paddr: usize) -> Result<(), Errno> {
+	let dev = device().ok_or(Errno::EIO)?;
+	let bid = dev.validate_bid(slot).ok_or(Errno::EIO)?;
+
+	let block: Block<[u8]> = dev.load(bid)?.into();
+
+	let page = kmap(paddr).map_err(|_| Errno::ENOMEM)?;
+	unsafe { copy_nonoverlapping(block.as_slice_ref(PAGE_SIZE).as_ptr(), page.as_ptr(), PAGE_SIZE) };
+	kunmap(page.as_ptr() as usize);
+
+	Ok(())
+}
+
+struct SwapOutPage {
+	paddr: usize,
+	waiter: Arc<Task>,
+	done: Locked<bool>,
+}
+
+impl WriteBack for SwapOutPage {
+	fn as_phys_addr(&self) -> usize {
+		self.paddr
+	}
+
+	fn size(&self) -> usize {
+		PAGE_SIZE
+	}
+
+	fn cleanup(&self) {
+		*self.done.lock() = true;
+		wake_up(&self.waiter, Sleep::Deep);
+	}
+}
+
+/// write physical page `paddr` to `slot` and wait for completion.
+fn write_slot(slot: usize, paddr: usize) -> Result<(), Errno> {
+	let dev = device().ok_or(Errno::EIO)?;
+	let bid = dev.validate_bid(slot).ok_or(Errno::EIO)?;
+
+	let page = Arc::new(SwapOutPage {
+		paddr,
+		waiter: unsafe { CURRENT.get_ref() }.clone(),
+		done: Locked::new(false),
+	});
+
+	dev.write_back(bid, page.clone());
+
+	loop {
+		let done = page.done.lock();
+		if *done {
+			break;
+		}
+		sleep_and_yield_lock(Sleep::Deep, done);
+	}
+
+	Ok(())
+}
+
+fn user_tasks() -> Vec<Arc<Task>> {
+	PROCESS_TREE
+		.lock()
+		.members()
+		.values()
+		.filter(|task| task.get_user_ext().is_some())
+		.cloned()
+		.collect()
+}
+
+fn swap_out_page(ext: &UserTaskExt, vaddr: usize) -> Result<bool, Errno> {
+	let slot = alloc_slot().ok_or(Errno::ENOSPC)?;
+
+	let paddr = match ext.lock_memory().start_swap_out(vaddr) {
+		Some(paddr) => paddr,
+		None => {
+			free_slot(slot);
+			return Ok(false);
+		}
+	};
+
+	let result = write_slot(slot, paddr);
+
+	let swapped = ext
+		.lock_memory()
+		.finish_swap_out(vaddr, paddr, result.is_ok().then_some(slot));
+
+	if !swapped {
+		free_slot(slot);
+	}
+
+	result.map(|_| swapped)
+}
+
+/// swap out at most `nr_pages` pages of user tasks.
+/// returns number of pages swapped out.
+pub fn swap_out(nr_pages: usize) -> usize {
+	let mut count = 0;
+
+	for task in user_tasks() {
+		let ext = task.get_user_ext().expect("must be user task");
+		let candidates = ext.lock_memory().swap_candidates();
+
+		for vaddr in candidates {
+			if count >= nr_pages {
+				return count;
+			}
+
+			match swap_out_page(ext, vaddr) {
+				Ok(true) => count += 1,
+				Ok(false) => (),
+				Err(_) => return count,
+			}
+		}
+	}
+
+	count
+}
+
+pub fn oom_handler() {
+	let shortage = OOM_WATER_MARK.saturating_sub(get_available_pages());
+
+	swap_out(shortage);
+}
+
+pub fn swapon(dev: PartBorrow) -> Result<(), Errno> {
+	let mut swap = SWAP.lock();
+	if swap.is_some() {
+		return Err(Errno::EBUSY);
+	}
+
+	*swap = Some(SwapArea::new(dev)?);
+
+	Ok(())
+}
+
+/// read every swapped out page back into memory and release swap area.
+pub fn swapoff(entry: &VfsBlockEntry) -> Result<(), Errno> {
+	{
+		let mut swap = SWAP.lock();
+		let area = swap
+			.as_mut()
+			.filter(|area| entry.is_device_of(&area.dev))
+			.ok_or(Errno::EINVAL)?;
+
+		if !area.active {
+			return Err(Errno::EBUSY);
+		}
+		area.active = false;
+	}
+
+	let result = swap_in_all();
+
+	let mut swap = SWAP.lock();
+	let area = swap.as_mut().expect("swap area is not released during swapoff");
+
+	match result {
+		Ok(_) if area.nr_free == area.slots.len() => {
+			*swap = None;
+			Ok(())
+		}
+		x => {
+			area.active = true;
+			x.and(Err(Errno::EBUSY))
+		}
+	}
+}
+
+fn swap_in_all() -> Result<(), Errno> {
+	for task in user_tasks() {
+		let ext = task.get_user_ext().expect("must be user task");
+		let pages = ext.lock_memory().swapped_pages();
+
+		for vaddr in pages {
+			swap_in(ext, vaddr)?;
+		}
+	}
+
+	Ok(())
+}
@@ -0,0 +1,40 @@
+use crate::fs::path::Path;
+use crate::fs::vfs::lookup_entry_nofollow;
+use crate::mm::user::verify::verify_path;
+use crate::process::task::CURRENT;
+use crate::syscall::errno::Errno;
+
+use super::{swapoff, swapon};
+
+pub fn sys_swapon(path: usize, _flags: usize) -> Result<usize, Errno> {
+	let current = unsafe { CURRENT.get_mut() };
+
+	if !current.is_privileged() {
+		return Err(Errno::EPERM);
+	}
+
+	let path = Path::new(verify_path(path, current)?);
+	let entry = lookup_entry_nofollow(&path, current).and_then(|x| x.downcast_block())?;
+
+	// mounted partition is borrowed by its file system until unmounted.
+	let block_device = entry.get_device().map_err(|_| Errno::EBUSY)?;
+
+	swapon(block_device)?;
+
+	Ok(0)
+}
+
+pub fn sys_swapoff(path: usize) -> Result<usize, Errno> {
+	let current = unsafe { CURRENT.get_mut() };
+
+	if !current.is_privileged() {
+		return Err(Errno::EPERM);
+	}
+
+	let path = Path::new(verify_path(path, current)?);
+	let entry = lookup_entry_nofollow(&path, current).and_then(|x| x.downcast_block())?;
+
+	swapoff(&entry)?;
+
+	Ok(0)
+}
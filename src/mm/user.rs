@@ -1,3 +1,4 @@
+pub mod aslr;
 mod auxv;
 pub mod brk;
 pub mod copy;
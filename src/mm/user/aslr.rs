@@ -0,0 +1,51 @@
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::config::USTACK_BASE;
+use crate::mm::constant::PAGE_SIZE;
+use crate::util::random::get_random_below;
+
+use super::vma::VALID_USER_AREA;
+
+const PIE_BASE: usize = 0x0804_8000;
+const INTERP_BASE: usize = 0x0040_0000;
+
+const STACK_RND_PAGES: usize = 1 << 11; // 8MB
+const MMAP_RND_PAGES: usize = 1 << 16; // 256MB
+const PIE_RND_PAGES: usize = 1 << 14; // 64MB
+const INTERP_RND_PAGES: usize = 1 << 14; // 64MB
+
+/// `/proc/sys/kernel/randomize_va_space`.
+///
+/// - 0: no randomization.
+/// - 1, 2: randomize stack, mmap base, PIE executable and interpreter.
+pub static RANDOMIZE_VA_SPACE: AtomicUsize = AtomicUsize::new(2);
+
+pub const RANDOMIZE_VA_SPACE_MAX: usize = 2;
+
+/// random page aligned offset less than `max_pages` pages.
+fn random_offset(max_pages: usize) -> usize {
+	match RANDOMIZE_VA_SPACE.load(Ordering::Relaxed) {
+		0 => 0,
+		_ => get_random_below(max_pages) * PAGE_SIZE,
+	}
+}
+
+/// top of user stack. (exclusive)
+pub fn stack_base() -> usize {
+	USTACK_BASE - random_offset(STACK_RND_PAGES)
+}
+
+/// address where search for free area of `mmap` starts.
+pub fn mmap_base() -> usize {
+	VALID_USER_AREA.start + random_offset(MMAP_RND_PAGES)
+}
+
+/// load address of position independent executable.
+pub fn pie_base() -> usize {
+	PIE_BASE + random_offset(PIE_RND_PAGES)
+}
+
+/// load address of position independent interpreter.
+pub fn interp_base() -> usize {
+	INTERP_BASE + random_offset(INTERP_RND_PAGES)
+}
@@ -9,10 +9,17 @@ pub enum AuxEntryType {
 	Pagesz = 6,
 	Base = 7,
 	Entry = 9,
+	Uid = 11,
+	Euid = 12,
+	Gid = 13,
+	Egid = 14,
+	/// set-user-ID or set-group-ID program, loader must not trust environment.
+	Secure = 23,
 	Execfn = 31,
 }
 
 #[repr(C)]
+#[derive(Clone, Copy)]
 pub struct AuxEntry {
 	kind: AuxEntryType,
 	value: usize,
@@ -30,7 +37,12 @@ impl AuxEntry {
 		Self { kind, value }
 	}
 
+	/// in the order of pushes to stack, which grows down.
 	pub fn serialize(&self) -> [usize; 2] {
 		[self.value, self.kind as usize]
 	}
+
+	pub fn to_pair(self) -> [usize; 2] {
+		[self.kind as usize, self.value]
+	}
 }
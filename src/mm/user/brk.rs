@@ -1,16 +1,35 @@
-use crate::{process::task::CURRENT, syscall::errno::Errno};
+use crate::{
+	process::{rlimit::Resource, task::CURRENT},
+	syscall::errno::Errno,
+};
 
+/// heap cannot be larger than `RLIMIT_DATA`, nor address space than `RLIMIT_AS`.
 pub fn sys_brk(new_end: usize) -> Result<usize, Errno> {
 	let current = unsafe { CURRENT.get_ref() };
+	let user_ext = current.get_user_ext().expect("must be user process");
 
-	let mut memory = current
-		.get_user_ext()
-		.expect("must be user process")
-		.lock_memory();
+	let (data_limit, as_limit) = {
+		let rlimits = user_ext.lock_rlimits();
+
+		(
+			rlimits.get(Resource::Data).cur,
+			rlimits.get(Resource::As).cur,
+		)
+	};
+
+	let mut memory = user_ext.lock_memory();
 
 	if new_end == 0 {
 		return Ok(memory.get_data_end());
 	}
 
+	let old_end = memory.get_data_end();
+	if new_end > old_end
+		&& (new_end - memory.get_data_start() > data_limit
+			|| !memory.may_expand(new_end - old_end, as_limit))
+	{
+		return Err(Errno::ENOMEM);
+	}
+
 	memory.brk(new_end)
 }
@@ -1,5 +1,11 @@
+mod advise;
 mod chunk;
+mod fault;
+mod growsdown;
 mod mapped_file;
+mod reclaim;
+mod remap;
+mod writeback;
 
 use alloc::collections::BTreeMap;
 use alloc::vec::Vec;
@@ -9,27 +15,32 @@ use core::alloc::AllocError;
 use core::cmp::min;
 use core::mem::size_of;
 use core::ptr::NonNull;
-use core::slice::{from_raw_parts, from_raw_parts_mut};
+use core::slice::from_raw_parts;
 
-use crate::config::{TRAMPOLINE_BASE, USTACK_BASE, USTACK_PAGES};
+use crate::config::{TRAMPOLINE_BASE, USTACK_BASE};
 use crate::elf::{Elf, ProgramHdr};
 use crate::fs::path::Path;
-use crate::fs::vfs::{Entry, VfsHandle, Whence};
-use crate::mm::alloc::page::free_pages;
+use crate::fs::vfs::{Entry, VfsHandle};
+use crate::mm::alloc::page::{free_pages, get_page, page_count};
 use crate::mm::alloc::virt::{kmap, kunmap};
 use crate::mm::alloc::Zone;
-use crate::mm::page::{get_zero_page_phys, index_to_meta, PageFlag, PD};
+use crate::mm::page::{get_zero_page_phys, PageFlag, PD};
+use crate::mm::swap;
 use crate::mm::{constant::*, util::*};
+use crate::process::cred::Credentials;
 use crate::process::task::CURRENT;
 use crate::ptr::PageBox;
 use crate::syscall::errno::Errno;
 use crate::syscall::exec::read_user_binary;
 use crate::trace_feature;
 
+pub use self::fault::{access_forced, handle_fault, prefault, swap_in};
+pub use self::growsdown::expand_stack;
+pub use self::mapped_file::DirtyPages;
 use self::mapped_file::MappedFile;
 
+use super::aslr;
 use super::auxv::{AuxEntry, AuxEntryType};
-use super::copy::{copy_user_to_user_page, memset_to_user_page};
 use super::stack::UserStack;
 use super::string_vec::StringVec;
 use super::vma::{AreaFlag, UserAddressSpace};
@@ -37,10 +48,14 @@ use super::vma::{AreaFlag, UserAddressSpace};
 pub struct Memory {
 	stack_pointer: usize,
 	system_data_base: usize,
+	/// where heap of `brk` begins. (`RLIMIT_DATA`)
+	data_start: usize,
 	pub entry_point: usize,
 	vma: UserAddressSpace,
 	file_mapping: BTreeMap<usize, MappedFile>,
 	page_dir: PD,
+	/// auxiliary vector given to the program. (in push order, `AT_NULL` first)
+	auxv: Vec<AuxEntry>,
 }
 
 extern "C" {
@@ -55,7 +70,7 @@ impl Memory {
 		let elf = Elf::new(raw_bin.as_slice()).map_err(|_| Errno::ENOEXEC)?;
 
 		let interp_base = match elf.is_position_independent() {
-			true => 0x400000,
+			true => aslr::interp_base(),
 			false => 0,
 		};
 
@@ -66,22 +81,31 @@ impl Memory {
 		Ok(interp_base)
 	}
 
-	pub fn from_elf(elf: Elf<'_>, argv: StringVec, envp: StringVec) -> Result<Self, Errno> {
+	pub fn from_elf(
+		elf: Elf<'_>,
+		argv: StringVec,
+		envp: StringVec,
+		cred: &Credentials,
+	) -> Result<Self, Errno> {
 		let mut memory = Self {
 			entry_point: 0,
 			stack_pointer: USTACK_BASE,
 			system_data_base: TRAMPOLINE_BASE,
+			data_start: TRAMPOLINE_BASE,
 			vma: UserAddressSpace::new(),
 			file_mapping: BTreeMap::new(),
 			page_dir: PD::new().map_err(|_| Errno::ENOMEM)?,
+			auxv: Vec::new(),
 		};
 
 		let len = __trampoline_end as usize - __trampoline_start as usize;
 		let trampoline = unsafe { from_raw_parts(__trampoline_start as *const u8, len) };
 		memory.push_data(trampoline)?;
 
+		memory.vma.set_mmap_base(aslr::mmap_base());
+
 		let executable_base = match elf.is_position_independent() {
-			true => 0x0804_8000,
+			true => aslr::pie_base(),
 			false => 0,
 		};
 
@@ -90,25 +114,39 @@ impl Memory {
 		memory.load_sections(executable_base, &elf)?;
 
 		let mut stack = UserStack::new();
+		let mut auxv = Vec::new();
 
-		stack.push_aux_entry(AuxEntry::new_null())?;
+		auxv.push(AuxEntry::new_null());
 
 		if let Some(interp) = elf.get_interpreter() {
 			let interp_base = memory.load_interp(interp)?;
-			stack.push_aux_entry(AuxEntry::new(AuxEntryType::Base, interp_base))?;
-			stack.push_aux_entry(AuxEntry::new(
+			auxv.push(AuxEntry::new(AuxEntryType::Base, interp_base));
+			auxv.push(AuxEntry::new(
 				AuxEntryType::Phdr,
 				executable_base + elf.program_hdrs[0].p_vaddr,
-			))?;
-			stack.push_aux_entry(AuxEntry::new(AuxEntryType::Phent, size_of::<ProgramHdr>()))?;
-			stack.push_aux_entry(AuxEntry::new(AuxEntryType::Phnum, elf.program_hdrs.len()))?;
-			stack.push_aux_entry(AuxEntry::new(
+			));
+			auxv.push(AuxEntry::new(AuxEntryType::Phent, size_of::<ProgramHdr>()));
+			auxv.push(AuxEntry::new(AuxEntryType::Phnum, elf.program_hdrs.len()));
+			auxv.push(AuxEntry::new(
 				AuxEntryType::Entry,
 				executable_base + elf.get_entry_point(),
-			))?;
+			));
 		}
 
-		stack.push_aux_entry(AuxEntry::new(AuxEntryType::Pagesz, PAGE_SIZE))?;
+		auxv.push(AuxEntry::new(AuxEntryType::Pagesz, PAGE_SIZE));
+		auxv.push(AuxEntry::new(AuxEntryType::Uid, cred.uid));
+		auxv.push(AuxEntry::new(AuxEntryType::Euid, cred.euid));
+		auxv.push(AuxEntry::new(AuxEntryType::Gid, cred.gid));
+		auxv.push(AuxEntry::new(AuxEntryType::Egid, cred.egid));
+		auxv.push(AuxEntry::new(
+			AuxEntryType::Secure,
+			cred.is_secure_exec() as usize,
+		));
+
+		for aux in auxv.iter() {
+			stack.push_aux_entry(aux)?;
+		}
+		memory.auxv = auxv;
 
 		memory.push_string_array(envp, &mut stack)?;
 
@@ -117,7 +155,8 @@ impl Memory {
 
 		stack.push(argc)?;
 
-		memory.reserve_stack(stack)?;
+		memory.reserve_stack(stack, aslr::stack_base())?;
+		memory.data_start = memory.system_data_base;
 
 		Ok(memory)
 	}
@@ -144,6 +183,18 @@ impl Memory {
 		return true;
 	}
 
+	/// page at `vaddr` has contents somewhere, though it may not be present.
+	/// (swapped out or backed by file) untouched anonymous page reads as zero.
+	pub fn has_contents(&self, vaddr: usize) -> bool {
+		let Some(area) = self.vma.find_area(vaddr) else {
+			return false;
+		};
+
+		self.page_dir.lookup(vaddr).is_some()
+			|| self.page_dir.lookup_swap(vaddr).is_some()
+			|| self.file_mapping.contains_key(&area.start)
+	}
+
 	pub fn brk(&mut self, new_data_end: usize) -> Result<usize, Errno> {
 		let begin = next_align(self.system_data_base, PAGE_SIZE);
 		let end = next_align(new_data_end, PAGE_SIZE);
@@ -174,6 +225,20 @@ impl Memory {
 		self.system_data_base
 	}
 
+	pub fn get_data_start(&self) -> usize {
+		self.data_start
+	}
+
+	/// bytes of address space mapped.
+	pub fn total_vm(&self) -> usize {
+		self.vma.get_areas().iter().map(|a| a.end - a.start).sum()
+	}
+
+	/// `bytes` more can be mapped under `limit` of address space. (`RLIMIT_AS`)
+	pub fn may_expand(&self, bytes: usize, limit: usize) -> bool {
+		self.total_vm().saturating_add(bytes) <= limit
+	}
+
 	pub fn mmap_shared(
 		&mut self,
 		start: usize,
@@ -182,22 +247,18 @@ impl Memory {
 		offset: isize,
 		flags: AreaFlag,
 	) -> Result<usize, Errno> {
-		file.lseek(offset, Whence::Begin)?;
+		let mapping_len = min(len, Self::file_len_from(&file, offset));
+		let count = size_to_pages(mapping_len);
+		let mapped_file = MappedFile::new(file, offset, mapping_len);
 
-		let file_end_from_offset = file
-			.as_entry()
-			.and_then(|ent| ent.statx().ok())
-			.and_then(|stat| {
-				offset
-					.try_into()
-					.ok()
-					.and_then(|offset| stat.size.checked_sub(offset))
-			})
-			.unwrap_or_default();
+		let mut pages = Vec::new();
+		for i in 0..count {
+			let page = PageBox::new(Zone::High)?;
+			mapped_file.read_page(i, page.as_phys_addr())?;
+			pages.push(page);
+		}
 
-		let mapping_len = min(len, file_end_from_offset as usize);
-		let count = size_to_pages(mapping_len);
-		let (start, pages) = self.alloc_memory(start, count, flags)?;
+		let start = self.map_pages(start, &pages, flags)?;
 
 		trace_feature!(
 			"mmap_shared",
@@ -206,51 +267,74 @@ impl Memory {
 			mapping_len
 		);
 
-		let buf = unsafe { from_raw_parts_mut(start as *mut u8, mapping_len) };
-		let mut cursor = 0;
-		while cursor < mapping_len {
-			let buf = &mut buf[cursor..];
-			let x = match file.read(buf) {
-				Ok(x) => x,
-				Err(e) => {
-					self.mmap_cleanup(start, count);
-					return Err(e);
-				}
-			};
-			cursor += x;
-		}
+		self.file_mapping.insert(start, mapped_file);
+		pages.into_iter().for_each(|p| p.forget());
+		Ok(start)
+	}
+
+	/// map file privately.
+	///
+	/// nothing is read here. each page is filled with file contents on first access,
+	/// and modification of page is not written back to file.
+	pub fn mmap_private_file(
+		&mut self,
+		start: usize,
+		len: usize,
+		file: VfsHandle,
+		offset: isize,
+		flags: AreaFlag,
+	) -> Result<usize, Errno> {
+		let mapping_len = min(len, Self::file_len_from(&file, offset));
+		let start = self.alloc_area(start, size_to_pages(len), flags)?;
+
+		trace_feature!(
+			"mmap_private",
+			"private file start: {:x}, len: {}",
+			start,
+			mapping_len
+		);
 
 		self.file_mapping
 			.insert(start, MappedFile::new(file, offset, mapping_len));
-		pages.into_iter().for_each(|p| p.forget());
+
 		Ok(start)
 	}
 
-	fn alloc_memory(
+	/// remaining bytes of `file` from `offset`.
+	fn file_len_from(file: &VfsHandle, offset: isize) -> usize {
+		file.as_entry()
+			.and_then(|ent| ent.statx().ok())
+			.and_then(|stat| {
+				offset
+					.try_into()
+					.ok()
+					.and_then(|offset| stat.size.checked_sub(offset))
+			})
+			.unwrap_or_default() as usize
+	}
+
+	fn map_pages(
 		&mut self,
 		start: usize,
-		count: usize,
+		pages: &[PageBox],
 		flags: AreaFlag,
-	) -> Result<(usize, Vec<PageBox>), Errno> {
-		let mut pages = Vec::new();
-
-		for _ in 0..count {
-			pages.push(PageBox::new(Zone::High)?);
-		}
+	) -> Result<usize, Errno> {
+		let start = self.alloc_area(start, pages.len(), flags)?;
 
-		let start = self.alloc_area(start, count, flags)?;
+		for (i, page) in pages.iter().enumerate() {
+			let vaddr = start + i * PAGE_SIZE;
 
-		for (i, page) in (0..count).zip(pages.iter()) {
-			if let Err(_) =
-				self.page_dir
-					.map_user(start + i * PAGE_SIZE, page.as_phys_addr(), flags.into())
+			if self
+				.page_dir
+				.map_user(vaddr, page.as_phys_addr(), flags.into())
+				.is_err()
 			{
 				self.mmap_cleanup(start, i);
 				return Err(Errno::ENOMEM);
 			}
 		}
 
-		Ok((start, pages))
+		Ok(start)
 	}
 
 	pub fn mmap_private(
@@ -265,7 +349,7 @@ impl Memory {
 			if let Err(_) = self.page_dir.map_user(
 				start + i * PAGE_SIZE,
 				get_zero_page_phys(),
-				PageFlag::Present | PageFlag::User,
+				Self::page_flags(flags, get_zero_page_phys()),
 			) {
 				self.mmap_cleanup(start, i);
 				return Err(Errno::ENOMEM);
@@ -297,67 +381,189 @@ impl Memory {
 		}
 	}
 
-	pub fn munmap(&mut self, start: usize, pages: usize) -> Result<(), Errno> {
-		let end = start.checked_add(pages * PAGE_SIZE).ok_or(Errno::EINVAL)?;
+	/// unmap [start, start + pages * PAGE_SIZE). areas partially in range are split.
+	///
+	/// returns modified pages of shared file mapping, which have to be written back
+	/// by caller after memory lock is released.
+	pub fn munmap(&mut self, start: usize, pages: usize) -> Result<Vec<DirtyPages>, Errno> {
+		let len = pages.checked_mul(PAGE_SIZE).ok_or(Errno::EINVAL)?;
+		let end = start.checked_add(len).ok_or(Errno::EINVAL)?;
+
+		self.split_area(start);
+		self.split_area(end);
+
+		let areas: Vec<(usize, usize, AreaFlag)> = self
+			.vma
+			.areas_in_range_mut(start, end)
+			.map(|area| (area.start, area.end, area.flags))
+			.collect();
+
+		let mut dirty = Vec::new();
+		for (area_start, area_end, flags) in areas {
+			if flags.contains(AreaFlag::Shared) {
+				dirty.extend(self.take_dirty_pages(area_start, area_start, area_end));
+			}
+
+			self.file_mapping.remove(&area_start);
 
-		let area = self.vma.find_area(start).ok_or(Errno::EINVAL)?;
-		if area.start != start || area.end != end {
-			return Err(Errno::EINVAL);
+			self.vma.deallocate_area(area_start).unwrap();
+
+			for vaddr in (area_start..area_end).step_by(PAGE_SIZE) {
+				Self::free_page_if_allocated(self.get_pd(), vaddr);
+				self.page_dir.unmap_user(vaddr);
+			}
 		}
 
-		if let Some(mapped_file) = self.file_mapping.remove(&start) {
-			mapped_file.sync_with_buf(start as *const u8)?;
+		Ok(dirty)
+	}
+
+	pub fn mprotect(&mut self, start: usize, pages: usize, prot: AreaFlag) -> Result<(), Errno> {
+		let len = pages.checked_mul(PAGE_SIZE).ok_or(Errno::ENOMEM)?;
+		let end = start.checked_add(len).ok_or(Errno::ENOMEM)?;
+
+		if !self.query_flags_range(start, len, AreaFlag::empty()) {
+			return Err(Errno::ENOMEM);
 		}
 
-		self.vma.deallocate_area(start).unwrap();
+		// shared mapping of file not opened for writing cannot be written back.
+		if prot.contains(AreaFlag::Writable) {
+			let file_mapping = &self.file_mapping;
+			let denied = self.vma.areas_in_range_mut(start, end).any(|area| {
+				area.flags.contains(AreaFlag::Shared)
+					&& file_mapping
+						.get(&area.start)
+						.is_some_and(|file| !file.is_writable())
+			});
+
+			if denied {
+				return Err(Errno::EACCES);
+			}
+		}
 
-		for vaddr in (0..pages).map(|x| start + x * PAGE_SIZE) {
-			Self::free_page_if_allocated(self.get_pd(), vaddr);
-			self.page_dir.unmap_user(vaddr);
+		self.split_area(start);
+		self.split_area(end);
+
+		for area in self.vma.areas_in_range_mut(start, end) {
+			area.flags = prot | (area.flags & AreaFlag::KEEP_ON_PROTECT);
+
+			for vaddr in area.iter_pages() {
+				if let Some(paddr) = self.page_dir.lookup(vaddr) {
+					// keep dirty bit for write back of shared mapping.
+					let dirty = self
+						.page_dir
+						.lookup_flag(vaddr)
+						.map_or(PageFlag::empty(), |flag| flag & PageFlag::Dirty);
+
+					let flags = Self::page_flags(area.flags, paddr) | dirty;
+					self.page_dir.map_user(vaddr, paddr, flags)?;
+				}
+			}
 		}
 
+		let boundaries: Vec<usize> = self
+			.vma
+			.areas_in_range_mut(start, end)
+			.map(|area| area.end)
+			.collect();
+
+		self.merge_area(start);
+		boundaries
+			.into_iter()
+			.for_each(|addr| self.merge_area(addr));
+
 		Ok(())
 	}
 
-	pub fn clone(&self) -> Result<Self, AllocError> {
-		fn get_copied_page(src_paddr: usize) -> Result<usize, AllocError> {
-			let page = PageBox::new(Zone::High)?;
+	/// split area (and its file mapping) at `addr`.
+	fn split_area(&mut self, addr: usize) {
+		let area_start = match self.vma.find_area(addr) {
+			Some(area) if area.start != addr => area.start,
+			_ => return,
+		};
 
-			unsafe { copy_user_to_user_page(src_paddr, page.as_phys_addr())? };
+		self.vma.split_area(addr);
 
-			let paddr = page.as_phys_addr();
+		if let Some(mapped_file) = self.file_mapping.get_mut(&area_start) {
+			if let Some(tail) = mapped_file.split_off(addr - area_start) {
+				self.file_mapping.insert(addr, tail);
+			}
+		}
+	}
 
-			page.forget();
+	/// merge area that starts at `addr` into its left neighbor.
+	/// areas backed by file are never merged.
+	fn merge_area(&mut self, addr: usize) {
+		let left_start = match addr.checked_sub(1).and_then(|x| self.vma.find_area(x)) {
+			Some(area) => area.start,
+			None => return,
+		};
 
-			Ok(paddr)
+		if self.file_mapping.contains_key(&left_start) || self.file_mapping.contains_key(&addr) {
+			return;
 		}
 
+		self.vma.merge_area(addr);
+	}
+
+	/// page table flags for page `paddr` in area with `flags`.
+	///
+	/// private page which is still shared (zero page or copy-on-write page)
+	/// must be write protected even if area is writable.
+	fn page_flags(flags: AreaFlag, paddr: usize) -> PageFlag {
+		let page_flags: PageFlag = flags.into();
+
+		if paddr == get_zero_page_phys() {
+			return page_flags.difference(PageFlag::Write);
+		}
+
+		if flags.contains(AreaFlag::Shared) || !page_flags.contains(PageFlag::Write) {
+			return page_flags;
+		}
+
+		let shared = page_count(paddr) > 1;
+
+		match shared {
+			true => page_flags.difference(PageFlag::Write),
+			false => page_flags,
+		}
+	}
+
+	/// duplicate address space for `fork`.
+	///
+	/// private pages are not copied here. both parent and child map them without
+	/// write permission and the first write fault copies the page. (see `handle_user_page_fault`)
+	pub fn clone(&mut self) -> Result<Self, AllocError> {
 		let vma = self.vma.clone();
 		let mut page_dir = PD::new()?;
 
 		for area in vma.get_areas() {
-			for vaddr in (area.start..area.end).step_by(PAGE_SIZE) {
-				let src_paddr = self.page_dir.lookup(vaddr).unwrap();
-				let zero_paddr = get_zero_page_phys();
+			for vaddr in area.iter_pages() {
+				if let Some(slot) = self.page_dir.lookup_swap(vaddr) {
+					page_dir.map_swap(vaddr, slot)?;
+					swap::dup_slot(slot);
+					continue;
+				}
 
-				let paddr = if src_paddr == zero_paddr {
-					zero_paddr
-				} else if area.flags.contains(AreaFlag::Shared) {
-					let index = src_paddr / PAGE_SIZE;
+				let src_paddr = match self.page_dir.lookup(vaddr) {
+					Some(x) => x,
+					None => continue,
+				};
 
-					let mut meta = index_to_meta(index);
-					unsafe { meta.as_mut().inc_inuse() };
+				if src_paddr != get_zero_page_phys() {
+					get_page(src_paddr);
+				}
 
-					trace_feature!("mmap_shared", "clone: inuse: {}", unsafe {
-						meta.as_mut().inuse()
-					});
+				// protection of the area is kept. private page is now shared,
+				// so it is write protected in both of them.
+				let flags = Self::page_flags(area.flags, src_paddr);
 
-					src_paddr
+				if area.flags.contains(AreaFlag::Shared) {
+					trace_feature!("mmap_shared", "clone: inuse: {}", page_count(src_paddr));
 				} else {
-					get_copied_page(src_paddr)?
-				};
+					self.page_dir.map_user(vaddr, src_paddr, flags)?;
+				}
 
-				page_dir.map_user(vaddr, paddr, PageFlag::USER_RDWR)?;
+				page_dir.map_user(vaddr, src_paddr, flags)?;
 			}
 		}
 
@@ -365,9 +571,11 @@ impl Memory {
 			entry_point: self.entry_point,
 			stack_pointer: self.stack_pointer,
 			system_data_base: self.system_data_base,
+			data_start: self.data_start,
 			vma,
 			page_dir,
 			file_mapping: self.file_mapping.clone(),
+			auxv: self.auxv.clone(),
 		})
 	}
 
@@ -384,6 +592,15 @@ impl Memory {
 		&self.vma
 	}
 
+	/// auxiliary vector as laid out on user stack. (type and value pairs)
+	pub fn get_auxv(&self) -> Vec<usize> {
+		self.auxv
+			.iter()
+			.rev()
+			.flat_map(|aux| aux.to_pair())
+			.collect()
+	}
+
 	fn push_string_array(&mut self, strv: StringVec, stack: &mut UserStack) -> Result<(), Errno> {
 		let copy_base = next_align(self.system_data_base, PAGE_SIZE);
 
@@ -503,28 +720,23 @@ impl Memory {
 		Ok(())
 	}
 
-	fn reserve_stack(&mut self, mut stack: UserStack) -> Result<(), AllocError> {
-		self.stack_pointer = stack.get_stack_pointer(USTACK_BASE);
+	/// map pages of `stack` just below `base`.
+	///
+	/// stack area grows down on fault below it. (see `expand_stack`)
+	fn reserve_stack(&mut self, mut stack: UserStack, base: usize) -> Result<(), AllocError> {
+		self.stack_pointer = stack.get_stack_pointer(base);
 
-		let stack_top = USTACK_BASE - (USTACK_PAGES * PAGE_SIZE);
+		let pages = stack.nr_pages();
+		let stack_top = base - pages * PAGE_SIZE;
 
 		self.vma.allocate_fixed_area(
 			stack_top,
-			USTACK_PAGES,
-			AreaFlag::Readable | AreaFlag::Writable,
+			pages,
+			AreaFlag::Readable | AreaFlag::Writable | AreaFlag::GrowsDown | AreaFlag::StackGuard,
 		)?;
 
-		for user_vaddr in (1..=USTACK_PAGES).map(|x| USTACK_BASE - x * PAGE_SIZE) {
-			let user_page = match stack.pop_page() {
-				Some(page) => page,
-				None => {
-					let page = PageBox::new(Zone::High)?;
-
-					unsafe { memset_to_user_page(page.as_phys_addr(), 0)? };
-
-					page
-				}
-			};
+		for user_vaddr in (1..=pages).map(|x| base - x * PAGE_SIZE) {
+			let user_page = stack.pop_page().expect("stack page must exist");
 
 			self.page_dir
 				.map_user(user_vaddr, user_page.as_phys_addr(), PageFlag::USER_RDWR)?;
@@ -540,6 +752,11 @@ impl Memory {
 	}
 
 	fn free_page_if_allocated(pd: &PD, vaddr: usize) -> Option<()> {
+		if let Some(slot) = pd.lookup_swap(vaddr) {
+			swap::free_slot(slot);
+			return Some(());
+		}
+
 		let paddr = pd.lookup(vaddr)?;
 
 		if get_zero_page_phys() != paddr {
@@ -552,12 +769,22 @@ impl Memory {
 
 impl Drop for Memory {
 	fn drop(&mut self) {
+		let shared_areas = self
+			.vma
+			.get_areas()
+			.iter()
+			.filter(|area| area.flags.contains(AreaFlag::Shared))
+			.map(|area| (area.start, area.end))
+			.collect::<Vec<_>>();
+
+		for (start, end) in shared_areas {
+			if let Some(dirty) = self.take_dirty_pages(start, start, end) {
+				let _ = dirty.write_back(false);
+			}
+		}
+
 		for area in self.vma.get_areas() {
 			for vaddr in area.iter_pages() {
-				if let Some(mapped_file) = self.file_mapping.remove(&vaddr) {
-					let _ = mapped_file.sync_with_buf(vaddr as *const u8);
-				}
-
 				Self::free_page_if_allocated(&self.page_dir, vaddr);
 			}
 		}
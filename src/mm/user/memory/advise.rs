@@ -0,0 +1,82 @@
+use core::cmp::{max, min};
+
+use alloc::vec::Vec;
+
+use crate::mm::constant::PAGE_SIZE;
+use crate::mm::page::get_zero_page_phys;
+use crate::mm::user::vma::AreaFlag;
+use crate::syscall::errno::Errno;
+
+use super::Memory;
+
+impl Memory {
+	/// parts of areas in [start, start + len) with their flags.
+	fn ranges_in(
+		&mut self,
+		start: usize,
+		len: usize,
+	) -> Result<Vec<(usize, usize, AreaFlag)>, Errno> {
+		if !self.query_flags_range(start, len, AreaFlag::empty()) {
+			return Err(Errno::ENOMEM);
+		}
+
+		let end = start + len;
+
+		Ok(self
+			.vma
+			.areas_in_range_mut(start, end)
+			.map(|area| (max(area.start, start), min(area.end, end), area.flags))
+			.collect())
+	}
+
+	/// readable parts of [start, start + len), to be prefaulted by caller.
+	pub fn readable_ranges(
+		&mut self,
+		start: usize,
+		len: usize,
+	) -> Result<Vec<(usize, usize)>, Errno> {
+		Ok(self
+			.ranges_in(start, len)?
+			.into_iter()
+			.filter(|(_, _, flags)| flags.contains(AreaFlag::Readable))
+			.map(|(begin, end, _)| (begin, end - begin))
+			.collect())
+	}
+
+	/// drop pages in [start, start + len).
+	///
+	/// private pages are zero-filled (or read from file) again on next access.
+	/// for shared file mapping, only unmodified pages are dropped,
+	/// since modified pages are not written back yet.
+	pub fn discard_pages(&mut self, start: usize, len: usize) -> Result<(), Errno> {
+		for (begin, end, flags) in self.ranges_in(start, len)? {
+			if flags.contains(AreaFlag::Shared) {
+				self.invalidate_clean_pages(begin, end);
+				continue;
+			}
+
+			for vaddr in (begin..end).step_by(PAGE_SIZE) {
+				if Self::free_page_if_allocated(&self.page_dir, vaddr).is_some() {
+					self.page_dir.unmap_user(vaddr);
+				}
+			}
+		}
+
+		Ok(())
+	}
+
+	/// residency of each page in [start, start + len).
+	/// pages not allocated yet (or swapped out) are not resident.
+	pub fn mincore(&mut self, start: usize, len: usize) -> Result<Vec<u8>, Errno> {
+		self.ranges_in(start, len)?;
+
+		Ok((start..start + len)
+			.step_by(PAGE_SIZE)
+			.map(|vaddr| {
+				self.page_dir
+					.lookup(vaddr)
+					.is_some_and(|paddr| paddr != get_zero_page_phys()) as u8
+			})
+			.collect())
+	}
+}
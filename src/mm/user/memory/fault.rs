@@ -0,0 +1,330 @@
+use core::ptr::NonNull;
+
+use crate::mm::alloc::page::{free_pages, page_count};
+use crate::mm::alloc::Zone;
+use crate::mm::constant::{PAGE_MASK, PAGE_SIZE};
+use crate::mm::page::{get_zero_page_phys, PageFlag};
+use crate::mm::swap;
+use crate::mm::user::copy::{copy_user_to_user_page, memset_to_user_page};
+use crate::mm::user::vma::AreaFlag;
+use crate::mm::util::phys_to_virt;
+use crate::process::task::{UserTaskExt, CURRENT};
+use crate::ptr::PageBox;
+use crate::syscall::errno::Errno;
+
+use super::growsdown::expand_stack;
+use super::mapped_file::MappedFile;
+use super::Memory;
+
+/// where the contents of newly allocated page come from.
+enum PageSource {
+	Zero,
+	File(MappedFile, usize),
+	Swap(usize),
+}
+
+impl PageSource {
+	/// fill physical page `paddr`. this may sleep.
+	fn fill(&self, paddr: usize) -> Result<(), Errno> {
+		match self {
+			PageSource::Zero => unsafe { memset_to_user_page(paddr, 0) }.map_err(|_| Errno::ENOMEM),
+			PageSource::File(file, index) => file.read_page(*index, paddr),
+			PageSource::Swap(slot) => swap::read_slot(*slot, paddr),
+		}
+	}
+
+	fn swap_slot(&self) -> Option<usize> {
+		match self {
+			PageSource::Swap(slot) => Some(*slot),
+			_ => None,
+		}
+	}
+}
+
+impl Memory {
+	fn required_flag(write: bool) -> AreaFlag {
+		match write {
+			true => AreaFlag::Writable,
+			false => AreaFlag::Readable,
+		}
+	}
+
+	/// resolve fault which doesn't need new page contents. (copy-on-write)
+	///
+	/// returns `Some(source)` if a new page filled from `source` has to be
+	/// installed by `install_page`.
+	fn try_resolve_fault(
+		&mut self,
+		vaddr: usize,
+		write: bool,
+	) -> Result<Option<PageSource>, Errno> {
+		let area = self
+			.vma
+			.find_area(vaddr)
+			.filter(|area| area.flags.contains(Self::required_flag(write)))
+			.ok_or(Errno::EFAULT)?;
+
+		let (area_start, area_flags) = (area.start, area.flags);
+		let base = vaddr & PAGE_MASK;
+
+		match self.page_dir.lookup(base) {
+			Some(paddr) if write => {
+				let paddr = copy_on_write(paddr)?;
+				self.page_dir.map_user(base, paddr, area_flags.into())?;
+				Ok(None)
+			}
+			Some(_) => Err(Errno::EFAULT),
+			None => Ok(Some(self.page_source(area_start, base))),
+		}
+	}
+
+	/// same as `try_resolve_fault`, but protection of the area is ignored. (ptrace)
+	///
+	/// write to private area always breaks copy-on-write,
+	/// and the private copy is mapped with protection of the area.
+	fn try_resolve_forced(
+		&mut self,
+		vaddr: usize,
+		write: bool,
+	) -> Result<Option<PageSource>, Errno> {
+		let area = self.vma.find_area(vaddr).ok_or(Errno::EFAULT)?;
+
+		let (area_start, area_flags) = (area.start, area.flags);
+		let base = vaddr & PAGE_MASK;
+
+		// shared mapping has no private copy to write to.
+		let shared = area_flags.contains(AreaFlag::Shared);
+		if write && shared && !area_flags.contains(AreaFlag::Writable) {
+			return Err(Errno::EFAULT);
+		}
+
+		match self.page_dir.lookup(base) {
+			Some(paddr) if write && !shared => {
+				let new_paddr = copy_on_write(paddr)?;
+				if new_paddr != paddr {
+					self.page_dir.map_user(base, new_paddr, area_flags.into())?;
+				}
+				Ok(None)
+			}
+			Some(_) => Ok(None),
+			None => Ok(Some(self.page_source(area_start, base))),
+		}
+	}
+
+	fn access_resolved<T>(
+		&mut self,
+		vaddr: usize,
+		write: bool,
+		f: impl FnOnce(usize) -> T,
+	) -> Result<T, Errno> {
+		let base = vaddr & PAGE_MASK;
+		let (paddr, flag) = self
+			.page_dir
+			.lookup(base)
+			.zip(self.page_dir.lookup_flag(base))
+			.ok_or(Errno::EFAULT)?;
+
+		let result = f(paddr);
+
+		if write {
+			self.page_dir
+				.map_user(base, paddr, flag | PageFlag::Dirty)?;
+		}
+
+		Ok(result)
+	}
+
+	fn page_source(&self, area_start: usize, base: usize) -> PageSource {
+		match (
+			self.page_dir.lookup_swap(base),
+			self.file_mapping.get(&area_start),
+		) {
+			(Some(slot), _) => PageSource::Swap(slot),
+			(None, Some(file)) => PageSource::File(file.clone(), (base - area_start) / PAGE_SIZE),
+			(None, None) => PageSource::Zero,
+		}
+	}
+
+	/// map `page` filled from `source` at `vaddr`.
+	///
+	/// memory lock was released while `page` was being filled,
+	/// so the area could have been changed or the fault could have been resolved by others.
+	fn install_page(
+		&mut self,
+		vaddr: usize,
+		required: AreaFlag,
+		source: &PageSource,
+		page: PageBox,
+	) -> Result<(), Errno> {
+		let area_flags = self
+			.vma
+			.find_area(vaddr)
+			.filter(|area| area.flags.contains(required))
+			.ok_or(Errno::EFAULT)?
+			.flags;
+
+		let base = vaddr & PAGE_MASK;
+		if self.page_dir.lookup(base).is_some()
+			|| self.page_dir.lookup_swap(base) != source.swap_slot()
+		{
+			return Ok(());
+		}
+
+		self.page_dir
+			.map_user(base, page.as_phys_addr(), area_flags.into())?;
+		page.forget();
+
+		if let Some(slot) = source.swap_slot() {
+			swap::free_slot(slot);
+		}
+
+		Ok(())
+	}
+}
+
+/// allocate new page filled from `source` and map it at `vaddr`.
+fn fill_and_install(
+	ext: &UserTaskExt,
+	vaddr: usize,
+	required: AreaFlag,
+	source: PageSource,
+) -> Result<(), Errno> {
+	let page = PageBox::new(Zone::High)?;
+
+	source.fill(page.as_phys_addr())?;
+
+	ext.lock_memory()
+		.install_page(vaddr, required, &source, page)
+}
+
+/// make page that contains `vaddr` accessible.
+///
+/// - not present page: allocate new page, filled with file contents if area is
+///   backed by file, zeroed otherwise.
+/// - write to write protected page: copy-on-write.
+///
+/// - swapped out page: read the page back from swap area.
+/// - just below stack: expand the stack.
+///
+/// memory lock is released while filling new page, since reading file or swap may sleep.
+/// `EINTR` is returned if it is interrupted by a signal.
+pub fn handle_fault(ext: &UserTaskExt, vaddr: usize, write: bool) -> Result<(), Errno> {
+	expand_stack(ext, vaddr);
+
+	let source = match ext.lock_memory().try_resolve_fault(vaddr, write)? {
+		Some(source) => source,
+		None => {
+			account_fault(false);
+			return Ok(());
+		}
+	};
+
+	let major = !matches!(source, PageSource::Zero);
+	fill_and_install(ext, vaddr, Memory::required_flag(write), source)?;
+
+	account_fault(major);
+	Ok(())
+}
+
+/// charge the fault to the current task.
+fn account_fault(major: bool) {
+	unsafe { CURRENT.get_ref() }.lock_usage().fault(major);
+}
+
+/// read swapped out page at `vaddr` back regardless of protection of the area.
+pub fn swap_in(ext: &UserTaskExt, vaddr: usize) -> Result<(), Errno> {
+	let slot = match ext.lock_memory().get_pd().lookup_swap(vaddr) {
+		Some(slot) => slot,
+		None => return Ok(()),
+	};
+
+	fill_and_install(ext, vaddr, AreaFlag::empty(), PageSource::Swap(slot))
+}
+
+/// access page that contains `vaddr` of other task regardless of protection of the area. (ptrace)
+///
+/// `f` is called with physical address of the page while memory is locked.
+/// write breaks copy-on-write of private page even if the area is read-only,
+/// and marks the page dirty so that shared mapping is written back.
+/// the fault is not charged to anyone.
+pub fn access_forced<T>(
+	ext: &UserTaskExt,
+	vaddr: usize,
+	write: bool,
+	f: impl FnOnce(usize) -> T,
+) -> Result<T, Errno> {
+	loop {
+		let source = {
+			let mut memory = ext.lock_memory();
+			match memory.try_resolve_forced(vaddr, write)? {
+				Some(source) => source,
+				None => return memory.access_resolved(vaddr, write, f),
+			}
+		};
+
+		fill_and_install(ext, vaddr, AreaFlag::empty(), source)?;
+	}
+}
+
+/// resolve every fault in [start, start + len) in advance,
+/// so that kernel can access user memory without page fault.
+pub fn prefault(ext: &UserTaskExt, start: usize, len: usize, write: bool) -> Result<(), Errno> {
+	if len == 0 {
+		return Ok(());
+	}
+
+	let end = start.checked_add(len).ok_or(Errno::EFAULT)?;
+
+	let resolved = |vaddr: usize| {
+		ext.lock_memory()
+			.get_pd()
+			.lookup_flag(vaddr)
+			.is_some_and(|flag| !write || flag.contains(PageFlag::Write))
+	};
+
+	// `handle_fault` may leave the page unresolved. (raced with others)
+	// interrupted one returns to the syscall, so that the signal is handled.
+	for vaddr in ((start & PAGE_MASK)..end).step_by(PAGE_SIZE) {
+		while !resolved(vaddr) {
+			handle_fault(ext, vaddr, write)?;
+		}
+	}
+
+	Ok(())
+}
+
+fn alloc_zeroed_page() -> Result<usize, Errno> {
+	let page = PageBox::new(Zone::High)?;
+
+	unsafe { memset_to_user_page(page.as_phys_addr(), 0)? };
+
+	let paddr = page.as_phys_addr();
+	page.forget();
+
+	Ok(paddr)
+}
+
+/// resolve write fault on present (write protected) page.
+///
+/// if the page is still shared with other address space, copy it to new page.
+/// otherwise, it is safe to reuse the page as-is.
+fn copy_on_write(paddr: usize) -> Result<usize, Errno> {
+	if paddr == get_zero_page_phys() {
+		return alloc_zeroed_page();
+	}
+
+	if page_count(paddr) <= 1 {
+		return Ok(paddr);
+	}
+
+	let page = PageBox::new(Zone::High)?;
+	unsafe { copy_user_to_user_page(paddr, page.as_phys_addr())? };
+
+	// drop reference of old page.
+	free_pages(unsafe { NonNull::new_unchecked(phys_to_virt(paddr) as *mut u8) });
+
+	let paddr = page.as_phys_addr();
+	page.forget();
+
+	Ok(paddr)
+}
@@ -0,0 +1,42 @@
+use crate::mm::constant::PAGE_MASK;
+use crate::mm::user::vma::AreaFlag;
+use crate::process::rlimit::Resource;
+use crate::process::task::UserTaskExt;
+
+use super::Memory;
+
+impl Memory {
+	/// expand grows-down area just above unmapped `vaddr` to cover it.
+	///
+	/// the area cannot be larger than `limit` bytes, nor address space than
+	/// `as_limit` bytes. its guard gap must be kept from the area below.
+	fn expand_stack(&mut self, vaddr: usize, limit: usize, as_limit: usize) -> bool {
+		let area = match self.vma.find_next_area(vaddr) {
+			Some(area) if area.flags.contains(AreaFlag::GrowsDown) => area,
+			_ => return false,
+		};
+
+		let new_start = vaddr & PAGE_MASK;
+		if area.end - new_start > limit || !self.may_expand(area.start - new_start, as_limit) {
+			return false;
+		}
+
+		self.vma.expand_area_down(area.start, new_start).is_ok()
+	}
+}
+
+/// expand stack of the task to cover `vaddr`, up to `RLIMIT_STACK` and `RLIMIT_AS`.
+///
+/// returns `false` if `vaddr` is not just below the stack or stack cannot grow anymore.
+pub fn expand_stack(ext: &UserTaskExt, vaddr: usize) -> bool {
+	let (limit, as_limit) = {
+		let rlimits = ext.lock_rlimits();
+
+		(
+			rlimits.get(Resource::Stack).cur,
+			rlimits.get(Resource::As).cur,
+		)
+	};
+
+	ext.lock_memory().expand_stack(vaddr, limit, as_limit)
+}
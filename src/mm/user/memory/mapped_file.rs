@@ -1,33 +1,227 @@
-use core::slice::from_raw_parts;
+use core::cmp::min;
+use core::ptr::NonNull;
+use core::slice::{from_raw_parts, from_raw_parts_mut};
+
+use alloc::{sync::Arc, vec::Vec};
 
 use crate::{
 	fs::vfs::{VfsHandle, Whence},
+	mm::{
+		alloc::{
+			page::{free_pages, get_page},
+			virt::{kmap, kunmap},
+		},
+		constant::PAGE_SIZE,
+		util::phys_to_virt,
+	},
+	process::wait_list::WaitList,
+	scheduler::sleep::{sleep_and_yield_lock, Sleep},
+	sync::Locked,
 	syscall::errno::Errno,
 };
 
+/// serializes `lseek` and the transfer after it.
+///
+/// page faults are handled in interrupt context, where `LocalLocked` cannot be used.
+/// so the owner is tracked by a flag, and others sleep until it is done.
+struct IoLock {
+	busy: bool,
+	waiters: WaitList,
+}
+
+struct IoGuard<'a>(&'a Locked<IoLock>);
+
+impl Drop for IoGuard<'_> {
+	fn drop(&mut self) {
+		let mut io = self.0.lock();
+
+		io.busy = false;
+		io.waiters.wake_up_all();
+	}
+}
+
+fn lock_io(lock: &Locked<IoLock>) -> IoGuard<'_> {
+	loop {
+		let mut io = lock.lock();
+
+		if !io.busy {
+			io.busy = true;
+			return IoGuard(lock);
+		}
+
+		io.waiters.register();
+		sleep_and_yield_lock(Sleep::Deep, io);
+	}
+}
+
 #[derive(Clone)]
 pub struct MappedFile {
 	file: VfsHandle,
 	offset: isize,
 	len: usize,
+	/// `file` is shared by clones of the mapping, which fault without memory lock.
+	io: Arc<Locked<IoLock>>,
 }
 
 impl MappedFile {
 	pub fn new(file: VfsHandle, offset: isize, len: usize) -> Self {
-		Self { file, offset, len }
+		Self {
+			file,
+			offset,
+			len,
+			io: Arc::new(Locked::new(IoLock {
+				busy: false,
+				waiters: WaitList::new(),
+			})),
+		}
+	}
+
+	/// file is opened for writing, so the mapping can be written back.
+	pub fn is_writable(&self) -> bool {
+		self.file.access_flags().write_ok()
 	}
 
-	pub fn sync_with_buf(&self, buf: *const u8) -> Result<(), Errno> {
-		self.file.lseek(self.offset, Whence::Begin)?;
+	/// write back `index`th page of mapping from physical page `paddr`.
+	/// bytes after end of mapping are not written.
+	pub fn write_page(&self, index: usize, paddr: usize) -> Result<(), Errno> {
+		let begin = index * PAGE_SIZE;
+		let len = min(self.len.saturating_sub(begin), PAGE_SIZE);
 
-		let buf = unsafe { from_raw_parts(buf, self.len) };
+		if len == 0 {
+			return Ok(());
+		}
+
+		let page = kmap(paddr).map_err(|_| Errno::ENOMEM)?;
+		let buf = unsafe { from_raw_parts(page.as_ptr(), len) };
+
+		let result = self.write_all(begin, buf);
+
+		kunmap(page.as_ptr() as usize);
+
+		result
+	}
+
+	fn write_all(&self, begin: usize, buf: &[u8]) -> Result<(), Errno> {
+		let _io = lock_io(&self.io);
+
+		self.file
+			.lseek(self.offset + begin as isize, Whence::Begin)?;
 
 		let mut cursor = 0;
-		while cursor < self.len {
-			let buf = &buf[cursor..];
-			let x = self.file.write(buf)?;
-			cursor += x;
+		while cursor < buf.len() {
+			match self.file.write(&buf[cursor..])? {
+				// file cannot grow any more.
+				0 => return Err(Errno::EIO),
+				x => cursor += x,
+			}
 		}
+
 		Ok(())
 	}
+
+	pub fn sync(&self) -> Result<(), Errno> {
+		self.file.sync()
+	}
+
+	/// read `index`th page of mapping into physical page `paddr`.
+	/// remaining bytes after end of file are filled with zero.
+	pub fn read_page(&self, index: usize, paddr: usize) -> Result<(), Errno> {
+		let begin = index * PAGE_SIZE;
+		let len = min(self.len.saturating_sub(begin), PAGE_SIZE);
+
+		let page = kmap(paddr).map_err(|_| Errno::ENOMEM)?;
+		let buf = unsafe { from_raw_parts_mut(page.as_ptr(), PAGE_SIZE) };
+
+		let result = self.read_exact(begin, &mut buf[..len]);
+		buf[len..].fill(0);
+
+		kunmap(page.as_ptr() as usize);
+
+		result
+	}
+
+	fn read_exact(&self, begin: usize, buf: &mut [u8]) -> Result<(), Errno> {
+		let _io = lock_io(&self.io);
+
+		self.file
+			.lseek(self.offset + begin as isize, Whence::Begin)?;
+
+		let mut cursor = 0;
+		while cursor < buf.len() {
+			match self.file.read(&mut buf[cursor..])? {
+				0 => break,
+				x => cursor += x,
+			}
+		}
+		buf[cursor..].fill(0);
+
+		Ok(())
+	}
+
+	/// split mapping at `at` bytes from the beginning.
+	/// returns latter part of mapping if it has any byte of file.
+	pub fn split_off(&mut self, at: usize) -> Option<MappedFile> {
+		if self.len <= at {
+			return None;
+		}
+
+		let tail = MappedFile {
+			file: self.file.clone(),
+			offset: self.offset + at as isize,
+			len: self.len - at,
+			io: self.io.clone(),
+		};
+		self.len = at;
+
+		Some(tail)
+	}
+}
+
+/// modified pages of a shared file mapping.
+///
+/// pages are pinned until this is dropped, so they can be written back
+/// after memory lock is released. (writing file may sleep)
+pub struct DirtyPages {
+	file: MappedFile,
+	/// (index in mapping, physical address)
+	pages: Vec<(usize, usize)>,
+}
+
+impl DirtyPages {
+	pub fn new(file: MappedFile) -> Self {
+		Self {
+			file,
+			pages: Vec::new(),
+		}
+	}
+
+	pub fn push(&mut self, index: usize, paddr: usize) {
+		get_page(paddr);
+
+		self.pages.push((index, paddr));
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.pages.is_empty()
+	}
+
+	/// write pages to file. flush them to the device too if `sync` is set.
+	pub fn write_back(&self, sync: bool) -> Result<(), Errno> {
+		for (index, paddr) in self.pages.iter() {
+			self.file.write_page(*index, *paddr)?;
+		}
+
+		match sync {
+			true => self.file.sync(),
+			false => Ok(()),
+		}
+	}
+}
+
+impl Drop for DirtyPages {
+	fn drop(&mut self) {
+		for (_, paddr) in self.pages.iter() {
+			free_pages(unsafe { NonNull::new_unchecked(phys_to_virt(*paddr) as *mut u8) });
+		}
+	}
 }
@@ -0,0 +1,103 @@
+use core::ptr::NonNull;
+
+use alloc::vec::Vec;
+
+use crate::boot::MEM_INFO;
+use crate::mm::alloc::page::{free_pages, get_page, page_count};
+use crate::mm::page::{get_zero_page_phys, PageFlag};
+use crate::mm::user::vma::AreaFlag;
+use crate::mm::util::{addr_to_pfn, phys_to_virt};
+
+use super::Memory;
+
+impl Memory {
+	/// number of pages which are actually backed by physical memory.
+	/// (zero page is not counted)
+	pub fn nr_resident_pages(&self) -> usize {
+		self.vma
+			.get_areas()
+			.iter()
+			.flat_map(|area| area.iter_pages())
+			.filter_map(|vaddr| self.page_dir.lookup(vaddr))
+			.filter(|paddr| *paddr != get_zero_page_phys())
+			.count()
+	}
+
+	/// collect pages which can be swapped out.
+	///
+	/// page that was accessed since last scan gets second chance. (clock algorithm)
+	pub fn swap_candidates(&mut self) -> Vec<usize> {
+		let mut candidates = Vec::new();
+
+		for area in self.vma.get_areas() {
+			if area.flags.contains(AreaFlag::Shared) {
+				continue;
+			}
+
+			for vaddr in area.iter_pages() {
+				if self.page_dir.test_and_clear(vaddr, PageFlag::Accessed) {
+					continue;
+				}
+
+				if self.page_dir.lookup(vaddr).is_some_and(is_swappable) {
+					candidates.push(vaddr);
+				}
+			}
+		}
+
+		candidates
+	}
+
+	/// write protect page at `vaddr` while it is being written to swap area.
+	///
+	/// the page is pinned by extra reference, so write to the page during I/O
+	/// copies the page and `finish_swap_out` can notice it.
+	pub fn start_swap_out(&mut self, vaddr: usize) -> Option<usize> {
+		let paddr = self.page_dir.lookup(vaddr).filter(|x| is_swappable(*x))?;
+
+		let flags = self.page_dir.lookup_flag(vaddr)? - PageFlag::Write;
+		self.page_dir.map_user(vaddr, paddr, flags).ok()?;
+
+		get_page(paddr);
+
+		Some(paddr)
+	}
+
+	/// replace page with swap entry if the page was not modified during I/O.
+	/// `slot` is `None` if writing to swap area was failed.
+	/// returns whether page was swapped out.
+	pub fn finish_swap_out(&mut self, vaddr: usize, paddr: usize, slot: Option<usize>) -> bool {
+		let swapped = slot.is_some_and(|slot| {
+			self.page_dir.lookup(vaddr) == Some(paddr)
+				&& self.page_dir.map_swap(vaddr, slot).is_ok()
+		});
+
+		// drop pin. (and mapping, if swapped out)
+		let page = unsafe { NonNull::new_unchecked(phys_to_virt(paddr) as *mut u8) };
+		free_pages(page);
+		if swapped {
+			free_pages(page);
+		}
+
+		swapped
+	}
+
+	/// collect pages which are swapped out.
+	pub fn swapped_pages(&self) -> Vec<usize> {
+		self.vma
+			.get_areas()
+			.iter()
+			.flat_map(|area| area.iter_pages())
+			.filter(|vaddr| self.page_dir.lookup_swap(*vaddr).is_some())
+			.collect()
+	}
+}
+
+/// only private page of high memory that isn't shared with others can be swapped out.
+fn is_swappable(paddr: usize) -> bool {
+	if paddr == get_zero_page_phys() || addr_to_pfn(paddr) < unsafe { MEM_INFO.high_start_pfn } {
+		return false;
+	}
+
+	page_count(paddr) <= 1
+}
@@ -0,0 +1,114 @@
+use alloc::vec::Vec;
+
+use crate::mm::constant::PAGE_SIZE;
+use crate::syscall::errno::Errno;
+
+use super::mapped_file::DirtyPages;
+use super::Memory;
+
+impl Memory {
+	/// resize mapping [old, old + old_pages * PAGE_SIZE) to `new_pages`.
+	///
+	/// the mapping is grown in place if possible. otherwise it is moved
+	/// if `may_move` is set. with `fixed`, it is always moved to that address
+	/// and whatever was mapped there is unmapped.
+	///
+	/// returns new address of the mapping, and modified pages of shared file mapping
+	/// unmapped on the way, which have to be written back by caller.
+	pub fn mremap(
+		&mut self,
+		old: usize,
+		old_pages: usize,
+		new_pages: usize,
+		may_move: bool,
+		fixed: Option<usize>,
+	) -> Result<(usize, Vec<DirtyPages>), Errno> {
+		let old_len = old_pages.checked_mul(PAGE_SIZE).ok_or(Errno::EINVAL)?;
+		let new_len = new_pages.checked_mul(PAGE_SIZE).ok_or(Errno::EINVAL)?;
+		let old_end = old.checked_add(old_len).ok_or(Errno::EINVAL)?;
+
+		// old mapping must not span multiple areas.
+		match self.vma.find_area(old) {
+			Some(area) if old_end <= area.end => (),
+			_ => return Err(Errno::EFAULT),
+		};
+
+		let mut dirty = Vec::new();
+
+		if let Some(new) = fixed {
+			let new_end = new.checked_add(new_len).ok_or(Errno::EINVAL)?;
+			if new < old_end && old < new_end {
+				return Err(Errno::EINVAL);
+			}
+
+			dirty.extend(self.munmap(new, new_pages)?);
+		}
+
+		self.split_area(old);
+		self.split_area(old_end);
+
+		if new_pages < old_pages {
+			dirty.extend(self.munmap(old + new_len, old_pages - new_pages)?);
+		}
+
+		let start = match fixed {
+			None if new_pages <= old_pages || self.vma.resize_area(old, new_pages).is_ok() => old,
+			None if !may_move => {
+				self.merge_area(old);
+				self.merge_area(old_end);
+				return Err(Errno::ENOMEM);
+			}
+			_ => self.move_area(old, new_pages, fixed)?,
+		};
+
+		self.merge_area(start);
+		self.merge_area(start + new_len);
+
+		Ok((start, dirty))
+	}
+
+	/// move area that starts at `old` to new area of `new_pages` pages.
+	/// (at `fixed` if given, anywhere otherwise)
+	///
+	/// pages are moved with their page table entries, nothing is copied.
+	fn move_area(
+		&mut self,
+		old: usize,
+		new_pages: usize,
+		fixed: Option<usize>,
+	) -> Result<usize, Errno> {
+		let area = self.vma.find_area(old).expect("area must exist").clone();
+
+		let new = match fixed {
+			Some(addr) => self.vma.allocate_fixed_area(addr, new_pages, area.flags),
+			None => self.vma.allocate_area(new_pages, area.flags),
+		}
+		.map_err(|_| Errno::ENOMEM)?;
+
+		for (i, vaddr) in area.iter_pages().enumerate() {
+			let to = new + i * PAGE_SIZE;
+
+			if let Some(slot) = self.page_dir.lookup_swap(vaddr) {
+				self.page_dir.map_swap(to, slot)?;
+			} else if let Some((paddr, flags)) = self
+				.page_dir
+				.lookup(vaddr)
+				.zip(self.page_dir.lookup_flag(vaddr))
+			{
+				self.page_dir.map_user(to, paddr, flags)?;
+			} else {
+				continue;
+			}
+
+			self.page_dir.unmap_user(vaddr);
+		}
+
+		if let Some(mapped_file) = self.file_mapping.remove(&old) {
+			self.file_mapping.insert(new, mapped_file);
+		}
+
+		self.vma.deallocate_area(old);
+
+		Ok(new)
+	}
+}
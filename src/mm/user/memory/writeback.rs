@@ -0,0 +1,100 @@
+use core::cmp::{max, min};
+use core::ptr::NonNull;
+
+use alloc::vec::Vec;
+
+use crate::mm::alloc::page::{free_pages, page_count};
+use crate::mm::constant::PAGE_SIZE;
+use crate::mm::page::PageFlag;
+use crate::mm::user::vma::AreaFlag;
+use crate::mm::util::phys_to_virt;
+use crate::syscall::errno::Errno;
+
+use super::mapped_file::DirtyPages;
+use super::Memory;
+
+impl Memory {
+	/// collect modified pages in [start, end) of shared file mapping at `area_start`.
+	/// dirty bits of collected pages are cleared.
+	pub(super) fn take_dirty_pages(
+		&mut self,
+		area_start: usize,
+		start: usize,
+		end: usize,
+	) -> Option<DirtyPages> {
+		let mut dirty = DirtyPages::new(self.file_mapping.get(&area_start)?.clone());
+
+		for vaddr in (start..end).step_by(PAGE_SIZE) {
+			if !self.page_dir.test_and_clear(vaddr, PageFlag::Dirty) {
+				continue;
+			}
+
+			let paddr = self
+				.page_dir
+				.lookup(vaddr)
+				.expect("dirty page must be present");
+			dirty.push((vaddr - area_start) / PAGE_SIZE, paddr);
+		}
+
+		(!dirty.is_empty()).then_some(dirty)
+	}
+
+	/// drop unmodified pages in [start, end), so that they are read from file
+	/// again on next access. pages still shared with other address space are kept.
+	pub(super) fn invalidate_clean_pages(&mut self, start: usize, end: usize) {
+		for vaddr in (start..end).step_by(PAGE_SIZE) {
+			let clean = self
+				.page_dir
+				.lookup_flag(vaddr)
+				.is_some_and(|flag| !flag.contains(PageFlag::Dirty));
+
+			let paddr = match self.page_dir.lookup(vaddr) {
+				Some(paddr) if clean => paddr,
+				_ => continue,
+			};
+
+			if page_count(paddr) > 1 {
+				continue;
+			}
+
+			self.page_dir.unmap_user(vaddr);
+			free_pages(unsafe { NonNull::new_unchecked(phys_to_virt(paddr) as *mut u8) });
+		}
+	}
+
+	/// collect modified pages of shared file mappings in [start, start + len)
+	/// to be written back by caller.
+	///
+	/// if `invalidate` is set, unmodified pages are dropped as well.
+	pub fn msync(
+		&mut self,
+		start: usize,
+		len: usize,
+		invalidate: bool,
+	) -> Result<Vec<DirtyPages>, Errno> {
+		if !self.query_flags_range(start, len, AreaFlag::empty()) {
+			return Err(Errno::ENOMEM);
+		}
+
+		let end = start + len;
+		let ranges = self
+			.vma
+			.get_areas()
+			.iter()
+			.filter(|area| area.flags.contains(AreaFlag::Shared))
+			.filter(|area| area.start < end && start < area.end)
+			.map(|area| (area.start, max(area.start, start), min(area.end, end)))
+			.collect::<Vec<_>>();
+
+		let mut result = Vec::new();
+		for (area_start, begin, end) in ranges {
+			if invalidate {
+				self.invalidate_clean_pages(begin, end);
+			}
+
+			result.extend(self.take_dirty_pages(area_start, begin, end));
+		}
+
+		Ok(result)
+	}
+}
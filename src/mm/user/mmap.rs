@@ -1,11 +1,12 @@
 use crate::{
+	fs::vfs::IOFlag,
 	mm::{constant::PAGE_SIZE, util::size_to_pages},
-	process::{fd_table::Fd, task::CURRENT},
+	process::{fd_table::Fd, rlimit::Resource, task::CURRENT},
 	syscall::errno::Errno,
 };
 use bitflags::bitflags;
 
-use super::vma::AreaFlag;
+use super::{memory::prefault, verify::verify_buffer_mut, vma::AreaFlag};
 
 bitflags! {
 	#[repr(transparent)]
@@ -13,13 +14,93 @@ bitflags! {
 	pub struct MmapFlag: u32 {
 		const Shared = 1;
 		const Private = 2;
+		const Fixed = 0x10;
+		const Anonymous = 0x20;
+		const GrowsDown = 0x100;
+		const Stack = 0x20000;
+	}
+}
+
+bitflags! {
+	#[repr(transparent)]
+	#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+	pub struct MsyncFlag: u32 {
+		const Async = 1;
+		const Invalidate = 2;
+		const Sync = 4;
+	}
+}
+
+bitflags! {
+	#[repr(transparent)]
+	#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+	pub struct MremapFlag: u32 {
+		const MayMove = 1;
+		const Fixed = 2;
+	}
+}
+
+enum Advice {
+	Normal,
+	Random,
+	Sequential,
+	WillNeed,
+	DontNeed,
+	Free,
+}
+
+impl TryFrom<i32> for Advice {
+	type Error = Errno;
+
+	fn try_from(value: i32) -> Result<Self, Self::Error> {
+		match value {
+			0 => Ok(Self::Normal),
+			1 => Ok(Self::Random),
+			2 => Ok(Self::Sequential),
+			3 => Ok(Self::WillNeed),
+			4 => Ok(Self::DontNeed),
+			8 => Ok(Self::Free),
+			_ => Err(Errno::EINVAL),
+		}
+	}
+}
+
+bitflags! {
+	#[repr(transparent)]
+	#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+	pub struct MmapProt: u32 {
+		const Read = 1;
+		const Write = 2;
+		const Exec = 4;
+	}
+}
+
+impl From<MmapProt> for AreaFlag {
+	fn from(value: MmapProt) -> Self {
+		let mut result = AreaFlag::empty();
+
+		if value.contains(MmapProt::Read) {
+			result |= AreaFlag::Readable;
+		}
+
+		// x86 cannot make page write-only.
+		if value.contains(MmapProt::Write) {
+			result |= AreaFlag::Readable | AreaFlag::Writable;
+		}
+
+		// nor execute-only.
+		if value.contains(MmapProt::Exec) {
+			result |= AreaFlag::Readable | AreaFlag::Executable;
+		}
+
+		result
 	}
 }
 
 pub fn sys_mmap(
 	addr: usize,
 	len: usize,
-	_prot: i32,
+	prot: i32,
 	flags: i32,
 	fd: i32,
 	offset: isize,
@@ -28,26 +109,69 @@ pub fn sys_mmap(
 	let user_ext = current.get_user_ext().expect("must be user process");
 
 	// let flags = MmapFlag::from_bits(flags as u32).ok_or(Errno::EINVAL)?;
-	// let prot = AreaFlag::from_bits(prot as u32).ok_or(Errno::EINVAL)?;
 
 	let flags = MmapFlag::from_bits_truncate(flags as u32);
-	let prot = AreaFlag::Readable | AreaFlag::Writable;
+	let prot: AreaFlag = MmapProt::from_bits(prot as u32)
+		.ok_or(Errno::EINVAL)?
+		.into();
 
 	// misaligned address
 	if addr % PAGE_SIZE != 0 || len == 0 {
 		return Err(Errno::EINVAL);
 	}
 
+	let as_limit = user_ext.lock_rlimits().get(Resource::As).cur;
+	if !user_ext
+		.lock_memory()
+		.may_expand(size_to_pages(len) * PAGE_SIZE, as_limit)
+	{
+		return Err(Errno::ENOMEM);
+	}
+
 	if flags.contains(MmapFlag::Shared) {
 		let fd = Fd::from(fd as usize).ok_or(Errno::EINVAL)?;
 		let handle = user_ext.lock_fd_table().get_file(fd).ok_or(Errno::EINVAL)?;
 		let prot = prot.union(AreaFlag::Shared);
 
+		// modification must be written back to the file.
+		let access = handle.access_flags();
+		if !access.read_ok() || (prot.contains(AreaFlag::Writable) && !access.write_ok()) {
+			return Err(Errno::EACCES);
+		}
+
+		// pages are written back in place, even if `fd` was opened with `O_APPEND`.
+		let file = handle.reopen(IOFlag::empty())?;
+
 		user_ext
 			.lock_memory()
-			.mmap_shared(addr, len, handle.deep_copy()?, offset, prot)
+			.mmap_shared(addr, len, file, offset, prot)
+	} else if !flags.contains(MmapFlag::Anonymous) && fd >= 0 {
+		let fd = Fd::from(fd as usize).ok_or(Errno::EINVAL)?;
+		let handle = user_ext.lock_fd_table().get_file(fd).ok_or(Errno::EBADF)?;
+
+		if !handle.access_flags().read_ok() {
+			return Err(Errno::EACCES);
+		}
+
+		user_ext.lock_memory().mmap_private_file(
+			addr,
+			len,
+			handle.reopen(IOFlag::empty())?,
+			offset,
+			prot,
+		)
 	} else {
 		let pages = size_to_pages(len);
+		let mut prot = prot;
+
+		// thread stacks get guard gap below them.
+		if flags.contains(MmapFlag::GrowsDown) {
+			prot |= AreaFlag::GrowsDown | AreaFlag::StackGuard;
+		}
+		if flags.contains(MmapFlag::Stack) {
+			prot |= AreaFlag::StackGuard;
+		}
+
 		user_ext.lock_memory().mmap_private(addr, pages, prot)
 	}
 }
@@ -59,10 +183,174 @@ pub fn sys_munmap(addr: usize, len: usize) -> Result<usize, Errno> {
 		return Err(Errno::EINVAL);
 	}
 
+	let dirty = current
+		.get_user_ext()
+		.expect("must be user process")
+		.lock_memory()
+		.munmap(addr, len / PAGE_SIZE)?;
+
+	for pages in dirty {
+		pages.write_back(false)?;
+	}
+
+	Ok(0)
+}
+
+pub fn sys_mprotect(addr: usize, len: usize, prot: i32) -> Result<usize, Errno> {
+	let current = unsafe { CURRENT.get_mut() };
+
+	if addr % PAGE_SIZE != 0 {
+		return Err(Errno::EINVAL);
+	}
+
+	let prot = MmapProt::from_bits(prot as u32).ok_or(Errno::EINVAL)?;
+
+	if len == 0 {
+		return Ok(0);
+	}
+
 	let mut memory = current
 		.get_user_ext()
 		.expect("must be user process")
 		.lock_memory();
 
-	memory.munmap(addr, len / PAGE_SIZE).map(|_| 0)
+	memory
+		.mprotect(addr, size_to_pages(len), prot.into())
+		.map(|_| 0)
+}
+
+/// write back modified pages of shared file mappings.
+///
+/// modified pages also reach the file on `munmap` and on exit.
+pub fn sys_msync(addr: usize, len: usize, flags: i32) -> Result<usize, Errno> {
+	let current = unsafe { CURRENT.get_mut() };
+
+	let flags = MsyncFlag::from_bits(flags as u32).ok_or(Errno::EINVAL)?;
+
+	if addr % PAGE_SIZE != 0 || flags.contains(MsyncFlag::Async | MsyncFlag::Sync) {
+		return Err(Errno::EINVAL);
+	}
+
+	if len == 0 {
+		return Ok(0);
+	}
+
+	let dirty = current
+		.get_user_ext()
+		.expect("must be user process")
+		.lock_memory()
+		.msync(
+			addr,
+			size_to_pages(len) * PAGE_SIZE,
+			flags.contains(MsyncFlag::Invalidate),
+		)?;
+
+	for pages in dirty {
+		pages.write_back(flags.contains(MsyncFlag::Sync))?;
+	}
+
+	Ok(0)
+}
+
+pub fn sys_mremap(
+	old_addr: usize,
+	old_len: usize,
+	new_len: usize,
+	flags: i32,
+	new_addr: usize,
+) -> Result<usize, Errno> {
+	let current = unsafe { CURRENT.get_mut() };
+
+	let flags = MremapFlag::from_bits(flags as u32).ok_or(Errno::EINVAL)?;
+
+	if old_addr % PAGE_SIZE != 0 || old_len == 0 || new_len == 0 {
+		return Err(Errno::EINVAL);
+	}
+
+	let fixed = match flags.contains(MremapFlag::Fixed) {
+		true if !flags.contains(MremapFlag::MayMove) || new_addr % PAGE_SIZE != 0 => {
+			return Err(Errno::EINVAL)
+		}
+		true => Some(new_addr),
+		false => None,
+	};
+
+	let user_ext = current.get_user_ext().expect("must be user process");
+	let as_limit = user_ext.lock_rlimits().get(Resource::As).cur;
+
+	let mut memory = user_ext.lock_memory();
+	let grow = size_to_pages(new_len).saturating_sub(size_to_pages(old_len)) * PAGE_SIZE;
+	if !memory.may_expand(grow, as_limit) {
+		return Err(Errno::ENOMEM);
+	}
+
+	let (addr, dirty) = memory.mremap(
+		old_addr,
+		size_to_pages(old_len),
+		size_to_pages(new_len),
+		flags.contains(MremapFlag::MayMove),
+		fixed,
+	)?;
+	drop(memory);
+
+	for pages in dirty {
+		pages.write_back(false)?;
+	}
+
+	Ok(addr)
+}
+
+/// `MADV_FREE` is handled same as `MADV_DONTNEED`. (pages are dropped immediately)
+pub fn sys_madvise(addr: usize, len: usize, advice: i32) -> Result<usize, Errno> {
+	let current = unsafe { CURRENT.get_mut() };
+	let user_ext = current.get_user_ext().expect("must be user process");
+
+	let advice = Advice::try_from(advice)?;
+
+	if addr % PAGE_SIZE != 0 {
+		return Err(Errno::EINVAL);
+	}
+
+	if len == 0 {
+		return Ok(0);
+	}
+
+	let len = size_to_pages(len) * PAGE_SIZE;
+
+	match advice {
+		Advice::Normal | Advice::Random | Advice::Sequential => {
+			user_ext.lock_memory().readable_ranges(addr, len)?;
+		}
+		Advice::WillNeed => {
+			let ranges = user_ext.lock_memory().readable_ranges(addr, len)?;
+
+			for (start, len) in ranges {
+				prefault(user_ext, start, len, false)?;
+			}
+		}
+		Advice::DontNeed | Advice::Free => user_ext.lock_memory().discard_pages(addr, len)?,
+	};
+
+	Ok(0)
+}
+
+pub fn sys_mincore(addr: usize, len: usize, vec: usize) -> Result<usize, Errno> {
+	let current = unsafe { CURRENT.get_mut() };
+
+	if addr % PAGE_SIZE != 0 {
+		return Err(Errno::EINVAL);
+	}
+
+	let pages = size_to_pages(len);
+	let vec = verify_buffer_mut(vec, pages, current)?;
+
+	let residency = current
+		.get_user_ext()
+		.expect("must be user process")
+		.lock_memory()
+		.mincore(addr, pages * PAGE_SIZE)?;
+
+	vec.copy_from_slice(&residency);
+
+	Ok(0)
 }
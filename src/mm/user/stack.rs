@@ -44,7 +44,7 @@ impl UserStack {
 		Ok(())
 	}
 
-	pub fn push_aux_entry(&mut self, aux: AuxEntry) -> Result<(), Errno> {
+	pub fn push_aux_entry(&mut self, aux: &AuxEntry) -> Result<(), Errno> {
 		let data = aux.serialize();
 
 		for x in data {
@@ -58,6 +58,10 @@ impl UserStack {
 		base - self.pages.len() * PAGE_SIZE + self.next_offset + size_of::<usize>()
 	}
 
+	pub fn nr_pages(&self) -> usize {
+		self.pages.len()
+	}
+
 	pub fn pop_page(&mut self) -> Option<PageBox> {
 		self.pages.pop_front()
 	}
@@ -5,12 +5,22 @@ use alloc::{sync::Arc, vec::Vec};
 use crate::{
 	config::{MAX_PAGE_PER_ARG, MAX_PAGE_PER_ARGV},
 	mm::constant::PAGE_SIZE,
-	process::task::Task,
+	process::{rlimit::Resource, task::Task},
 	syscall::errno::Errno,
 };
 
 use super::verify::{verify_ptr, verify_string};
 
+/// strings of a vector take at most a quarter of `RLIMIT_STACK`,
+/// but no less than `MAX_PAGE_PER_ARGV` pages.
+fn arg_max(task: &Arc<Task>) -> usize {
+	let stack = task
+		.get_user_ext()
+		.map_or(0, |ext| ext.lock_rlimits().get(Resource::Stack).cur);
+
+	(stack / 4).max(MAX_PAGE_PER_ARGV * PAGE_SIZE)
+}
+
 pub struct StringVec {
 	pub data: Vec<u8>,
 	pub index: Vec<usize>,
@@ -35,6 +45,7 @@ impl StringVec {
 		let mut data: Vec<u8> = Vec::new();
 		let mut index: Vec<usize> = Vec::new();
 		let mut curr_idx = 0;
+		let max = arg_max(task);
 
 		for i in (0..).step_by(size_of::<usize>()) {
 			let argp = verify_ptr::<usize>(argv_ptr + i, task)?;
@@ -43,7 +54,7 @@ impl StringVec {
 			}
 
 			let string = verify_string(*argp, task, MAX_PAGE_PER_ARG * PAGE_SIZE)?;
-			if string.len() + data.len() > MAX_PAGE_PER_ARGV * PAGE_SIZE {
+			if string.len() + data.len() > max {
 				return Err(Errno::E2BIG);
 			}
 			data.extend(string);
@@ -60,4 +71,27 @@ impl StringVec {
 	pub fn len(&self) -> usize {
 		self.index.len()
 	}
+
+	/// keep only strings for which `f` returns true.
+	pub fn retain(&mut self, f: impl Fn(&[u8]) -> bool) {
+		let mut data = Vec::new();
+		let mut index = Vec::new();
+
+		for &start in self.index.iter() {
+			let len = self.data[start..]
+				.iter()
+				.position(|c| *c == b'\0')
+				.unwrap_or(self.data.len() - start);
+			let string = &self.data[start..start + len];
+
+			if f(string) {
+				index.push(data.len());
+				data.extend_from_slice(string);
+				data.push(b'\0');
+			}
+		}
+
+		self.data = data;
+		self.index = index;
+	}
 }
@@ -5,6 +5,7 @@ use core::slice::{from_raw_parts, from_raw_parts_mut};
 use alloc::sync::Arc;
 
 use crate::config::PATH_MAX;
+use crate::mm::user::memory::{expand_stack, prefault};
 use crate::mm::user::vma::{AreaFlag, UserAddressSpace};
 use crate::process::task::Task;
 use crate::syscall::errno::Errno;
@@ -27,8 +28,12 @@ fn query_max_readable_len(start: usize, vma: &UserAddressSpace, limit: usize) ->
 	curr - start
 }
 
-fn user_strlen(path: usize, vma: &UserAddressSpace, limit: usize) -> Result<usize, Errno> {
-	let max_len = query_max_readable_len(path, vma, limit);
+fn user_strlen(path: usize, task: &Arc<Task>, limit: usize) -> Result<usize, Errno> {
+	let user_ext = task.get_user_ext().expect("must be user process");
+
+	let max_len = query_max_readable_len(path, user_ext.lock_memory().get_vma(), limit);
+
+	prefault(user_ext, path, max_len, false)?;
 
 	let length = (0..max_len)
 		.map(|i| (path + i) as *const u8)
@@ -39,14 +44,7 @@ fn user_strlen(path: usize, vma: &UserAddressSpace, limit: usize) -> Result<usiz
 }
 
 pub fn verify_path(path: usize, task: &Arc<Task>) -> Result<&'_ [u8], Errno> {
-	let memory = task
-		.get_user_ext()
-		.expect("must be user process")
-		.lock_memory();
-
-	let vma = memory.get_vma();
-
-	let length = user_strlen(path, vma, PATH_MAX)?;
+	let length = user_strlen(path, task, PATH_MAX)?;
 
 	if length == 0 {
 		return Err(Errno::EINVAL);
@@ -56,14 +54,7 @@ pub fn verify_path(path: usize, task: &Arc<Task>) -> Result<&'_ [u8], Errno> {
 }
 
 pub fn verify_string(string: usize, task: &Arc<Task>, limit: usize) -> Result<&'_ [u8], Errno> {
-	let memory = task
-		.get_user_ext()
-		.expect("must be user process")
-		.lock_memory();
-
-	let vma = memory.get_vma();
-
-	let length = user_strlen(string, vma, limit)?;
+	let length = user_strlen(string, task, limit)?;
 
 	Ok(unsafe { from_raw_parts(string as *const u8, length) })
 }
@@ -74,15 +65,17 @@ pub fn verify_region(
 	task: &Arc<Task>,
 	flags: AreaFlag,
 ) -> Result<(), Errno> {
-	let memory = task
-		.get_user_ext()
-		.expect("must be user process")
-		.lock_memory();
-	if !memory.query_flags_range(buf_ptr, len, flags) {
+	let user_ext = task.get_user_ext().expect("must be user process");
+
+	// buffer can be placed on stack which is not expanded yet.
+	expand_stack(user_ext, buf_ptr);
+
+	if !user_ext.lock_memory().query_flags_range(buf_ptr, len, flags) {
 		return Err(Errno::EFAULT);
 	}
 
-	Ok(())
+	// kernel must not fault on user memory. (it can be accessed while holding locks)
+	prefault(user_ext, buf_ptr, len, flags.contains(AreaFlag::Writable))
 }
 
 pub fn verify_buffer_mut(
@@ -1,8 +1,14 @@
-use core::{alloc::AllocError, cmp::Ordering, ops::Range};
+use core::{
+	alloc::AllocError,
+	cmp::{max, Ordering},
+	iter::once,
+	ops::Range,
+};
 
 use alloc::vec::Vec;
 use bitflags::bitflags;
 
+use crate::config::USTACK_GUARD_PAGES;
 use crate::mm::{constant::*, page::PageFlag};
 
 bitflags! {
@@ -11,15 +17,38 @@ bitflags! {
 		const Readable = (1 << 0);
 		const Writable = (1 << 1);
 		const Shared = (1 << 2);
+		const Executable = (1 << 3);
+		/// area is expanded downward on fault just below it. (stack)
+		const GrowsDown = (1 << 4);
+		/// unmapped guard gap is kept below the area.
+		const StackGuard = (1 << 5);
 	}
 }
 
-impl Into<PageFlag> for AreaFlag {
-	fn into(self) -> PageFlag {
-		if self.contains(AreaFlag::Writable) {
+impl AreaFlag {
+	/// flags which are not changed by `mprotect`.
+	pub const KEEP_ON_PROTECT: Self = Self::Shared.union(Self::GrowsDown).union(Self::StackGuard);
+}
+
+/// size of unmapped gap that must be kept below area with `flags`.
+fn guard_gap(flags: AreaFlag) -> usize {
+	match flags.contains(AreaFlag::StackGuard) {
+		true => USTACK_GUARD_PAGES * PAGE_SIZE,
+		false => 0,
+	}
+}
+
+/// note: i686 paging (non-PAE) has no NX bit.
+/// so `Executable` is tracked only in area, and executable page is readable.
+impl From<AreaFlag> for PageFlag {
+	fn from(value: AreaFlag) -> Self {
+		if value.contains(AreaFlag::Writable) {
 			PageFlag::USER_RDWR
-		} else {
+		} else if value.intersects(AreaFlag::Readable | AreaFlag::Executable) {
 			PageFlag::USER_RDONLY
+		} else {
+			// PROT_NONE: keep page present for kernel, but user cannot access it.
+			PageFlag::Present
 		}
 	}
 }
@@ -83,6 +112,11 @@ impl Area {
 		}
 	}
 
+	/// lowest address that can be used by area below this one.
+	fn guarded_start(&self) -> usize {
+		self.start - guard_gap(self.flags)
+	}
+
 	pub fn iter_pages(&self) -> AreaPageIter {
 		let nr_pages = (self.end - self.start) / PAGE_SIZE;
 
@@ -93,16 +127,25 @@ impl Area {
 	}
 }
 
-const VALID_USER_AREA: Range<usize> = PT_COVER_SIZE..VM_OFFSET;
+pub const VALID_USER_AREA: Range<usize> = PT_COVER_SIZE..VM_OFFSET;
 
 #[derive(Clone)]
 pub struct UserAddressSpace {
 	areas: Vec<Area>,
+	mmap_base: usize,
 }
 
 impl UserAddressSpace {
 	pub fn new() -> Self {
-		Self { areas: Vec::new() }
+		Self {
+			areas: Vec::new(),
+			mmap_base: VALID_USER_AREA.start,
+		}
+	}
+
+	/// free areas are searched from `base` first. (see `allocate_area`)
+	pub fn set_mmap_base(&mut self, base: usize) {
+		self.mmap_base = base;
 	}
 
 	pub fn find_area(&self, addr: usize) -> Option<&Area> {
@@ -136,13 +179,13 @@ impl UserAddressSpace {
 		let l_area_end = if r_area_idx == 0 {
 			VALID_USER_AREA.start
 		} else {
-			self.areas[r_area_idx - 1].end
+			self.areas[r_area_idx - 1].end + guard_gap(flags)
 		};
 
 		let r_area_start = if r_area_idx == self.areas.len() {
 			VALID_USER_AREA.end
 		} else {
-			self.areas[r_area_idx].start
+			self.areas[r_area_idx].guarded_start()
 		};
 
 		if l_area_end <= start && end <= r_area_start {
@@ -153,26 +196,36 @@ impl UserAddressSpace {
 		}
 	}
 
-	pub fn allocate_area(&mut self, count: usize, flags: AreaFlag) -> Result<usize, AllocError> {
-		let size = count.checked_mul(PAGE_SIZE).ok_or(AllocError)?;
+	/// find the lowest free range of `size` bytes above `base`.
+	///
+	/// returns index where new area will be inserted, and start address of it.
+	fn find_free_range(&self, size: usize, flags: AreaFlag, base: usize) -> Option<(usize, usize)> {
+		let gap = guard_gap(flags);
 
-		let l = Some(VALID_USER_AREA.start)
-			.iter()
-			.chain(self.areas.iter().map(|area| &area.end));
+		let l = once(VALID_USER_AREA.start).chain(self.areas.iter().map(|area| area.end + gap));
 
 		let r = self
 			.areas
 			.iter()
-			.map(|area| &area.start)
-			.chain(Some(VALID_USER_AREA.end).iter());
+			.map(|area| area.guarded_start())
+			.chain(once(VALID_USER_AREA.end));
 
-		let (idx, (start, _)) = l
-			.zip(r)
+		l.zip(r)
+			.map(|(start, end)| (max(start, base), end))
 			.enumerate()
-			.find(|(_, (start, end))| *end - *start >= size)
-			.ok_or(AllocError)?;
+			.find(|(_, (start, end))| end.saturating_sub(*start) >= size)
+			.map(|(idx, (start, _))| (idx, start))
+	}
 
-		let start = *start;
+	/// allocate area anywhere. search starts from mmap base,
+	/// and falls back to the whole user space.
+	pub fn allocate_area(&mut self, count: usize, flags: AreaFlag) -> Result<usize, AllocError> {
+		let size = count.checked_mul(PAGE_SIZE).ok_or(AllocError)?;
+
+		let (idx, start) = self
+			.find_free_range(size, flags, self.mmap_base)
+			.or_else(|| self.find_free_range(size, flags, VALID_USER_AREA.start))
+			.ok_or(AllocError)?;
 
 		self.areas
 			.insert(idx, Area::new(start, start + PAGE_SIZE * count, flags));
@@ -192,6 +245,124 @@ impl UserAddressSpace {
 	pub fn get_areas(&self) -> &Vec<Area> {
 		&self.areas
 	}
+
+	/// split area that contains `addr` into [start, addr) and [addr, end).
+	///
+	/// nothing happens if `addr` is not mapped or already a boundary of area.
+	pub fn split_area(&mut self, addr: usize) {
+		debug_assert!(addr % PAGE_SIZE == 0, "split_area: misaligned address");
+
+		let idx = match self.areas.binary_search_by(|x| x.cmp_addr(addr)) {
+			Ok(idx) => idx,
+			Err(_) => return,
+		};
+
+		let area = &mut self.areas[idx];
+		if area.start == addr {
+			return;
+		}
+
+		let tail = Area::new(addr, area.end, area.flags);
+		area.end = addr;
+
+		self.areas.insert(idx + 1, tail);
+	}
+
+	/// merge area that starts at `addr` into its left neighbor.
+	///
+	/// two areas are merged only if they are adjacent and have same flags.
+	pub fn merge_area(&mut self, addr: usize) -> bool {
+		let idx = match self.areas.binary_search_by(|x| x.start.cmp(&addr)) {
+			Ok(idx) if idx > 0 => idx,
+			_ => return false,
+		};
+
+		let (l, r) = (&self.areas[idx - 1], &self.areas[idx]);
+		if l.end != r.start || l.flags != r.flags {
+			return false;
+		}
+
+		let r = self.areas.remove(idx);
+		self.areas[idx - 1].end = r.end;
+
+		true
+	}
+
+	/// resize area that starts at `area_start` to `count` pages in place.
+	///
+	/// fails if the area would overlap its right neighbor or kernel space.
+	pub fn resize_area(&mut self, area_start: usize, count: usize) -> Result<(), AllocError> {
+		let idx = self
+			.areas
+			.binary_search_by(|x| x.start.cmp(&area_start))
+			.map_err(|_| AllocError)?;
+
+		let end = count
+			.checked_mul(PAGE_SIZE)
+			.and_then(|x| x.checked_add(area_start))
+			.filter(|end| *end > area_start)
+			.ok_or(AllocError)?;
+
+		let r_area_start = match self.areas.get(idx + 1) {
+			Some(area) => area.guarded_start(),
+			None => VALID_USER_AREA.end,
+		};
+
+		if end > r_area_start {
+			return Err(AllocError);
+		}
+
+		self.areas[idx].end = end;
+
+		Ok(())
+	}
+
+	/// find the lowest area above unmapped address `addr`.
+	pub fn find_next_area(&self, addr: usize) -> Option<&Area> {
+		match self.areas.binary_search_by(|x| x.cmp_addr(addr)) {
+			Ok(_) => None,
+			Err(idx) => self.areas.get(idx),
+		}
+	}
+
+	/// expand area that starts at `area_start` downward to `new_start`.
+	///
+	/// fails if guard gap of the area cannot be kept from its left neighbor.
+	pub fn expand_area_down(
+		&mut self,
+		area_start: usize,
+		new_start: usize,
+	) -> Result<(), AllocError> {
+		let idx = self
+			.areas
+			.binary_search_by(|x| x.start.cmp(&area_start))
+			.map_err(|_| AllocError)?;
+
+		let l_area_end = match idx {
+			0 => VALID_USER_AREA.start,
+			_ => self.areas[idx - 1].end + guard_gap(self.areas[idx].flags),
+		};
+
+		if new_start < l_area_end || area_start < new_start {
+			return Err(AllocError);
+		}
+
+		self.areas[idx].start = new_start;
+
+		Ok(())
+	}
+
+	/// iterate areas which overlap with [start, end).
+	pub fn areas_in_range_mut(
+		&mut self,
+		start: usize,
+		end: usize,
+	) -> impl Iterator<Item = &mut Area> {
+		self.areas
+			.iter_mut()
+			.skip_while(move |area| area.end <= start)
+			.take_while(move |area| area.start < end)
+	}
 }
 
 mod test {
@@ -324,4 +495,110 @@ mod test {
 			.unwrap();
 		us.deallocate_area(temp);
 	}
+
+	#[ktest(uvma)]
+	fn split_merge() {
+		let mut us = UserAddressSpace::new();
+		let rw = AreaFlag::Readable | AreaFlag::Writable;
+
+		let start = us.allocate_fixed_area(0xb000_0000, 4, rw).unwrap();
+
+		// boundary and unmapped address do nothing.
+		us.split_area(start);
+		us.split_area(start + 4 * PAGE_SIZE);
+		assert_eq!(us.get_areas().len(), 1);
+
+		us.split_area(start + PAGE_SIZE);
+		us.split_area(start + 3 * PAGE_SIZE);
+		assert_eq!(us.get_areas().len(), 3);
+
+		let mid = us.find_area(start + PAGE_SIZE).unwrap();
+		assert!(mid.start == start + PAGE_SIZE && mid.end == start + 3 * PAGE_SIZE);
+
+		for area in us.areas_in_range_mut(start + PAGE_SIZE, start + 3 * PAGE_SIZE) {
+			area.flags = AreaFlag::Readable;
+		}
+		assert!(us.query_flag(start, AreaFlag::Writable));
+		assert!(!us.query_flag(start + 2 * PAGE_SIZE, AreaFlag::Writable));
+		assert!(us.query_flag(start + 3 * PAGE_SIZE, AreaFlag::Writable));
+
+		// different flags cannot be merged.
+		assert!(!us.merge_area(start + PAGE_SIZE));
+
+		for area in us.areas_in_range_mut(start + PAGE_SIZE, start + 3 * PAGE_SIZE) {
+			area.flags = rw;
+		}
+		assert!(us.merge_area(start + PAGE_SIZE));
+		assert!(us.merge_area(start + 3 * PAGE_SIZE));
+
+		let area = us.find_area(start).unwrap();
+		assert!(area.start == start && area.end == start + 4 * PAGE_SIZE);
+		assert_eq!(us.get_areas().len(), 1);
+	}
+
+	#[ktest(uvma)]
+	fn resize() {
+		let mut us = UserAddressSpace::new();
+
+		let start = us
+			.allocate_fixed_area(0xb000_0000, 2, AreaFlag::Readable)
+			.unwrap();
+		us.allocate_fixed_area(0xb000_4000, 2, AreaFlag::Readable)
+			.unwrap();
+
+		// grow until right neighbor.
+		us.resize_area(start, 4).unwrap();
+		assert!(us.query_flag(start + 3 * PAGE_SIZE, AreaFlag::Readable));
+		us.resize_area(start, 5).unwrap_err();
+
+		// empty area is not allowed.
+		us.resize_area(start, 0).unwrap_err();
+
+		us.resize_area(start, 1).unwrap();
+		assert!(us.query_flag(start, AreaFlag::Readable));
+		assert!(!us.query_flag(start + PAGE_SIZE, AreaFlag::Readable));
+
+		// not a start of area.
+		us.resize_area(start + PAGE_SIZE, 1).unwrap_err();
+	}
+
+	#[ktest(uvma)]
+	fn stack_guard() {
+		let mut us = UserAddressSpace::new();
+		let stack = AreaFlag::Readable | AreaFlag::GrowsDown | AreaFlag::StackGuard;
+		let gap = USTACK_GUARD_PAGES * PAGE_SIZE;
+
+		let start = us.allocate_fixed_area(0xb010_0000, 1, stack).unwrap();
+
+		// guard gap below stack cannot be allocated.
+		us.allocate_fixed_area(start - gap, 1, AreaFlag::Readable)
+			.unwrap_err();
+		let below = us
+			.allocate_fixed_area(start - gap - 3 * PAGE_SIZE, 1, AreaFlag::Readable)
+			.unwrap();
+
+		// stack grows down until guard gap.
+		assert!(us
+			.find_next_area(start - PAGE_SIZE)
+			.is_some_and(|a| a.start == start));
+		us.expand_area_down(start, below + PAGE_SIZE).unwrap_err();
+		us.expand_area_down(start, below + PAGE_SIZE + gap).unwrap();
+		assert!(us.query_flag(below + PAGE_SIZE + gap, AreaFlag::GrowsDown));
+		assert!(!us.query_flag(below + PAGE_SIZE, AreaFlag::Readable));
+		assert!(us.find_next_area(start).is_none());
+	}
+
+	#[ktest(uvma)]
+	fn mmap_base() {
+		let mut us = UserAddressSpace::new();
+
+		us.set_mmap_base(0xb000_0000);
+		let start = us.allocate_area(2, AreaFlag::Readable).unwrap();
+		assert_eq!(start, 0xb000_0000);
+
+		// no room above base.
+		us.set_mmap_base(VALID_USER_AREA.end - PAGE_SIZE);
+		let start = us.allocate_area(2, AreaFlag::Readable).unwrap();
+		assert_eq!(start, VALID_USER_AREA.start);
+	}
 }
@@ -94,10 +94,11 @@ fn local_socket_bind(
 
 	let base_entry = lookup_entry_follow(&path, task).and_then(|ent| ent.downcast_dir())?;
 
+	let cred = task.get_cred();
 	let socket_inode = Arc::new(SocketInode::new(
 		Permission::from_bits_truncate(0o755),
-		task.get_uid(),
-		task.get_gid(),
+		cred.fsuid,
+		cred.fsgid,
 	));
 
 	let socket = VfsEntry::new_socket(Arc::new(VfsSocketEntry::new(
@@ -3,7 +3,10 @@ use alloc::sync::Arc;
 use crate::fs::vfs::VfsHandle;
 use crate::mm::user::verify::verify_ptr_mut;
 use crate::net::address::UnknownSocketAddress;
-use crate::process::{fd_table::Fd, task::CURRENT};
+use crate::process::{
+	fd_table::{Fd, FdFlag},
+	task::CURRENT,
+};
 use crate::syscall::errno::Errno;
 
 pub fn sys_accept(socket_fd: usize, addr: usize, addr_len_ptr: usize) -> Result<usize, Errno> {
@@ -41,7 +44,10 @@ pub fn sys_accept(socket_fd: usize, addr: usize, addr_len_ptr: usize) -> Result<
 		.get_user_ext()
 		.expect("must be user process")
 		.lock_fd_table()
-		.alloc_fd(VfsHandle::Socket(Arc::new(vfs_socket_handle)))
+		.alloc_fd(
+			VfsHandle::Socket(Arc::new(vfs_socket_handle)),
+			FdFlag::empty(),
+		)
 		.ok_or(Errno::EMFILE)?;
 
 	Ok(fd.index())
@@ -27,7 +27,7 @@ pub fn sys_recvfrom(
 	use VfsHandle::*;
 	let handle = match get_file(socket_fd)? {
 		Socket(x) => Ok(x),
-		File(_) | Dir(_) => Err(Errno::EBADF),
+		File(_) | Dir(_) | TimerFd(_) => Err(Errno::EBADF),
 	}?;
 
 	let nread = handle.recv_from(&mut addr, buf)?;
@@ -20,7 +20,7 @@ pub fn sys_sendto(
 	use VfsHandle::*;
 	let handle = match get_file(socket_fd)? {
 		Socket(x) => Ok(x),
-		File(_) | Dir(_) => Err(Errno::EBADF),
+		File(_) | Dir(_) | TimerFd(_) => Err(Errno::EBADF),
 	}?;
 
 	handle.send_to(&addr, buf)
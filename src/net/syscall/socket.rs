@@ -3,7 +3,7 @@ use alloc::sync::Arc;
 use crate::fs::vfs::{AccessFlag, IOFlag, VfsHandle, VfsSocketHandle};
 use crate::net::domain::create_socket;
 use crate::net::socket::SocketKind;
-use crate::process::task::CURRENT;
+use crate::process::{fd_table::FdFlag, task::CURRENT};
 use crate::syscall::errno::Errno;
 
 pub fn sys_socket(domain: i32, kind: i32, protocol: i32) -> Result<usize, Errno> {
@@ -23,7 +23,7 @@ pub fn sys_socket(domain: i32, kind: i32, protocol: i32) -> Result<usize, Errno>
 		.get_user_ext()
 		.expect("must be user process")
 		.lock_fd_table()
-		.alloc_fd(handle)
+		.alloc_fd(handle, FdFlag::empty())
 		.ok_or(Errno::EMFILE)
 		.map(|fd| fd.index())
 }
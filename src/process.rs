@@ -1,13 +1,21 @@
+pub mod coredump;
+pub mod cred;
 pub mod exit;
 pub mod fd_table;
 pub mod gid;
+pub mod itimer;
 pub mod kstack;
 pub mod kthread;
+pub mod posix_timer;
 pub mod process_tree;
+pub mod ptrace;
 pub mod relation;
+pub mod rlimit;
+pub mod rusage;
 pub mod set_thread_area;
 pub mod signal;
 pub mod task;
+pub mod thread_group;
 pub mod uid;
 pub mod wait_list;
 
@@ -19,7 +27,9 @@ use self::{
 	task::{Task, CURRENT},
 };
 
-use crate::{user_bin::get_user_elf, util::backtrace::kernel_stack_top};
+use crate::{
+	scheduler::set_idle_task, user_bin::get_user_elf, util::backtrace::kernel_stack_top,
+};
 use alloc::sync::Arc;
 
 static mut INIT_TASK: MaybeUninit<Arc<Task>> = MaybeUninit::uninit();
@@ -29,6 +39,7 @@ pub fn init() {
 	let idle_kstack = unsafe { Stack::from_raw(kernel_stack_top as usize as *mut _) };
 	let idle_task = Task::new_kernel_from_raw(Pid::allocate(), idle_kstack);
 	CURRENT.init(idle_task.clone());
+	set_idle_task(idle_task.clone());
 	unsafe { IDLE_TASK.write(idle_task) };
 
 	let init = get_user_elf("init").expect("invalid INIT elf file");
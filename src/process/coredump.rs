@@ -0,0 +1,412 @@
+//! core dump of a process killed by a signal.
+//!
+//! the dump is an ELF core file. a `PT_NOTE` segment holds registers,
+//! siginfo and auxv of the crashing thread, and a `PT_LOAD` segment follows
+//! for each user memory area.
+
+use core::{mem::size_of, slice::from_raw_parts};
+
+use alloc::{format, sync::Arc, vec, vec::Vec};
+
+use crate::{
+	elf::{ElfHdr, ElfType, ProgramHdr, SegmentFlag, SegmentType},
+	fs::{
+		path::Path,
+		syscall::lookup_or_create,
+		vfs::{AccessFlag, CreationFlag, Entry, FileType, IOFlag, Permission, VfsHandle},
+	},
+	interrupt::InterruptFrame,
+	mm::{
+		alloc::virt::{kmap, kunmap},
+		constant::PAGE_SIZE,
+		user::{
+			memory::prefault,
+			vma::{Area, AreaFlag},
+		},
+		util::next_align,
+	},
+	pr_debug,
+	process::{
+		ptrace::UserRegs,
+		rlimit::Resource,
+		rusage::TimeVal,
+		signal::sig_info::{SigInfo, UserSigInfo},
+		task::{Task, CURRENT},
+	},
+	sync::Locked,
+	syscall::errno::Errno,
+};
+
+/// where the core is written, as `/proc/sys/kernel/core_pattern`.
+///
+/// `%p` is replaced by pid and `%e` by command name. empty means `core`.
+static CORE_PATTERN: Locked<Vec<u8>> = Locked::new(Vec::new());
+
+pub const CORE_PATTERN_MAX: usize = 128;
+
+const NT_PRSTATUS: u32 = 1;
+const NT_AUXV: u32 = 6;
+const NT_SIGINFO: u32 = 0x53494749;
+
+pub fn get_core_pattern() -> Vec<u8> {
+	match CORE_PATTERN.lock().as_slice() {
+		[] => b"core".to_vec(),
+		x => x.to_vec(),
+	}
+}
+
+pub fn set_core_pattern(pattern: &[u8]) -> Result<(), Errno> {
+	if pattern.len() > CORE_PATTERN_MAX {
+		return Err(Errno::EINVAL);
+	}
+
+	*CORE_PATTERN.lock() = pattern.to_vec();
+	Ok(())
+}
+
+fn expand_pattern(pattern: &[u8], pid: usize, cmd: &[u8]) -> Vec<u8> {
+	let name = cmd.rsplit(|c| *c == b'/').next().unwrap_or(cmd);
+
+	let mut path = Vec::new();
+	let mut iter = pattern.iter();
+
+	while let Some(c) = iter.next() {
+		if *c != b'%' {
+			path.push(*c);
+			continue;
+		}
+
+		match iter.next() {
+			Some(b'p') => path.extend_from_slice(format!("{}", pid).as_bytes()),
+			Some(b'e') => path.extend_from_slice(name),
+			Some(b'%') => path.push(b'%'),
+			// unknown specifier is dropped.
+			_ => {}
+		}
+	}
+
+	path
+}
+
+/// `struct elf_prstatus` of i386.
+#[repr(C)]
+struct PrStatus {
+	signo: i32,
+	code: i32,
+	errno: i32,
+	cursig: u16,
+	_pad: u16,
+	sigpend: usize,
+	sighold: usize,
+	pid: i32,
+	ppid: i32,
+	pgrp: i32,
+	sid: i32,
+	utime: TimeVal,
+	stime: TimeVal,
+	cutime: TimeVal,
+	cstime: TimeVal,
+	regs: UserRegs,
+	fpvalid: i32,
+}
+
+fn as_bytes<T>(value: &T) -> &[u8] {
+	unsafe { from_raw_parts(value as *const T as *const u8, size_of::<T>()) }
+}
+
+fn push_note(buf: &mut Vec<u8>, kind: u32, desc: &[u8]) {
+	// "CORE" with nul, padded to 4 bytes.
+	const NAME: &[u8; 8] = b"CORE\0\0\0\0";
+
+	buf.extend_from_slice(&5u32.to_ne_bytes());
+	buf.extend_from_slice(&(desc.len() as u32).to_ne_bytes());
+	buf.extend_from_slice(&kind.to_ne_bytes());
+	buf.extend_from_slice(NAME);
+	buf.extend_from_slice(desc);
+	buf.resize(next_align(buf.len(), 4), 0);
+}
+
+fn notes(current: &Arc<Task>, info: &SigInfo, frame: &InterruptFrame) -> Vec<u8> {
+	let ext = current.get_user_ext().expect("user task");
+	let usage = *current.lock_usage();
+	let children_usage = ext.get_thread_group().get_children_usage();
+
+	let status = PrStatus {
+		signo: info.num as i32,
+		code: info.code.as_raw(),
+		errno: 0,
+		cursig: info.num as u16,
+		_pad: 0,
+		sigpend: ext.signal.pending().bits() as usize,
+		sighold: ext.signal.lock_mask().bits() as usize,
+		pid: current.get_pid().as_raw() as i32,
+		ppid: current.get_ppid().as_raw() as i32,
+		pgrp: current.get_pgid().as_raw() as i32,
+		sid: current.get_sid().as_raw() as i32,
+		utime: TimeVal::from_ticks(usage.utime),
+		stime: TimeVal::from_ticks(usage.stime),
+		cutime: TimeVal::from_ticks(children_usage.utime),
+		cstime: TimeVal::from_ticks(children_usage.stime),
+		regs: UserRegs::new(frame, !0),
+		fpvalid: 0,
+	};
+
+	let siginfo = UserSigInfo::from(info);
+	let auxv = ext.lock_memory().get_auxv();
+
+	let mut buf = Vec::new();
+	push_note(&mut buf, NT_PRSTATUS, as_bytes(&status));
+	push_note(&mut buf, NT_SIGINFO, as_bytes(&siginfo));
+	push_note(&mut buf, NT_AUXV, unsafe {
+		from_raw_parts(auxv.as_ptr() as *const u8, auxv.len() * size_of::<usize>())
+	});
+
+	buf
+}
+
+/// core file being written, up to `RLIMIT_CORE`.
+struct CoreFile {
+	handle: VfsHandle,
+	written: usize,
+	limit: usize,
+}
+
+impl CoreFile {
+	/// `EFBIG` when the limit is reached. (the dump is truncated there)
+	fn write(&mut self, buf: &[u8]) -> Result<(), Errno> {
+		let len = buf.len().min(self.limit - self.written);
+
+		let mut done = 0;
+		while done < len {
+			match self.handle.write(&buf[done..len])? {
+				0 => return Err(Errno::ENOSPC),
+				x => done += x,
+			}
+		}
+		self.written += len;
+
+		match len == buf.len() {
+			true => Ok(()),
+			false => Err(Errno::EFBIG),
+		}
+	}
+}
+
+fn open_core(current: &Arc<Task>, limit: usize) -> Result<CoreFile, Errno> {
+	let path = expand_pattern(
+		&get_core_pattern(),
+		current.get_tgid().as_raw(),
+		&current.lock_cmd(),
+	);
+
+	// existing file is overwritten only if it is a regular file of the dumping user,
+	// not to be tricked into a file of others by a planted link.
+	let entry = lookup_or_create(
+		Path::new(&path),
+		CreationFlag::O_CREAT | CreationFlag::O_NOFOLLOW,
+		Permission::from_bits_truncate(0o600),
+		current,
+	)?;
+
+	let stat = entry.statx()?;
+	if stat.get_type() != FileType::Regular || stat.uid != current.get_cred().fsuid {
+		return Err(Errno::EPERM);
+	}
+
+	let file = entry.clone().downcast_file()?;
+	file.truncate(0, current)?;
+
+	let handle = entry.open(IOFlag::empty(), AccessFlag::O_WRONLY, current)?;
+
+	Ok(CoreFile {
+		handle,
+		written: 0,
+		limit,
+	})
+}
+
+fn program_header(p_type: u32, offset: usize, area: &Area) -> ProgramHdr {
+	let mut flags = SegmentFlag::empty();
+	flags.set(SegmentFlag::READ, area.flags.contains(AreaFlag::Readable));
+	flags.set(SegmentFlag::WRITE, area.flags.contains(AreaFlag::Writable));
+	flags.set(
+		SegmentFlag::EXECUTE,
+		area.flags.contains(AreaFlag::Executable),
+	);
+
+	let size = area.end - area.start;
+
+	ProgramHdr {
+		p_type,
+		p_offset: offset,
+		p_vaddr: area.start,
+		p_paddr: 0,
+		// not readable area is only described.
+		p_filesz: match area.flags.contains(AreaFlag::Readable) {
+			true => size,
+			false => 0,
+		},
+		p_memsz: size,
+		p_flags: flags.bits(),
+		p_align: PAGE_SIZE as u32,
+	}
+}
+
+fn header(nr_phdrs: usize) -> ElfHdr {
+	let mut ident = [0; 16];
+	// magic, 32-bit, little endian, version 1.
+	ident[..7].copy_from_slice(b"\x7fELF\x01\x01\x01");
+
+	ElfHdr {
+		e_ident: ident,
+		e_type: ElfType::CORE,
+		e_machine: 3, // EM_386
+		e_version: 1,
+		e_entry: 0,
+		e_phoff: size_of::<ElfHdr>(),
+		e_shoff: 0,
+		e_flags: 0,
+		e_ehsize: size_of::<ElfHdr>() as u16,
+		e_phentsize: size_of::<ProgramHdr>() as u16,
+		e_phnum: nr_phdrs as u16,
+		e_shentsize: 0,
+		e_shnum: 0,
+		e_shstrndx: 0,
+	}
+}
+
+/// copy a page of user memory into `buf`. not accessible page reads as zero.
+///
+/// untouched anonymous page is not allocated, but reads as zero as well.
+fn read_page(current: &Arc<Task>, vaddr: usize, buf: &mut [u8]) {
+	let ext = current.get_user_ext().expect("user task");
+
+	buf.fill(0);
+
+	if !ext.lock_memory().has_contents(vaddr) {
+		return;
+	}
+
+	if prefault(ext, vaddr, PAGE_SIZE, false).is_err() {
+		return;
+	}
+
+	let Some(paddr) = ext.lock_memory().get_pd().lookup(vaddr) else {
+		return;
+	};
+
+	if let Ok(page) = kmap(paddr) {
+		unsafe {
+			buf.as_mut_ptr()
+				.copy_from_nonoverlapping(page.as_ptr(), PAGE_SIZE)
+		};
+		kunmap(page.as_ptr() as usize);
+	}
+}
+
+fn write_core(
+	file: &mut CoreFile,
+	current: &Arc<Task>,
+	info: &SigInfo,
+	frame: &InterruptFrame,
+) -> Result<(), Errno> {
+	let ext = current.get_user_ext().expect("user task");
+	let areas = ext.lock_memory().get_vma().get_areas().clone();
+
+	let notes = notes(current, info, frame);
+	let nr_phdrs = areas.len() + 1;
+
+	let notes_offset = size_of::<ElfHdr>() + nr_phdrs * size_of::<ProgramHdr>();
+	let mut offset = next_align(notes_offset + notes.len(), PAGE_SIZE);
+
+	let mut buf = Vec::new();
+	buf.extend_from_slice(as_bytes(&header(nr_phdrs)));
+
+	let note = ProgramHdr {
+		p_type: SegmentType::NOTE,
+		p_offset: notes_offset,
+		p_vaddr: 0,
+		p_paddr: 0,
+		p_filesz: notes.len(),
+		p_memsz: 0,
+		p_flags: 0,
+		p_align: 4,
+	};
+	buf.extend_from_slice(as_bytes(&note));
+
+	for area in areas.iter() {
+		let phdr = program_header(SegmentType::LOAD, offset, area);
+		buf.extend_from_slice(as_bytes(&phdr));
+		offset += phdr.p_filesz;
+	}
+
+	buf.extend_from_slice(&notes);
+	buf.resize(next_align(buf.len(), PAGE_SIZE), 0);
+	file.write(&buf)?;
+
+	let mut page = vec![0; PAGE_SIZE];
+	for area in areas
+		.iter()
+		.filter(|a| a.flags.contains(AreaFlag::Readable))
+	{
+		for vaddr in area.iter_pages() {
+			read_page(current, vaddr, &mut page);
+			file.write(&page)?;
+		}
+	}
+
+	Ok(())
+}
+
+/// dump core of current process, killed by `info`.
+///
+/// other threads must be gone already. returns true if core was written.
+pub fn do_coredump(info: &SigInfo, frame: &InterruptFrame) -> bool {
+	let current = unsafe { CURRENT.get_ref() };
+
+	let limit = current
+		.get_user_ext()
+		.map_or(0, |ext| ext.lock_rlimits().get(Resource::Core).cur);
+
+	// credentials changed at exec. (e.g. set-user-ID program)
+	let dumpable = current.get_user_ext().is_some_and(|ext| ext.is_dumpable());
+
+	if limit == 0 || !dumpable {
+		return false;
+	}
+
+	let result = open_core(current, limit).and_then(|mut file| {
+		let result = write_core(&mut file, current, info, frame);
+		let _ = file.handle.close();
+		result
+	});
+
+	pr_debug!("{:?}: core dump: {:?}", current.get_pid(), result);
+
+	// truncated by the limit, but still written.
+	matches!(result, Ok(_) | Err(Errno::EFBIG))
+}
+
+mod test {
+	use super::*;
+	use kfs_macro::ktest;
+
+	#[ktest(coredump)]
+	fn pattern_expansion() {
+		assert_eq!(expand_pattern(b"core", 3, b"sh"), b"core");
+		assert_eq!(expand_pattern(b"core.%p", 42, b"sh"), b"core.42");
+		assert_eq!(
+			expand_pattern(b"/tmp/%e-%p%%", 7, b"/bin/sh"),
+			b"/tmp/sh-7%"
+		);
+		assert_eq!(expand_pattern(b"a%zb", 1, b"sh"), b"ab");
+	}
+
+	#[ktest(coredump)]
+	fn linux_layout() {
+		assert_eq!(size_of::<ElfHdr>(), 52);
+		assert_eq!(size_of::<ProgramHdr>(), 32);
+		assert_eq!(size_of::<PrStatus>(), 144);
+		assert_eq!(size_of::<UserSigInfo>(), 128);
+	}
+}
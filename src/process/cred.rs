@@ -0,0 +1,417 @@
+use alloc::vec::Vec;
+
+use crate::{
+	mm::user::verify::{verify_array, verify_array_mut, verify_ptr_mut},
+	process::task::CURRENT,
+	syscall::errno::Errno,
+};
+
+/// maximum number of supplementary groups.
+pub const NGROUPS_MAX: usize = 32;
+
+/// user and group ids of a task.
+///
+/// effective ids decide privilege, fs ids decide file access (they follow
+/// effective ids unless set on their own), and saved ids let an unprivileged
+/// task switch back to an id it dropped.
+#[derive(Debug, Clone)]
+pub struct Credentials {
+	pub uid: usize,
+	pub euid: usize,
+	pub suid: usize,
+	pub fsuid: usize,
+	pub gid: usize,
+	pub egid: usize,
+	pub sgid: usize,
+	pub fsgid: usize,
+	pub groups: Vec<usize>,
+}
+
+/// `-1` leaves the id unchanged.
+fn id_arg(raw: usize) -> Option<usize> {
+	match raw as u32 {
+		u32::MAX => None,
+		x => Some(x as usize),
+	}
+}
+
+impl Credentials {
+	pub const fn root() -> Self {
+		Self {
+			uid: 0,
+			euid: 0,
+			suid: 0,
+			fsuid: 0,
+			gid: 0,
+			egid: 0,
+			sgid: 0,
+			fsgid: 0,
+			groups: Vec::new(),
+		}
+	}
+
+	pub fn is_privileged(&self) -> bool {
+		self.euid == 0
+	}
+
+	/// `gid` is the fs group id or one of supplementary groups.
+	pub fn in_group(&self, gid: usize) -> bool {
+		self.fsgid == gid || self.groups.contains(&gid)
+	}
+
+	/// effective uid matches real or effective uid of `target`.
+	/// (scheduling parameters, affinity)
+	pub fn is_owner_of(&self, target: &Credentials) -> bool {
+		self.is_privileged() || self.euid == target.uid || self.euid == target.euid
+	}
+
+	/// real or effective uid matches real or saved uid of `target`.
+	pub fn can_signal(&self, target: &Credentials) -> bool {
+		self.is_privileged()
+			|| [self.uid, self.euid]
+				.iter()
+				.any(|x| target.uid == *x || target.suid == *x)
+	}
+
+	/// every id of `target` is the real id of this task.
+	pub fn can_trace(&self, target: &Credentials) -> bool {
+		let uid = [target.uid, target.euid, target.suid];
+		let gid = [target.gid, target.egid, target.sgid];
+
+		self.is_privileged()
+			|| (uid.iter().all(|x| *x == self.uid) && gid.iter().all(|x| *x == self.gid))
+	}
+
+	fn is_uid(&self, uid: usize) -> bool {
+		self.uid == uid || self.euid == uid || self.suid == uid
+	}
+
+	fn is_gid(&self, gid: usize) -> bool {
+		self.gid == gid || self.egid == gid || self.sgid == gid
+	}
+
+	/// privileged task sets every uid. others only switch effective uid
+	/// to its real or saved uid.
+	pub fn setuid(&mut self, uid: usize) -> Result<(), Errno> {
+		if self.is_privileged() {
+			self.uid = uid;
+			self.suid = uid;
+		} else if uid != self.uid && uid != self.suid {
+			return Err(Errno::EPERM);
+		}
+
+		self.euid = uid;
+		self.fsuid = uid;
+		Ok(())
+	}
+
+	pub fn setgid(&mut self, gid: usize) -> Result<(), Errno> {
+		if self.is_privileged() {
+			self.gid = gid;
+			self.sgid = gid;
+		} else if gid != self.gid && gid != self.sgid {
+			return Err(Errno::EPERM);
+		}
+
+		self.egid = gid;
+		self.fsgid = gid;
+		Ok(())
+	}
+
+	/// saved uid follows new effective uid, if real uid is set
+	/// or effective uid is set to other than previous real uid.
+	pub fn setreuid(&mut self, ruid: Option<usize>, euid: Option<usize>) -> Result<(), Errno> {
+		if !self.is_privileged() {
+			let ruid_ok = ruid.map_or(true, |x| x == self.uid || x == self.euid);
+			let euid_ok = euid.map_or(true, |x| self.is_uid(x));
+
+			if !ruid_ok || !euid_ok {
+				return Err(Errno::EPERM);
+			}
+		}
+
+		let old_uid = self.uid;
+		let new_euid = euid.unwrap_or(self.euid);
+
+		if ruid.is_some() || euid.is_some_and(|x| x != old_uid) {
+			self.suid = new_euid;
+		}
+
+		self.uid = ruid.unwrap_or(self.uid);
+		self.euid = new_euid;
+		self.fsuid = new_euid;
+		Ok(())
+	}
+
+	pub fn setregid(&mut self, rgid: Option<usize>, egid: Option<usize>) -> Result<(), Errno> {
+		if !self.is_privileged() {
+			let rgid_ok = rgid.map_or(true, |x| x == self.gid || x == self.egid);
+			let egid_ok = egid.map_or(true, |x| self.is_gid(x));
+
+			if !rgid_ok || !egid_ok {
+				return Err(Errno::EPERM);
+			}
+		}
+
+		let old_gid = self.gid;
+		let new_egid = egid.unwrap_or(self.egid);
+
+		if rgid.is_some() || egid.is_some_and(|x| x != old_gid) {
+			self.sgid = new_egid;
+		}
+
+		self.gid = rgid.unwrap_or(self.gid);
+		self.egid = new_egid;
+		self.fsgid = new_egid;
+		Ok(())
+	}
+
+	/// unprivileged task sets each id to one of its current ids.
+	pub fn setresuid(
+		&mut self,
+		ruid: Option<usize>,
+		euid: Option<usize>,
+		suid: Option<usize>,
+	) -> Result<(), Errno> {
+		let allowed = |x: Option<usize>| x.map_or(true, |x| self.is_uid(x));
+
+		if !self.is_privileged() && ![ruid, euid, suid].into_iter().all(allowed) {
+			return Err(Errno::EPERM);
+		}
+
+		self.uid = ruid.unwrap_or(self.uid);
+		self.euid = euid.unwrap_or(self.euid);
+		self.suid = suid.unwrap_or(self.suid);
+		self.fsuid = self.euid;
+		Ok(())
+	}
+
+	pub fn setresgid(
+		&mut self,
+		rgid: Option<usize>,
+		egid: Option<usize>,
+		sgid: Option<usize>,
+	) -> Result<(), Errno> {
+		let allowed = |x: Option<usize>| x.map_or(true, |x| self.is_gid(x));
+
+		if !self.is_privileged() && ![rgid, egid, sgid].into_iter().all(allowed) {
+			return Err(Errno::EPERM);
+		}
+
+		self.gid = rgid.unwrap_or(self.gid);
+		self.egid = egid.unwrap_or(self.egid);
+		self.sgid = sgid.unwrap_or(self.sgid);
+		self.fsgid = self.egid;
+		Ok(())
+	}
+
+	/// set-user-ID (set-group-ID) program runs with owner (group) of its file
+	/// as effective id. saved ids follow effective ids in any case.
+	pub fn exec(&mut self, owner: Option<usize>, group: Option<usize>) {
+		self.euid = owner.unwrap_or(self.euid);
+		self.egid = group.unwrap_or(self.egid);
+
+		self.suid = self.euid;
+		self.fsuid = self.euid;
+		self.sgid = self.egid;
+		self.fsgid = self.egid;
+	}
+
+	/// program does not run with ids of whom executed it. (`AT_SECURE`)
+	pub fn is_secure_exec(&self) -> bool {
+		self.uid != self.euid || self.gid != self.egid
+	}
+
+	pub fn set_groups(&mut self, groups: Vec<usize>) -> Result<(), Errno> {
+		if !self.is_privileged() {
+			return Err(Errno::EPERM);
+		}
+
+		if groups.len() > NGROUPS_MAX {
+			return Err(Errno::EINVAL);
+		}
+
+		self.groups = groups;
+		Ok(())
+	}
+}
+
+impl Default for Credentials {
+	fn default() -> Self {
+		Self::root()
+	}
+}
+
+pub fn sys_setreuid(ruid: usize, euid: usize) -> Result<usize, Errno> {
+	let current = unsafe { CURRENT.get_ref() };
+
+	current
+		.lock_cred()
+		.setreuid(id_arg(ruid), id_arg(euid))
+		.map(|_| 0)
+}
+
+pub fn sys_setregid(rgid: usize, egid: usize) -> Result<usize, Errno> {
+	let current = unsafe { CURRENT.get_ref() };
+
+	current
+		.lock_cred()
+		.setregid(id_arg(rgid), id_arg(egid))
+		.map(|_| 0)
+}
+
+pub fn sys_setresuid(ruid: usize, euid: usize, suid: usize) -> Result<usize, Errno> {
+	let current = unsafe { CURRENT.get_ref() };
+
+	current
+		.lock_cred()
+		.setresuid(id_arg(ruid), id_arg(euid), id_arg(suid))
+		.map(|_| 0)
+}
+
+pub fn sys_setresgid(rgid: usize, egid: usize, sgid: usize) -> Result<usize, Errno> {
+	let current = unsafe { CURRENT.get_ref() };
+
+	current
+		.lock_cred()
+		.setresgid(id_arg(rgid), id_arg(egid), id_arg(sgid))
+		.map(|_| 0)
+}
+
+fn write_ids(ptrs: [usize; 3], ids: [usize; 3]) -> Result<usize, Errno> {
+	let current = unsafe { CURRENT.get_ref() };
+
+	for (ptr, id) in ptrs.into_iter().zip(ids) {
+		*verify_ptr_mut::<u32>(ptr, current)? = id as u32;
+	}
+
+	Ok(0)
+}
+
+pub fn sys_getresuid(ruid: usize, euid: usize, suid: usize) -> Result<usize, Errno> {
+	let cred = unsafe { CURRENT.get_ref() }.lock_cred().clone();
+
+	write_ids([ruid, euid, suid], [cred.uid, cred.euid, cred.suid])
+}
+
+pub fn sys_getresgid(rgid: usize, egid: usize, sgid: usize) -> Result<usize, Errno> {
+	let cred = unsafe { CURRENT.get_ref() }.lock_cred().clone();
+
+	write_ids([rgid, egid, sgid], [cred.gid, cred.egid, cred.sgid])
+}
+
+/// with `size` 0, only the number of groups is returned.
+pub fn sys_getgroups(size: usize, list: usize) -> Result<usize, Errno> {
+	let current = unsafe { CURRENT.get_ref() };
+	let groups = current.lock_cred().groups.clone();
+
+	if size == 0 {
+		return Ok(groups.len());
+	}
+
+	if size < groups.len() {
+		return Err(Errno::EINVAL);
+	}
+
+	let list = verify_array_mut::<u32>(list, groups.len(), current)?;
+	for (dst, gid) in list.iter_mut().zip(groups.iter()) {
+		*dst = *gid as u32;
+	}
+
+	Ok(groups.len())
+}
+
+pub fn sys_setgroups(size: usize, list: usize) -> Result<usize, Errno> {
+	let current = unsafe { CURRENT.get_ref() };
+
+	if size > NGROUPS_MAX {
+		return Err(Errno::EINVAL);
+	}
+
+	let groups = match size {
+		0 => Vec::new(),
+		_ => verify_array::<u32>(list, size, current)?
+			.iter()
+			.map(|gid| *gid as usize)
+			.collect(),
+	};
+
+	current.lock_cred().set_groups(groups).map(|_| 0)
+}
+
+mod test {
+	use super::*;
+	use kfs_macro::ktest;
+
+	fn user(uid: usize) -> Credentials {
+		Credentials {
+			uid,
+			euid: uid,
+			suid: uid,
+			fsuid: uid,
+			..Credentials::root()
+		}
+	}
+
+	#[ktest(cred)]
+	fn drop_and_regain_privilege() {
+		let mut cred = Credentials::root();
+
+		// keep root as saved uid, so that it can come back.
+		cred.setresuid(Some(1000), Some(1000), None).unwrap();
+		assert!(!cred.is_privileged());
+		assert_eq!(cred.fsuid, 1000);
+
+		cred.setuid(0).unwrap();
+		assert!(cred.is_privileged());
+
+		// setuid by root drops every uid.
+		cred.setuid(1000).unwrap();
+		assert_eq!((cred.uid, cred.euid, cred.suid), (1000, 1000, 1000));
+		assert!(matches!(cred.setuid(0), Err(Errno::EPERM)));
+	}
+
+	#[ktest(cred)]
+	fn unprivileged_setreuid() {
+		let mut cred = user(1000);
+		cred.suid = 2000;
+
+		// effective uid may switch to saved uid.
+		cred.setreuid(None, Some(2000)).unwrap();
+		assert_eq!((cred.uid, cred.euid, cred.suid), (1000, 2000, 2000));
+
+		assert!(matches!(cred.setreuid(Some(3000), None), Err(Errno::EPERM)));
+		assert!(matches!(cred.set_groups(alloc::vec![1]), Err(Errno::EPERM)));
+	}
+
+	#[ktest(cred)]
+	fn setreuid_keeps_saved_ids() {
+		let mut cred = user(1000);
+		(cred.euid, cred.suid) = (2000, 3000);
+		(cred.egid, cred.sgid) = (2000, 3000);
+
+		// (-1, -1) changes nothing.
+		cred.setreuid(None, None).unwrap();
+		assert_eq!((cred.uid, cred.euid, cred.suid), (1000, 2000, 3000));
+
+		cred.setregid(None, None).unwrap();
+		assert_eq!((cred.gid, cred.egid, cred.sgid), (0, 2000, 3000));
+	}
+
+	#[ktest(cred)]
+	fn exec_setuid_program() {
+		let mut cred = user(1000);
+
+		cred.exec(Some(0), None);
+		assert_eq!((cred.uid, cred.euid, cred.suid), (1000, 0, 0));
+		assert!(cred.is_secure_exec());
+
+		// program may drop back to the invoker and regain root later.
+		cred.setuid(1000).unwrap();
+		assert_eq!((cred.uid, cred.euid, cred.suid), (1000, 1000, 0));
+		cred.setuid(0).unwrap();
+
+		cred.exec(None, None);
+		assert!(!cred.is_secure_exec());
+	}
+}
@@ -1,7 +1,15 @@
+use alloc::{sync::Arc, vec::Vec};
+
 use crate::{
+	mm::user::verify::verify_ptr_mut,
 	pr_debug,
-	process::{signal::sig_num::SigNum, task::CURRENT},
-	scheduler::context::yield_now,
+	process::{
+		ptrace,
+		signal::{notify_parent, sig_code::SigCode, sig_info::SigInfo, sig_num::SigNum},
+		task::{Task, CURRENT},
+	},
+	scheduler::{context::yield_now, futex::wake_child_tid},
+	syscall::errno::Errno,
 };
 
 #[repr(transparent)]
@@ -17,31 +25,175 @@ impl ExitStatus {
 		}
 	}
 
+	/// killed by `termsig` after dumping core.
+	pub fn new_dumped(termsig: u8) -> Self {
+		Self {
+			raw: (termsig & 0x7f) as usize | 0x80,
+		}
+	}
+
 	pub fn new_exited(status: u8) -> Self {
 		Self {
 			raw: (status as usize) << 8,
 		}
 	}
 
+	/// stopped by `sig`, as reported to `waitpid` with `WUNTRACED` or to a tracer.
+	pub fn new_stopped(sig: u8) -> Self {
+		Self {
+			raw: ((sig as usize) << 8) | 0x7f,
+		}
+	}
+
 	pub fn as_raw(&self) -> usize {
 		self.raw
 	}
+
+	/// how the process ended, as reported to the parent by `SIGCHLD`.
+	pub fn sig_code(&self) -> SigCode {
+		match (self.raw & 0x7f, self.raw & 0x80) {
+			(0, _) => SigCode::CLD_EXITED,
+			(_, 0) => SigCode::CLD_KILLED,
+			_ => SigCode::CLD_DUMPED,
+		}
+	}
 }
 
-pub fn sys_exit(status: usize) -> ! {
+/// release the thread waiting on `clear_child_tid`. (pthread_join)
+fn clear_child_tid(current: &Arc<Task>) {
+	let Some(ext) = current.get_user_ext() else {
+		return;
+	};
+
+	let addr = ext.get_clear_child_tid();
+	if addr == 0 {
+		return;
+	}
+
+	if let Ok(tid) = verify_ptr_mut::<u32>(addr, current) {
+		*tid = 0;
+		wake_child_tid(addr);
+	}
+}
+
+fn do_exit(status: ExitStatus) {
 	let current = unsafe { CURRENT.get_mut() };
-	current.exit(ExitStatus::new_exited(status as u8));
+
+	clear_child_tid(current);
+
+	let last = current
+		.get_user_ext()
+		.map_or(true, |ext| ext.get_thread_group().nr_threads() == 1);
+	ptrace::exit_notify(current, last);
+
+	let exit_signal = current
+		.get_user_ext()
+		.filter(|_| last)
+		.map(|ext| ext.get_thread_group())
+		.and_then(|group| {
+			let status = group.get_exit_status().unwrap_or(status);
+			Some((group.get_exit_signal()?, status.sig_code()))
+		});
+
+	current.exit(status);
+
+	// the process is a zombie now, so the parent can reap it on the signal.
+	if let Some((num, code)) = exit_signal {
+		notify_parent(current, num, code);
+	}
+}
+
+/// make every thread in the group exit with `status`.
+fn do_group_exit(status: ExitStatus) {
+	let current = unsafe { CURRENT.get_mut() };
+
+	if let Some(ext) = current.get_user_ext() {
+		let group = ext.get_thread_group();
+
+		if group.set_exit_status(status) {
+			kill_threads(group.others(current.get_pid()));
+		}
+	}
+
+	do_exit(status);
+}
+
+fn kill_threads(threads: Vec<Arc<Task>>) {
+	for thread in threads {
+		let info = SigInfo {
+			num: SigNum::KILL,
+			pid: 0,
+			uid: 0,
+			code: SigCode::SI_KERNEL,
+			value: 0,
+		};
+
+		let _ = thread.recv_signal(info);
+	}
+}
+
+/// make other threads in the group exit, and wait until they are gone.
+/// (exec replaces memory shared with them)
+pub fn kill_other_threads() -> Result<(), Errno> {
+	let current = unsafe { CURRENT.get_ref() };
+	let group = current.user_ext_ok_or(Errno::EPERM)?.get_thread_group();
+
+	if group.nr_threads() == 1 {
+		return Ok(());
+	}
+
+	// someone else is exiting the group. (including this thread)
+	if !group.set_exit_status(ExitStatus::new_signaled(SigNum::KILL as usize as u8)) {
+		return Err(Errno::EINTR);
+	}
+
+	kill_threads(group.others(current.get_pid()));
+
+	while group.nr_threads() > 1 {
+		yield_now();
+	}
+
+	group.clear_exit_status();
+
+	Ok(())
+}
+
+/// exit only the calling thread.
+pub fn sys_exit(status: usize) -> ! {
+	do_exit(ExitStatus::new_exited(status as u8));
 
 	yield_now();
 	unreachable!("cannot scheduled after sys_exit");
 }
 
+pub fn sys_exit_group(status: usize) -> ! {
+	do_group_exit(ExitStatus::new_exited(status as u8));
+
+	yield_now();
+	unreachable!("cannot scheduled after sys_exit_group");
+}
+
 pub fn exit_with_signal(sig: SigNum) -> ! {
 	let current = unsafe { CURRENT.get_mut() };
 
 	pr_debug!("{:?} exit with SIG{:?}", current.get_pid(), sig);
-	current.exit(ExitStatus::new_signaled(sig as usize as u8));
+	do_group_exit(ExitStatus::new_signaled(sig as usize as u8));
 
 	yield_now();
 	unreachable!("cannot scheduled after exit_with_signal");
 }
+
+/// `exit_with_signal` after core of the process was dumped.
+pub fn exit_with_core_dump(sig: SigNum) -> ! {
+	let current = unsafe { CURRENT.get_mut() };
+
+	pr_debug!(
+		"{:?} exit with SIG{:?} (core dumped)",
+		current.get_pid(),
+		sig
+	);
+	do_group_exit(ExitStatus::new_dumped(sig as usize as u8));
+
+	yield_now();
+	unreachable!("cannot scheduled after exit_with_core_dump");
+}
@@ -1,10 +1,21 @@
+use alloc::vec::Vec;
 use core::mem::{replace, take};
-use core::{array, ops::IndexMut};
 
-use crate::fs::vfs::VfsHandle;
+use bitflags::bitflags;
+
+use crate::fs::vfs::{CreationFlag, VfsHandle};
 use crate::syscall::errno::Errno;
 
-pub const FDTABLE_SIZE: usize = 256;
+/// upper bound of descriptors. hard limit of `RLIMIT_NOFILE` cannot exceed this.
+pub const NR_OPEN: usize = 1 << 20;
+
+/// default soft limit of `RLIMIT_NOFILE`.
+pub const NOFILE_CUR: usize = 1024;
+
+/// default hard limit of `RLIMIT_NOFILE`.
+pub const NOFILE_MAX: usize = 4096;
+
+const BITS: usize = usize::BITS as usize;
 
 #[derive(Debug, Clone)]
 pub struct Fd(usize);
@@ -16,80 +27,318 @@ impl Fd {
 	}
 
 	pub fn from(v: usize) -> Option<Self> {
-		(v < FDTABLE_SIZE).then(|| Self(v))
+		(v < NR_OPEN).then_some(Self(v))
+	}
+}
+
+bitflags! {
+	#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+	pub struct FdFlag: usize {
+		const FD_CLOEXEC = 1;
+	}
+}
+
+impl From<CreationFlag> for FdFlag {
+	fn from(value: CreationFlag) -> Self {
+		match value.contains(CreationFlag::O_CLOEXEC) {
+			true => FdFlag::FD_CLOEXEC,
+			false => FdFlag::empty(),
+		}
 	}
 }
 
-pub struct FdTable([Option<VfsHandle>; FDTABLE_SIZE]);
+#[derive(Clone)]
+struct FdEntry {
+	handle: VfsHandle,
+	flags: FdFlag,
+}
+
+/// descriptors of a process. grows on demand up to `limit`.
+pub struct FdTable {
+	files: Vec<Option<FdEntry>>,
+	/// bit is set for each opened descriptor. `files` has `BITS` slots per word.
+	opened: Vec<usize>,
+	/// new descriptors are below this. (`RLIMIT_NOFILE`)
+	limit: usize,
+}
 
 impl FdTable {
 	pub fn new() -> Self {
-		Self(array::from_fn(|_| None))
+		Self {
+			files: Vec::new(),
+			opened: Vec::new(),
+			limit: NOFILE_CUR,
+		}
 	}
 
+	/// closed slots at the end of the table are not copied.
 	pub fn clone_for_fork(&self) -> Self {
-		Self(self.0.clone())
+		let words = self
+			.opened
+			.iter()
+			.rposition(|word| *word != 0)
+			.map_or(0, |i| i + 1);
+
+		Self {
+			files: self.files[..words * BITS].to_vec(),
+			opened: self.opened[..words].to_vec(),
+			limit: self.limit,
+		}
+	}
+
+	pub fn get_limit(&self) -> usize {
+		self.limit
+	}
+
+	/// already opened descriptors above `limit` are kept.
+	pub fn set_limit(&mut self, limit: usize) {
+		self.limit = limit.min(NR_OPEN);
 	}
 
 	pub fn get_file(&self, fd: Fd) -> Option<VfsHandle> {
-		self.0[fd.index()].clone()
+		self.entry(&fd).map(|e| e.handle.clone())
 	}
 
-	pub fn alloc_fd(&mut self, file: VfsHandle) -> Option<Fd> {
-		let (fd, entry) = self
-			.0
-			.iter_mut()
-			.enumerate()
-			.find(|(_, entry)| entry.is_none())?;
+	pub fn get_flags(&self, fd: Fd) -> Result<FdFlag, Errno> {
+		self.entry(&fd).map(|e| e.flags).ok_or(Errno::EBADF)
+	}
+
+	pub fn set_flags(&mut self, fd: Fd, flags: FdFlag) -> Result<(), Errno> {
+		let entry = self
+			.files
+			.get_mut(fd.index())
+			.and_then(|e| e.as_mut())
+			.ok_or(Errno::EBADF)?;
+
+		entry.flags = flags;
+
+		Ok(())
+	}
+
+	pub fn alloc_fd(&mut self, file: VfsHandle, flags: FdFlag) -> Option<Fd> {
+		let fd = self.find_free(0)?;
 
-		*entry = Some(file);
+		self.install(fd, file, flags).ok()?;
 
 		Some(Fd(fd))
 	}
 
 	pub fn close(&mut self, fd: Fd) -> Result<VfsHandle, Errno> {
-		let entry = self.0.index_mut(fd.index());
+		let entry = self.files.get_mut(fd.index()).ok_or(Errno::EBADF)?;
+		let entry = take(entry).ok_or(Errno::EBADF)?;
+
+		self.opened[fd.index() / BITS] &= !(1 << (fd.index() % BITS));
 
-		take(entry).ok_or(Errno::EBADF)
+		Ok(entry.handle)
 	}
 
-	pub fn dup2(&mut self, src: Fd, dst: Fd) -> Result<Option<VfsHandle>, Errno> {
-		let src = self.0[src.index()].clone().ok_or(Errno::EBADF)?;
+	/// returns handle previously opened at `dst`, which must be closed by caller.
+	pub fn dup2(&mut self, src: Fd, dst: Fd, flags: FdFlag) -> Result<Option<VfsHandle>, Errno> {
+		let src = self.get_file(src).ok_or(Errno::EBADF)?;
 
-		Ok(replace(&mut self.0[dst.index()], Some(src)))
+		if dst.index() >= self.limit {
+			return Err(Errno::EBADF);
+		}
+
+		self.install(dst.index(), src, flags)
+			.map(|old| old.map(|e| e.handle))
 	}
 
-	pub fn dup_start(&mut self, src_fd: Fd, start: usize) -> Result<Fd, Errno> {
-		let src = self.0[src_fd.index()].clone().ok_or(Errno::EBADF)?;
+	pub fn dup_start(&mut self, src_fd: Fd, start: usize, flags: FdFlag) -> Result<Fd, Errno> {
+		let src = self.get_file(src_fd).ok_or(Errno::EBADF)?;
 
-		let (dst_fd, entry) = self.0[start..]
-			.iter_mut()
-			.enumerate()
-			.find(|(_, entry)| entry.is_none())
-			.map(|(i, e)| (start + i, e))
-			.ok_or(Errno::EMFILE)?;
+		if start >= self.limit {
+			return Err(Errno::EINVAL);
+		}
+
+		let dst_fd = self.find_free(start).ok_or(Errno::EMFILE)?;
 
-		*entry = Some(src);
+		self.install(dst_fd, src, flags)?;
 
 		Ok(Fd(dst_fd))
 	}
 
 	pub fn dup(&mut self, src: Fd) -> Result<Fd, Errno> {
-		let src = self.0[src.index()].clone().ok_or(Errno::EBADF)?;
+		self.dup_start(src, 0, FdFlag::empty())
+	}
+
+	pub fn clear(&mut self) {
+		self.files.clear();
+		self.opened.clear();
+	}
 
-		let new_fd = self.alloc_fd(src).ok_or(Errno::EMFILE)?;
+	/// closes descriptors in `first..=last` and returns them.
+	pub fn close_range(&mut self, first: usize, last: usize) -> Vec<(Fd, VfsHandle)> {
+		self.take_if(first, last, |_| true)
+	}
 
-		Ok(new_fd)
+	pub fn set_cloexec_range(&mut self, first: usize, last: usize) {
+		self.files
+			.iter_mut()
+			.take(last.saturating_add(1))
+			.skip(first)
+			.flatten()
+			.for_each(|e| e.flags.insert(FdFlag::FD_CLOEXEC));
 	}
 
-	pub fn clear(&mut self) {
-		self.0.iter_mut().for_each(|e| *e = None);
+	/// closes descriptors with `FD_CLOEXEC` and returns them. called by exec.
+	pub fn take_cloexec(&mut self) -> Vec<(Fd, VfsHandle)> {
+		self.take_if(0, usize::MAX, |e| e.flags.contains(FdFlag::FD_CLOEXEC))
 	}
 
 	pub fn iter_opened(&self) -> impl '_ + Iterator<Item = (usize, VfsHandle)> {
-		self.0
+		self.files
 			.iter()
 			.enumerate()
-			.filter_map(|(i, x)| x.clone().map(|x| (i, x)))
+			.filter_map(|(i, x)| x.as_ref().map(|x| (i, x.handle.clone())))
+	}
+
+	fn entry(&self, fd: &Fd) -> Option<&FdEntry> {
+		self.files.get(fd.index()).and_then(|e| e.as_ref())
+	}
+
+	/// lowest closed descriptor from `start` under the limit.
+	fn find_free(&self, start: usize) -> Option<usize> {
+		let mut index = start / BITS;
+		let mut mask = usize::MAX << (start % BITS);
+
+		let fd = loop {
+			let Some(word) = self.opened.get(index) else {
+				break start.max(self.files.len());
+			};
+
+			let free = !word & mask;
+			if free != 0 {
+				break index * BITS + free.trailing_zeros() as usize;
+			}
+
+			index += 1;
+			mask = usize::MAX;
+		};
+
+		(fd < self.limit).then_some(fd)
+	}
+
+	/// table is doubled until it contains `fd`.
+	fn grow(&mut self, fd: usize) -> Result<(), Errno> {
+		if fd < self.files.len() {
+			return Ok(());
+		}
+
+		let words = (fd / BITS + 1).next_power_of_two();
+		let slots = words * BITS;
+
+		self.files
+			.try_reserve_exact(slots - self.files.len())
+			.map_err(|_| Errno::ENOMEM)?;
+		self.opened
+			.try_reserve_exact(words - self.opened.len())
+			.map_err(|_| Errno::ENOMEM)?;
+
+		self.files.resize(slots, None);
+		self.opened.resize(words, 0);
+
+		Ok(())
+	}
+
+	fn install(
+		&mut self,
+		fd: usize,
+		handle: VfsHandle,
+		flags: FdFlag,
+	) -> Result<Option<FdEntry>, Errno> {
+		self.grow(fd)?;
+
+		self.opened[fd / BITS] |= 1 << (fd % BITS);
+
+		Ok(replace(
+			&mut self.files[fd],
+			Some(FdEntry { handle, flags }),
+		))
+	}
+
+	fn take_if(
+		&mut self,
+		first: usize,
+		last: usize,
+		pred: impl Fn(&FdEntry) -> bool,
+	) -> Vec<(Fd, VfsHandle)> {
+		let mut closed = Vec::new();
+		let end = last.saturating_add(1).min(self.files.len());
+
+		for fd in first..end {
+			if !self.files[fd].as_ref().is_some_and(&pred) {
+				continue;
+			}
+
+			if let Ok(handle) = self.close(Fd(fd)) {
+				closed.push((Fd(fd), handle));
+			}
+		}
+
+		closed
+	}
+}
+
+mod test {
+	use super::*;
+	use crate::driver::pipe::open_pipe;
+	use kfs_macro::ktest;
+
+	#[ktest(fd_table)]
+	fn lowest_free_fd() {
+		let mut table = FdTable::new();
+		let (r, _) = open_pipe();
+
+		for i in 0..100 {
+			let fd = table.alloc_fd(r.clone(), FdFlag::empty()).unwrap();
+			assert_eq!(fd.index(), i);
+		}
+
+		table.close(Fd(3)).unwrap();
+		table.close(Fd(70)).unwrap();
+
+		assert_eq!(
+			table.alloc_fd(r.clone(), FdFlag::empty()).unwrap().index(),
+			3
+		);
+		assert_eq!(
+			table.alloc_fd(r.clone(), FdFlag::empty()).unwrap().index(),
+			70
+		);
+		assert_eq!(table.alloc_fd(r, FdFlag::empty()).unwrap().index(), 100);
+	}
+
+	#[ktest(fd_table)]
+	fn cloexec() {
+		let mut table = FdTable::new();
+		let (r, w) = open_pipe();
+
+		table.alloc_fd(r, FdFlag::FD_CLOEXEC).unwrap();
+		table.alloc_fd(w, FdFlag::empty()).unwrap();
+		table.dup_start(Fd(1), 10, FdFlag::FD_CLOEXEC).unwrap();
+
+		let closed = table.take_cloexec();
+		let closed: Vec<_> = closed.iter().map(|(fd, _)| fd.index()).collect();
+
+		assert_eq!(closed, [0, 10]);
+		assert!(table.get_file(Fd(1)).is_some());
+		assert_eq!(table.get_flags(Fd(1)).unwrap(), FdFlag::empty());
+	}
+
+	#[ktest(fd_table)]
+	fn limit() {
+		let mut table = FdTable::new();
+		let (r, _) = open_pipe();
+
+		table.set_limit(2);
+		table.alloc_fd(r.clone(), FdFlag::empty()).unwrap();
+		table.alloc_fd(r.clone(), FdFlag::empty()).unwrap();
+
+		assert!(table.alloc_fd(r, FdFlag::empty()).is_none());
+		assert!(matches!(
+			table.dup2(Fd(0), Fd(2), FdFlag::empty()),
+			Err(Errno::EBADF)
+		));
 	}
 }
@@ -0,0 +1,355 @@
+//! interval timers of a process. (`setitimer`)
+//!
+//! `ITIMER_REAL` expires by wall-clock time, checked on every timer tick.
+//! `ITIMER_VIRTUAL` and `ITIMER_PROF` count ticks the process ran,
+//! in user mode only or in both user and kernel mode.
+
+use alloc::{
+	collections::BTreeMap,
+	sync::{Arc, Weak},
+};
+
+use crate::{
+	config::TIMER_FREQUENCY_HZ,
+	driver::hpet::get_time_elapsed,
+	mm::user::verify::{verify_ptr, verify_ptr_mut},
+	sync::Locked,
+	syscall::errno::Errno,
+};
+
+use super::{
+	process_tree::PROCESS_TREE,
+	relation::Pid,
+	rusage::TimeVal,
+	signal::{
+		send_signal_to, send_signal_to_process, sig_code::SigCode, sig_info::SigInfo,
+		sig_num::SigNum,
+	},
+	task::{Task, CURRENT},
+	thread_group::ThreadGroup,
+};
+
+const NANO_PER_TICK: u64 = 1_000_000_000 / TIMER_FREQUENCY_HZ as u64;
+
+/// thread groups which `ITIMER_REAL` is armed, by tgid.
+static REAL_TIMERS: Locked<BTreeMap<Pid, Weak<ThreadGroup>>> = Locked::new(BTreeMap::new());
+
+#[derive(Clone, Copy)]
+pub enum Which {
+	Real = 0,
+	Virtual = 1,
+	Prof = 2,
+}
+
+impl TryFrom<usize> for Which {
+	type Error = Errno;
+
+	fn try_from(value: usize) -> Result<Self, Self::Error> {
+		match value {
+			0 => Ok(Self::Real),
+			1 => Ok(Self::Virtual),
+			2 => Ok(Self::Prof),
+			_ => Err(Errno::EINVAL),
+		}
+	}
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+pub struct ITimerVal {
+	pub interval: TimeVal,
+	pub value: TimeVal,
+}
+
+/// `value` and `interval` in ns. zero `value` means disarmed.
+#[derive(Clone, Copy)]
+struct ITimer {
+	value: u64,
+	interval: u64,
+}
+
+impl ITimer {
+	const fn disarmed() -> Self {
+		Self {
+			value: 0,
+			interval: 0,
+		}
+	}
+
+	/// advance by `elapsed` ns. returns true if expired.
+	fn advance(&mut self, elapsed: u64) -> bool {
+		if self.value == 0 {
+			return false;
+		}
+
+		if self.value > elapsed {
+			self.value -= elapsed;
+			return false;
+		}
+
+		self.value = self.interval;
+		true
+	}
+}
+
+pub struct ITimers {
+	/// `value` of real timer is the deadline on monotonic clock.
+	real: ITimer,
+	virt: ITimer,
+	prof: ITimer,
+}
+
+impl ITimers {
+	pub const fn new() -> Self {
+		Self {
+			real: ITimer::disarmed(),
+			virt: ITimer::disarmed(),
+			prof: ITimer::disarmed(),
+		}
+	}
+
+	pub fn get(&self, which: Which) -> ITimerVal {
+		let (value, interval) = match which {
+			Which::Real if self.real.value == 0 => (0, self.real.interval),
+			Which::Real => (
+				self.real.value.saturating_sub(get_time_elapsed()).max(1),
+				self.real.interval,
+			),
+			Which::Virtual => (self.virt.value, self.virt.interval),
+			Which::Prof => (self.prof.value, self.prof.interval),
+		};
+
+		ITimerVal {
+			interval: TimeVal::from_nano(interval),
+			value: TimeVal::from_nano(value),
+		}
+	}
+
+	/// returns the old value.
+	fn set(&mut self, which: Which, new: &ITimerVal) -> ITimerVal {
+		let old = self.get(which);
+
+		let value = new.value.nano();
+		let interval = new.interval.nano();
+
+		match which {
+			Which::Real => {
+				self.real = ITimer {
+					value: match value {
+						0 => 0,
+						x => get_time_elapsed() + x,
+					},
+					interval,
+				}
+			}
+			Which::Virtual => self.virt = tick_timer(value, interval),
+			Which::Prof => self.prof = tick_timer(value, interval),
+		};
+
+		old
+	}
+
+	fn is_real_armed(&self) -> bool {
+		self.real.value != 0
+	}
+}
+
+/// timer counting ticks expires after at least one tick.
+fn tick_timer(value: u64, interval: u64) -> ITimer {
+	let round_up = |nano: u64| match nano {
+		0 => 0,
+		x => x.max(NANO_PER_TICK),
+	};
+
+	ITimer {
+		value: round_up(value),
+		interval: round_up(interval),
+	}
+}
+
+fn timer_signal(num: SigNum) -> SigInfo {
+	SigInfo {
+		num,
+		pid: 0,
+		uid: 0,
+		code: SigCode::SI_KERNEL,
+		value: 0,
+	}
+}
+
+/// charge a tick to interval timers of `task`. called by timer interrupt.
+pub fn tick_process(task: &Arc<Task>, user: bool) {
+	let Some(ext) = task.get_user_ext() else {
+		return;
+	};
+
+	let (virt, prof) = {
+		let mut itimers = ext.get_thread_group().lock_itimers();
+
+		let virt = user && itimers.virt.advance(NANO_PER_TICK);
+		let prof = itimers.prof.advance(NANO_PER_TICK);
+
+		(virt, prof)
+	};
+
+	// sent to the running thread, which is alive.
+	if virt {
+		let _ = send_signal_to(task, &timer_signal(SigNum::VTALRM));
+	}
+	if prof {
+		let _ = send_signal_to(task, &timer_signal(SigNum::PROF));
+	}
+}
+
+/// fire expired `ITIMER_REAL`s. called by timer interrupt.
+pub fn tick_real() {
+	let now = get_time_elapsed();
+	let mut timers = REAL_TIMERS.lock();
+
+	timers.retain(|tgid, weak| {
+		let Some(group) = weak.upgrade() else {
+			return false;
+		};
+
+		let mut itimers = group.lock_itimers();
+		let real = &mut itimers.real;
+
+		if real.value == 0 {
+			return false;
+		}
+
+		if real.value > now {
+			return true;
+		}
+
+		// skip periods already passed, not to burst signals.
+		real.value = match real.interval {
+			0 => 0,
+			interval => now + interval - (now - real.value) % interval,
+		};
+		let armed = real.value != 0;
+		drop(itimers);
+
+		// leader may have exited while other threads are running.
+		if let Some(leader) = PROCESS_TREE.get_task(*tgid) {
+			let _ = send_signal_to_process(&leader, &timer_signal(SigNum::ALRM));
+		}
+
+		armed
+	});
+}
+
+/// disarm `ITIMER_REAL` of exiting process.
+pub fn cancel_real(tgid: Pid) {
+	REAL_TIMERS.lock().remove(&tgid);
+}
+
+fn set_itimer(which: Which, new: &ITimerVal) -> Result<ITimerVal, Errno> {
+	let current = unsafe { CURRENT.get_ref() };
+	let group = current.user_ext_ok_or(Errno::EPERM)?.get_thread_group();
+
+	if !new.value.is_valid() || !new.interval.is_valid() {
+		return Err(Errno::EINVAL);
+	}
+
+	let (old, armed) = {
+		let mut itimers = group.lock_itimers();
+		(itimers.set(which, new), itimers.is_real_armed())
+	};
+
+	// `tick_real` locks `REAL_TIMERS` first, then itimers of a group.
+	if let Which::Real = which {
+		let mut timers = REAL_TIMERS.lock();
+
+		match armed {
+			true => timers.insert(group.get_tgid(), Arc::downgrade(group)),
+			false => timers.remove(&group.get_tgid()),
+		};
+	}
+
+	Ok(old)
+}
+
+pub fn sys_getitimer(which: usize, curr_value: usize) -> Result<usize, Errno> {
+	let current = unsafe { CURRENT.get_ref() };
+	let which = Which::try_from(which)?;
+
+	let value = current
+		.user_ext_ok_or(Errno::EPERM)?
+		.get_thread_group()
+		.lock_itimers()
+		.get(which);
+
+	*verify_ptr_mut::<ITimerVal>(curr_value, current)? = value;
+
+	Ok(0)
+}
+
+pub fn sys_setitimer(which: usize, new_value: usize, old_value: usize) -> Result<usize, Errno> {
+	let current = unsafe { CURRENT.get_ref() };
+	let which = Which::try_from(which)?;
+
+	let new = match new_value {
+		0 => ITimerVal::default(),
+		x => *verify_ptr::<ITimerVal>(x, current)?,
+	};
+
+	let old_value = match old_value {
+		0 => None,
+		x => Some(verify_ptr_mut::<ITimerVal>(x, current)?),
+	};
+
+	let old = set_itimer(which, &new)?;
+
+	if let Some(old_value) = old_value {
+		*old_value = old;
+	}
+
+	Ok(0)
+}
+
+/// returns seconds remaining on previous alarm. (0 if none)
+pub fn sys_alarm(seconds: usize) -> Result<usize, Errno> {
+	let new = ITimerVal {
+		interval: TimeVal::default(),
+		value: TimeVal {
+			sec: seconds as isize,
+			usec: 0,
+		},
+	};
+
+	let old = set_itimer(Which::Real, &new)?.value;
+
+	// round to nearest, but never report 0 for a pending alarm.
+	let remain = match (old.sec, old.usec) {
+		(0, 0) => 0,
+		(sec, usec) if usec >= 500_000 => sec + 1,
+		(0, _) => 1,
+		(sec, _) => sec,
+	};
+
+	Ok(remain as usize)
+}
+
+mod test {
+	use super::*;
+	use kfs_macro::ktest;
+
+	#[ktest(itimer)]
+	fn periodic_tick_timer() {
+		let mut timer = tick_timer(NANO_PER_TICK * 2, NANO_PER_TICK);
+
+		assert!(!timer.advance(NANO_PER_TICK));
+		assert!(timer.advance(NANO_PER_TICK));
+		assert!(timer.advance(NANO_PER_TICK));
+	}
+
+	#[ktest(itimer)]
+	fn one_shot_disarms() {
+		let mut timer = tick_timer(1, 0);
+
+		assert_eq!(timer.value, NANO_PER_TICK);
+		assert!(timer.advance(NANO_PER_TICK));
+		assert!(!timer.advance(NANO_PER_TICK));
+	}
+}
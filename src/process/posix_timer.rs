@@ -0,0 +1,295 @@
+//! POSIX per-process timers. (`timer_create`)
+
+use alloc::{
+	collections::BTreeMap,
+	sync::{Arc, Weak},
+};
+
+use crate::{
+	fs::vfs::TimeSpec,
+	mm::user::verify::{verify_ptr, verify_ptr_mut},
+	scheduler::timer::{Expire, IntervalTimer},
+	sync::Locked,
+	syscall::{clock::ClockId, errno::Errno},
+};
+
+use super::{
+	process_tree::PROCESS_TREE,
+	relation::Pid,
+	signal::{
+		send_signal_to, send_signal_to_process, sig_code::SigCode, sig_info::SigInfo,
+		sig_num::SigNum,
+	},
+	task::CURRENT,
+};
+
+const SIGEV_SIGNAL: i32 = 0;
+const SIGEV_NONE: i32 = 1;
+const SIGEV_THREAD_ID: i32 = 4;
+
+const TIMER_ABSTIME: usize = 1;
+
+/// `struct sigevent` of user space.
+#[repr(C)]
+struct SigEvent {
+	value: usize,
+	signo: i32,
+	notify: i32,
+	tid: i32,
+	_pad: [i32; 12],
+}
+
+#[repr(C)]
+#[derive(Default, Clone)]
+pub struct ITimerSpec {
+	pub interval: TimeSpec,
+	pub value: TimeSpec,
+}
+
+impl ITimerSpec {
+	pub fn new(value: u64, interval: u64) -> Self {
+		Self {
+			interval: TimeSpec::from(interval),
+			value: TimeSpec::from(value),
+		}
+	}
+
+	pub fn is_valid(&self) -> bool {
+		self.value.is_valid() && self.interval.is_valid()
+	}
+
+	/// expiry time on realtime clock, of `self.value` in `clock`.
+	pub fn expires(&self, clock: ClockId, absolute: bool) -> u64 {
+		match (self.value.nano(), absolute) {
+			(0, _) => 0,
+			(value, true) => clock.to_realtime(value).max(1),
+			(value, false) => clock.to_realtime(clock.now() + value),
+		}
+	}
+}
+
+enum Notify {
+	None,
+	/// sent to a thread only with `SIGEV_THREAD_ID`, otherwise to the process.
+	Signal {
+		num: SigNum,
+		value: usize,
+		to: Pid,
+		thread: bool,
+	},
+}
+
+pub struct PosixTimer {
+	clock: ClockId,
+	notify: Notify,
+	timer: Locked<IntervalTimer>,
+}
+
+impl Expire for PosixTimer {
+	fn expire(&self, id: usize, now: u64) -> Option<u64> {
+		let (_, next) = self.timer.lock().expire(id, now)?;
+
+		if let Notify::Signal {
+			num,
+			value,
+			to,
+			thread,
+		} = self.notify
+		{
+			let info = SigInfo {
+				num,
+				pid: 0,
+				uid: 0,
+				code: SigCode::SI_TIMER,
+				value,
+			};
+
+			if let Some(task) = PROCESS_TREE.get_task(to) {
+				let _ = match thread {
+					true => send_signal_to(&task, &info),
+					false => send_signal_to_process(&task, &info),
+				};
+			}
+		}
+
+		next
+	}
+}
+
+impl PosixTimer {
+	fn settime(self: &Arc<Self>, spec: &ITimerSpec, absolute: bool) -> ITimerSpec {
+		let mut timer = self.timer.lock();
+		let old = self.gettime(&timer);
+
+		let weak: Weak<dyn Expire> = Arc::downgrade(self) as _;
+		timer.arm(
+			spec.expires(self.clock, absolute),
+			spec.interval.nano(),
+			weak,
+		);
+
+		old
+	}
+
+	fn gettime(&self, timer: &IntervalTimer) -> ITimerSpec {
+		let (value, interval) = timer.remaining(ClockId::Realtime.now());
+
+		ITimerSpec::new(value, interval)
+	}
+}
+
+/// timers of a process by timer id.
+pub struct PosixTimers(BTreeMap<usize, Arc<PosixTimer>>);
+
+impl PosixTimers {
+	pub const fn new() -> Self {
+		Self(BTreeMap::new())
+	}
+
+	/// delete every timer. (exec / exit)
+	pub fn clear(&mut self) {
+		self.0.clear();
+	}
+
+	/// the smallest unused id.
+	fn next_id(&self) -> usize {
+		(0..).find(|id| !self.0.contains_key(id)).unwrap()
+	}
+
+	fn get(&self, id: usize) -> Result<Arc<PosixTimer>, Errno> {
+		self.0.get(&id).cloned().ok_or(Errno::EINVAL)
+	}
+}
+
+fn get_timer(id: usize) -> Result<Arc<PosixTimer>, Errno> {
+	let current = unsafe { CURRENT.get_ref() };
+
+	current
+		.user_ext_ok_or(Errno::EINVAL)?
+		.get_thread_group()
+		.lock_posix_timers()
+		.get(id)
+}
+
+pub fn sys_timer_create(clockid: usize, sevp: usize, timerid: usize) -> Result<usize, Errno> {
+	let current = unsafe { CURRENT.get_ref() };
+	let group = current.user_ext_ok_or(Errno::EINVAL)?.get_thread_group();
+
+	let clock = ClockId::from_usize(clockid).ok_or(Errno::EINVAL)?;
+	let timerid = verify_ptr_mut::<i32>(timerid, current)?;
+
+	let notify = match sevp {
+		// SIGALRM to the process, with timer id as value.
+		0 => None,
+		x => Some(verify_ptr::<SigEvent>(x, current)?),
+	};
+
+	let to_signal = |signo: i32| SigNum::from_usize(signo as usize).ok_or(Errno::EINVAL);
+
+	let mut timers = group.lock_posix_timers();
+	let id = timers.next_id();
+
+	let notify = match notify {
+		None => Notify::Signal {
+			num: SigNum::ALRM,
+			value: id,
+			to: group.get_tgid(),
+			thread: false,
+		},
+		Some(ev) => match ev.notify {
+			SIGEV_NONE => Notify::None,
+			SIGEV_SIGNAL => Notify::Signal {
+				num: to_signal(ev.signo)?,
+				value: ev.value,
+				to: group.get_tgid(),
+				thread: false,
+			},
+			SIGEV_THREAD_ID => {
+				let tid = Pid::from_raw(ev.tid as usize);
+				if !group.contains(tid) {
+					return Err(Errno::EINVAL);
+				}
+
+				Notify::Signal {
+					num: to_signal(ev.signo)?,
+					value: ev.value,
+					to: tid,
+					thread: true,
+				}
+			}
+			_ => return Err(Errno::EINVAL),
+		},
+	};
+
+	let timer = Arc::new(PosixTimer {
+		clock,
+		notify,
+		timer: Locked::new(IntervalTimer::new()),
+	});
+
+	timers.0.insert(id, timer);
+	*timerid = id as i32;
+
+	Ok(0)
+}
+
+pub fn sys_timer_settime(
+	timerid: usize,
+	flags: usize,
+	new_value: usize,
+	old_value: usize,
+) -> Result<usize, Errno> {
+	let current = unsafe { CURRENT.get_ref() };
+
+	let timer = get_timer(timerid)?;
+	let new = verify_ptr::<ITimerSpec>(new_value, current)?;
+
+	if !new.is_valid() {
+		return Err(Errno::EINVAL);
+	}
+
+	let old_value = match old_value {
+		0 => None,
+		x => Some(verify_ptr_mut::<ITimerSpec>(x, current)?),
+	};
+
+	let old = timer.settime(new, flags & TIMER_ABSTIME != 0);
+
+	if let Some(old_value) = old_value {
+		*old_value = old;
+	}
+
+	Ok(0)
+}
+
+pub fn sys_timer_gettime(timerid: usize, curr_value: usize) -> Result<usize, Errno> {
+	let current = unsafe { CURRENT.get_ref() };
+
+	let timer = get_timer(timerid)?;
+	let curr_value = verify_ptr_mut::<ITimerSpec>(curr_value, current)?;
+
+	*curr_value = timer.gettime(&timer.timer.lock());
+
+	Ok(0)
+}
+
+pub fn sys_timer_getoverrun(timerid: usize) -> Result<usize, Errno> {
+	let timer = get_timer(timerid)?;
+	let overrun = timer.timer.lock().overrun();
+
+	Ok(overrun)
+}
+
+pub fn sys_timer_delete(timerid: usize) -> Result<usize, Errno> {
+	let current = unsafe { CURRENT.get_ref() };
+
+	current
+		.user_ext_ok_or(Errno::EINVAL)?
+		.get_thread_group()
+		.lock_posix_timers()
+		.0
+		.remove(&timerid)
+		.ok_or(Errno::EINVAL)?;
+
+	Ok(0)
+}
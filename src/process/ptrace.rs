@@ -0,0 +1,605 @@
+//! process tracing.
+//!
+//! a tracee stops before a signal is delivered, around system calls
+//! (`PTRACE_SYSCALL`) and after a single step, until its tracer resumes it.
+//! stops are reported to the tracer through `waitpid`.
+
+use core::{mem::size_of, ptr::read_volatile};
+
+use alloc::{
+	collections::BTreeMap,
+	sync::{Arc, Weak},
+	vec::Vec,
+};
+
+use crate::{
+	interrupt::InterruptFrame,
+	mm::{
+		alloc::virt::{kmap, kunmap},
+		constant::PAGE_SIZE,
+		user::{
+			memory::access_forced,
+			verify::{verify_ptr, verify_ptr_mut},
+		},
+	},
+	process::{
+		exit::ExitStatus,
+		process_tree::PROCESS_TREE,
+		relation::Pid,
+		signal::{
+			send_signal_to, sig_code::SigCode, sig_ctx::MContext, sig_info::SigInfo,
+			sig_mask::SigMask, sig_num::SigNum,
+		},
+		task::{Task, CURRENT},
+	},
+	scheduler::sleep::{sleep_and_yield_lock, wake_up, Sleep},
+	sync::Locked,
+	syscall::{errno::Errno, wait::Who},
+};
+
+/// tracees by their tid.
+static TRACEES: Locked<BTreeMap<Pid, Tracee>> = Locked::new(BTreeMap::new());
+
+/// trap flag of eflags.
+const EFLAGS_TF: usize = 0x100;
+
+/// `orig_eax` outside of system calls.
+const NO_SYSCALL: usize = !0;
+
+struct Tracee {
+	/// tgid of the tracer.
+	tracer: Pid,
+	task: Weak<Task>,
+	state: State,
+	resume: Resume,
+	/// signal to deliver on resume from a signal-delivery stop.
+	signal: Option<SigNum>,
+	/// system call number while stopped around it.
+	orig_eax: usize,
+	detached: bool,
+}
+
+#[derive(Clone, Copy)]
+enum State {
+	Running,
+	Stopped { status: ExitStatus, reported: bool },
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Resume {
+	Cont,
+	Syscall,
+	SingleStep,
+}
+
+enum Request {
+	TraceMe,
+	PeekData,
+	PokeData,
+	Cont,
+	Kill,
+	SingleStep,
+	GetRegs,
+	SetRegs,
+	Attach,
+	Detach,
+	Syscall,
+}
+
+impl TryFrom<usize> for Request {
+	type Error = Errno;
+
+	fn try_from(value: usize) -> Result<Self, Self::Error> {
+		use Request::*;
+		match value {
+			0 => Ok(TraceMe),
+			// text and data share the address space.
+			1 | 2 => Ok(PeekData),
+			4 | 5 => Ok(PokeData),
+			7 => Ok(Cont),
+			8 => Ok(Kill),
+			9 => Ok(SingleStep),
+			12 => Ok(GetRegs),
+			13 => Ok(SetRegs),
+			16 => Ok(Attach),
+			17 => Ok(Detach),
+			24 => Ok(Syscall),
+			_ => Err(Errno::EIO),
+		}
+	}
+}
+
+/// `struct user_regs_struct` of i386.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct UserRegs {
+	pub ebx: usize,
+	pub ecx: usize,
+	pub edx: usize,
+	pub esi: usize,
+	pub edi: usize,
+	pub ebp: usize,
+	pub eax: usize,
+	pub ds: usize,
+	pub es: usize,
+	pub fs: usize,
+	pub gs: usize,
+	pub orig_eax: usize,
+	pub eip: usize,
+	pub cs: usize,
+	pub eflags: usize,
+	pub esp: usize,
+	pub ss: usize,
+}
+
+impl UserRegs {
+	pub fn new(frame: &InterruptFrame, orig_eax: usize) -> Self {
+		Self {
+			ebx: frame.ebx,
+			ecx: frame.ecx,
+			edx: frame.edx,
+			esi: frame.esi,
+			edi: frame.edi,
+			ebp: frame.ebp,
+			eax: frame.eax,
+			ds: frame.ds,
+			es: frame.es,
+			fs: frame.fs,
+			gs: frame.gs,
+			orig_eax,
+			eip: frame.eip,
+			cs: frame.cs,
+			eflags: frame.eflags,
+			esp: frame.esp,
+			ss: frame.ss,
+		}
+	}
+
+	/// segments and privileged eflags are kept as `sigreturn` does.
+	fn restore(&self, frame: &mut InterruptFrame) {
+		frame.ebx = self.ebx;
+		frame.ecx = self.ecx;
+		frame.edx = self.edx;
+		frame.esi = self.esi;
+		frame.edi = self.edi;
+		frame.ebp = self.ebp;
+		frame.eax = self.eax;
+		frame.eip = self.eip;
+		frame.esp = self.esp;
+		frame.eflags =
+			(frame.eflags & !MContext::USER_EFLAGS) | (self.eflags & MContext::USER_EFLAGS);
+	}
+}
+
+/// user frame of a task stopped in kernel.
+fn saved_frame(task: &Arc<Task>) -> *mut InterruptFrame {
+	(task.kstack_base() - size_of::<InterruptFrame>()) as *mut InterruptFrame
+}
+
+pub fn is_traced(task: &Arc<Task>) -> bool {
+	TRACEES.lock().contains_key(&task.get_pid())
+}
+
+/// stop current task until the tracer resumes it.
+///
+/// returns how it was resumed, or `None` if it is not traced (anymore).
+fn stop(status: ExitStatus, orig_eax: usize) -> Option<(Resume, Option<SigNum>)> {
+	let current = unsafe { CURRENT.get_ref() };
+	let pid = current.get_pid();
+
+	let tracer = {
+		let mut tracees = TRACEES.lock();
+		let tracee = tracees.get_mut(&pid)?;
+
+		tracee.state = State::Stopped {
+			status,
+			reported: false,
+		};
+		tracee.orig_eax = orig_eax;
+		tracee.tracer
+	};
+
+	notify_tracer(tracer, current);
+
+	let signal = &current.get_user_ext().expect("user task").signal;
+
+	loop {
+		let mut tracees = TRACEES.lock();
+		let tracee = tracees.get(&pid)?;
+
+		if matches!(tracee.state, State::Running) {
+			let resumed = (tracee.resume, tracee.signal);
+			if tracee.detached {
+				tracees.remove(&pid);
+			}
+			return Some(resumed);
+		}
+
+		// killed while stopped.
+		if signal.pending().contains(SigMask::KILL) {
+			tracees.remove(&pid);
+			return None;
+		}
+
+		sleep_and_yield_lock(Sleep::Deep, tracees);
+	}
+}
+
+fn notify_tracer(tracer: Pid, current: &Arc<Task>) {
+	let Some(tracer) = PROCESS_TREE.get_task(tracer) else {
+		return;
+	};
+
+	let info = SigInfo {
+		num: SigNum::CHLD,
+		pid: current.get_pid().as_raw(),
+		uid: current.get_uid(),
+		code: SigCode::CLD_TRAPPED,
+		value: 0,
+	};
+
+	let _ = send_signal_to(&tracer, &info);
+}
+
+fn resume_mode() -> Option<Resume> {
+	let current = unsafe { CURRENT.get_ref() };
+
+	TRACEES.lock().get(&current.get_pid()).map(|t| t.resume)
+}
+
+/// let the tracer look at a signal before it is delivered.
+///
+/// returns the signal to deliver instead, if any.
+pub fn signal_stop(num: SigNum) -> Option<SigNum> {
+	let status = ExitStatus::new_stopped(num as usize as u8);
+
+	match stop(status, NO_SYSCALL) {
+		Some((_, signal)) => signal,
+		None => Some(num),
+	}
+}
+
+/// stop on entry of a system call, with `PTRACE_SYSCALL`.
+pub fn syscall_enter(frame: &mut InterruptFrame) {
+	if resume_mode() != Some(Resume::Syscall) {
+		return;
+	}
+
+	let status = ExitStatus::new_stopped(SigNum::TRAP as usize as u8);
+	stop(status, frame.eax);
+
+	// tracer may have changed the number. (`orig_eax`)
+	let current = unsafe { CURRENT.get_ref() };
+	if let Some(tracee) = TRACEES.lock().get(&current.get_pid()) {
+		frame.eax = tracee.orig_eax;
+	}
+}
+
+/// stop on exit of a system call, with `PTRACE_SYSCALL`.
+///
+/// returns the result, which tracer may have changed.
+pub fn syscall_exit(frame: &mut InterruptFrame, ret: isize) -> isize {
+	if resume_mode() != Some(Resume::Syscall) {
+		return ret;
+	}
+
+	let syscall_no = frame.eax;
+	frame.eax = ret as usize;
+
+	let status = ExitStatus::new_stopped(SigNum::TRAP as usize as u8);
+	stop(status, syscall_no);
+
+	// tracer writes the frame while stopped.
+	unsafe { read_volatile(&frame.eax) as isize }
+}
+
+/// a traced task gets `SIGTRAP` after successful exec.
+pub fn exec_notify() {
+	let current = unsafe { CURRENT.get_ref() };
+
+	if !is_traced(current) {
+		return;
+	}
+
+	let info = SigInfo {
+		num: SigNum::TRAP,
+		pid: 0,
+		uid: 0,
+		code: SigCode::SI_USER,
+		value: 0,
+	};
+
+	let _ = current.recv_signal(info);
+}
+
+/// forget tracing of exiting task, and release its tracees if it is the last thread.
+pub fn exit_notify(task: &Arc<Task>, last: bool) {
+	let mut tracees = TRACEES.lock();
+
+	tracees.remove(&task.get_pid());
+
+	if !last {
+		return;
+	}
+
+	let tgid = task.get_tgid();
+	let released: Vec<Pid> = tracees
+		.iter()
+		.filter(|(_, t)| t.tracer == tgid)
+		.map(|(pid, _)| *pid)
+		.collect();
+
+	for pid in released {
+		if let Some(tracee) = tracees.remove(&pid) {
+			if let Some(task) = tracee.task.upgrade() {
+				clear_single_step(&task);
+				wake_up(&task, Sleep::Deep);
+			}
+		}
+	}
+}
+
+/// report a stop of a tracee of current task to `waitpid`.
+pub fn wait_stopped(who: Who) -> Option<(Pid, ExitStatus)> {
+	let current = unsafe { CURRENT.get_ref() };
+	let tgid = current.get_tgid();
+
+	let mut tracees = TRACEES.lock();
+
+	for (pid, tracee) in tracees.iter_mut() {
+		let State::Stopped {
+			status,
+			reported: false,
+		} = tracee.state
+		else {
+			continue;
+		};
+
+		if tracee.tracer != tgid {
+			continue;
+		}
+
+		let matched = match who {
+			Who::Any => true,
+			Who::Pid(x) => x == *pid,
+			Who::Pgid(x) => tracee.task.upgrade().is_some_and(|t| t.get_pgid() == x),
+		};
+
+		if matched {
+			tracee.state = State::Stopped {
+				status,
+				reported: true,
+			};
+			return Some((*pid, status));
+		}
+	}
+
+	None
+}
+
+fn trace_me() -> Result<usize, Errno> {
+	let current = unsafe { CURRENT.get_ref() };
+
+	let mut tracees = TRACEES.lock();
+	if tracees.contains_key(&current.get_pid()) {
+		return Err(Errno::EPERM);
+	}
+
+	tracees.insert(
+		current.get_pid(),
+		Tracee {
+			tracer: current.get_ppid(),
+			task: Arc::downgrade(current),
+			state: State::Running,
+			resume: Resume::Cont,
+			signal: None,
+			orig_eax: NO_SYSCALL,
+			detached: false,
+		},
+	);
+
+	Ok(0)
+}
+
+fn attach(pid: Pid) -> Result<usize, Errno> {
+	let current = unsafe { CURRENT.get_ref() };
+
+	let task = PROCESS_TREE.get_task(pid).ok_or(Errno::ESRCH)?;
+	if task.is_kernel() || pid.as_raw() == 1 || task.get_tgid() == current.get_tgid() {
+		return Err(Errno::EPERM);
+	}
+
+	if !current.get_cred().can_trace(&task.get_cred()) {
+		return Err(Errno::EPERM);
+	}
+
+	{
+		let mut tracees = TRACEES.lock();
+		if tracees.contains_key(&pid) {
+			return Err(Errno::EPERM);
+		}
+
+		tracees.insert(
+			pid,
+			Tracee {
+				tracer: current.get_tgid(),
+				task: Arc::downgrade(&task),
+				state: State::Running,
+				resume: Resume::Cont,
+				signal: None,
+				orig_eax: NO_SYSCALL,
+				detached: false,
+			},
+		);
+	}
+
+	let info = SigInfo {
+		num: SigNum::STOP,
+		pid: current.get_tgid().as_raw(),
+		uid: current.get_uid(),
+		code: SigCode::SI_USER,
+		value: 0,
+	};
+
+	send_signal_to(&task, &info).map(|_| 0)
+}
+
+/// stopped tracee of current task.
+fn get_stopped(pid: Pid) -> Result<Arc<Task>, Errno> {
+	let current = unsafe { CURRENT.get_ref() };
+
+	let tracees = TRACEES.lock();
+	let tracee = tracees.get(&pid).ok_or(Errno::ESRCH)?;
+
+	match (tracee.tracer == current.get_tgid(), tracee.state) {
+		(true, State::Stopped { .. }) => tracee.task.upgrade().ok_or(Errno::ESRCH),
+		_ => Err(Errno::ESRCH),
+	}
+}
+
+fn clear_single_step(task: &Arc<Task>) {
+	unsafe { (*saved_frame(task)).eflags &= !EFLAGS_TF };
+}
+
+fn resume(pid: Pid, resume: Resume, signal: usize, detach: bool) -> Result<usize, Errno> {
+	let signal = match signal {
+		0 => None,
+		x => Some(SigNum::from_usize(x).ok_or(Errno::EIO)?),
+	};
+
+	let task = get_stopped(pid)?;
+
+	let frame = saved_frame(&task);
+	match resume {
+		Resume::SingleStep => unsafe { (*frame).eflags |= EFLAGS_TF },
+		_ => clear_single_step(&task),
+	}
+
+	if let Some(tracee) = TRACEES.lock().get_mut(&pid) {
+		tracee.state = State::Running;
+		tracee.resume = resume;
+		tracee.signal = signal;
+		tracee.detached = detach;
+	}
+
+	wake_up(&task, Sleep::Deep);
+
+	Ok(0)
+}
+
+/// access a word of `task` memory at `addr`, a byte at a time
+/// as it may span two pages.
+///
+/// protection of the area is ignored, so that breakpoints can be set on text.
+fn access_word(task: &Arc<Task>, addr: usize, write: Option<u32>) -> Result<u32, Errno> {
+	let ext = task.get_user_ext().ok_or(Errno::ESRCH)?;
+
+	let mut word = write.unwrap_or(0).to_ne_bytes();
+
+	for (i, byte) in word.iter_mut().enumerate() {
+		let vaddr = addr.checked_add(i).ok_or(Errno::EIO)?;
+
+		access_forced(ext, vaddr, write.is_some(), |page| -> Result<(), Errno> {
+			let ptr = kmap(page).map_err(|_| Errno::ENOMEM)?;
+			let target = unsafe { ptr.as_ptr().add(vaddr % PAGE_SIZE) };
+
+			match write {
+				Some(_) => unsafe { target.write(*byte) },
+				None => *byte = unsafe { target.read() },
+			}
+
+			kunmap(ptr.as_ptr() as usize);
+			Ok(())
+		})
+		.map_err(|_| Errno::EIO)??;
+	}
+
+	Ok(u32::from_ne_bytes(word))
+}
+
+fn peek_data(pid: Pid, addr: usize, data: usize) -> Result<usize, Errno> {
+	let current = unsafe { CURRENT.get_ref() };
+
+	let task = get_stopped(pid)?;
+	let out = verify_ptr_mut::<u32>(data, current)?;
+
+	*out = access_word(&task, addr, None)?;
+
+	Ok(0)
+}
+
+fn poke_data(pid: Pid, addr: usize, data: usize) -> Result<usize, Errno> {
+	let task = get_stopped(pid)?;
+
+	access_word(&task, addr, Some(data as u32)).map(|_| 0)
+}
+
+fn get_regs(pid: Pid, data: usize) -> Result<usize, Errno> {
+	let current = unsafe { CURRENT.get_ref() };
+
+	let task = get_stopped(pid)?;
+	let out = verify_ptr_mut::<UserRegs>(data, current)?;
+
+	let orig_eax = TRACEES.lock().get(&pid).map_or(NO_SYSCALL, |t| t.orig_eax);
+
+	*out = UserRegs::new(unsafe { &*saved_frame(&task) }, orig_eax);
+
+	Ok(0)
+}
+
+fn set_regs(pid: Pid, data: usize) -> Result<usize, Errno> {
+	let current = unsafe { CURRENT.get_ref() };
+
+	let task = get_stopped(pid)?;
+	let regs = *verify_ptr::<UserRegs>(data, current)?;
+
+	if let Some(tracee) = TRACEES.lock().get_mut(&pid) {
+		tracee.orig_eax = regs.orig_eax;
+	}
+
+	regs.restore(unsafe { &mut *saved_frame(&task) });
+
+	Ok(0)
+}
+
+fn kill(pid: Pid) -> Result<usize, Errno> {
+	let current = unsafe { CURRENT.get_ref() };
+
+	let task = {
+		let tracees = TRACEES.lock();
+		let tracee = tracees.get(&pid).ok_or(Errno::ESRCH)?;
+		if tracee.tracer != current.get_tgid() {
+			return Err(Errno::ESRCH);
+		}
+		tracee.task.upgrade().ok_or(Errno::ESRCH)?
+	};
+
+	let info = SigInfo {
+		num: SigNum::KILL,
+		pid: current.get_tgid().as_raw(),
+		uid: current.get_uid(),
+		code: SigCode::SI_USER,
+		value: 0,
+	};
+
+	task.recv_signal(info).map(|_| 0)
+}
+
+pub fn sys_ptrace(request: usize, pid: usize, addr: usize, data: usize) -> Result<usize, Errno> {
+	let pid = Pid::from_raw(pid);
+
+	use Request::*;
+	match Request::try_from(request)? {
+		TraceMe => trace_me(),
+		Attach => attach(pid),
+		PeekData => peek_data(pid, addr, data),
+		PokeData => poke_data(pid, addr, data),
+		GetRegs => get_regs(pid, data),
+		SetRegs => set_regs(pid, data),
+		Cont => resume(pid, Resume::Cont, data, false),
+		Syscall => resume(pid, Resume::Syscall, data, false),
+		SingleStep => resume(pid, Resume::SingleStep, data, false),
+		Detach => resume(pid, Resume::Cont, data, true),
+		Kill => kill(pid),
+	}
+}
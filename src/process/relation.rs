@@ -7,7 +7,10 @@ mod id;
 
 pub use id::*;
 
-use alloc::sync::{Arc, Weak};
+use alloc::{
+	sync::{Arc, Weak},
+	vec::Vec,
+};
 
 use crate::pr_debug;
 use crate::sync::Locked;
@@ -19,6 +22,7 @@ use self::pgroup::ProcessGroup;
 use self::session::Session;
 
 use super::exit::ExitStatus;
+use super::rusage::Usage;
 use super::task::Task;
 
 pub struct Relation {
@@ -47,6 +51,11 @@ impl Relation {
 		self.family.get_ppid()
 	}
 
+	/// live children, not yet exited.
+	pub fn children(&self) -> Vec<Pid> {
+		self.family.children()
+	}
+
 	pub fn get_pgroup(&self) -> Arc<ProcessGroup> {
 		self.pgroup.clone()
 	}
@@ -65,8 +74,8 @@ impl Relation {
 		result
 	}
 
-	pub fn exit(&mut self, pid: Pid, status: ExitStatus) {
-		let zombie = Zombie::new(pid, self.get_pgroup(), self.get_session(), status);
+	pub fn exit(&mut self, pid: Pid, status: ExitStatus, usage: Usage) {
+		let zombie = Zombie::new(pid, self.get_pgroup(), self.get_session(), status, usage);
 		self.family.exit(zombie);
 	}
 
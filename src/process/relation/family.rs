@@ -2,7 +2,7 @@ pub mod zombie;
 
 use core::mem::{self, take};
 
-use alloc::collections::BTreeSet;
+use alloc::{collections::BTreeSet, vec::Vec};
 
 use crate::process::get_init_task;
 use crate::{process::process_tree::PROCESS_TREE, syscall::errno::Errno};
@@ -100,4 +100,8 @@ impl Family {
 	pub fn has_child(&self, pid: Pid) -> bool {
 		self.children.contains(&pid)
 	}
+
+	pub fn children(&self) -> Vec<Pid> {
+		self.children.iter().copied().collect()
+	}
 }
@@ -7,6 +7,7 @@ use crate::{
 	process::{
 		exit::ExitStatus,
 		relation::{pgroup::ProcessGroup, session::Session, Pgid, Pid},
+		rusage::Usage,
 	},
 	sync::Locked,
 };
@@ -17,6 +18,8 @@ pub struct Zombie {
 	pub pgrp: Arc<ProcessGroup>,
 	pub sess: Arc<Locked<Session>>,
 	pub exit_status: ExitStatus,
+	/// usage of the process and its reaped children.
+	pub usage: Usage,
 }
 
 impl Zombie {
@@ -25,12 +28,14 @@ impl Zombie {
 		pgrp: Arc<ProcessGroup>,
 		sess: Arc<Locked<Session>>,
 		status: ExitStatus,
+		usage: Usage,
 	) -> Self {
 		Self {
 			pid,
 			pgrp,
 			sess,
 			exit_status: status,
+			usage,
 		}
 	}
 
@@ -0,0 +1,363 @@
+use alloc::sync::Arc;
+
+use crate::config::{KSTACK_RANK, TIMER_FREQUENCY_HZ, USTACK_LIMIT};
+use crate::mm::oom::total_pages;
+use crate::mm::user::verify::{verify_ptr, verify_ptr_mut};
+use crate::mm::util::rank_to_pages;
+use crate::process::fd_table::{NOFILE_CUR, NOFILE_MAX, NR_OPEN};
+use crate::process::process_tree::PROCESS_TREE;
+use crate::process::relation::Pid;
+use crate::process::signal::send_signal_to;
+use crate::process::signal::sig_code::SigCode;
+use crate::process::signal::sig_info::SigInfo;
+use crate::process::signal::sig_num::SigNum;
+use crate::process::task::{Task, CURRENT};
+use crate::syscall::errno::Errno;
+
+pub const RLIM_INFINITY: usize = usize::MAX;
+pub const RLIM_NLIMITS: usize = 16;
+
+#[repr(usize)]
+#[derive(Clone, Copy)]
+pub enum Resource {
+	/// cpu time in seconds.
+	Cpu = 0,
+	/// largest file in bytes.
+	Fsize = 1,
+	/// size of heap in bytes.
+	Data = 2,
+	Stack = 3,
+	/// largest core file in bytes. zero by default, so no core is dumped.
+	Core = 4,
+	/// tasks of the real user.
+	Nproc = 6,
+	/// one more than the largest file descriptor.
+	Nofile = 7,
+	/// size of address space in bytes.
+	As = 9,
+	/// real-time signals queued on a thread.
+	Sigpending = 11,
+}
+
+impl TryFrom<usize> for Resource {
+	type Error = Errno;
+
+	fn try_from(value: usize) -> Result<Self, Self::Error> {
+		match value {
+			0 => Ok(Self::Cpu),
+			1 => Ok(Self::Fsize),
+			2 => Ok(Self::Data),
+			3 => Ok(Self::Stack),
+			4 => Ok(Self::Core),
+			6 => Ok(Self::Nproc),
+			7 => Ok(Self::Nofile),
+			9 => Ok(Self::As),
+			11 => Ok(Self::Sigpending),
+			_ => Err(Errno::EINVAL),
+		}
+	}
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct RLimit {
+	pub cur: usize,
+	pub max: usize,
+}
+
+impl RLimit {
+	pub const fn new(cur: usize, max: usize) -> Self {
+		Self { cur, max }
+	}
+}
+
+/// `struct rlimit64` of `prlimit64`.
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct RLimit64 {
+	cur: u64,
+	max: u64,
+}
+
+impl From<RLimit> for RLimit64 {
+	fn from(value: RLimit) -> Self {
+		let widen = |x: usize| match x {
+			RLIM_INFINITY => u64::MAX,
+			x => x as u64,
+		};
+
+		Self {
+			cur: widen(value.cur),
+			max: widen(value.max),
+		}
+	}
+}
+
+impl From<RLimit64> for RLimit {
+	fn from(value: RLimit64) -> Self {
+		let narrow = |x: u64| usize::try_from(x).unwrap_or(RLIM_INFINITY);
+
+		Self {
+			cur: narrow(value.cur),
+			max: narrow(value.max),
+		}
+	}
+}
+
+/// half of memory can be used for kernel stacks of the tasks.
+fn default_nproc() -> usize {
+	(total_pages() / rank_to_pages(KSTACK_RANK) / 2).max(1)
+}
+
+/// resource limits of a process. inherited on fork and preserved across exec.
+#[derive(Clone)]
+pub struct RLimits([RLimit; RLIM_NLIMITS]);
+
+impl RLimits {
+	pub fn new() -> Self {
+		let mut limits = [RLimit::new(RLIM_INFINITY, RLIM_INFINITY); RLIM_NLIMITS];
+
+		limits[Resource::Stack as usize].cur = USTACK_LIMIT;
+		limits[Resource::Core as usize].cur = 0;
+		limits[Resource::Nproc as usize] = RLimit::new(default_nproc(), default_nproc());
+		limits[Resource::Nofile as usize] = RLimit::new(NOFILE_CUR, NOFILE_MAX);
+		limits[Resource::Sigpending as usize] = RLimit::new(default_nproc(), default_nproc());
+
+		Self(limits)
+	}
+
+	pub fn get(&self, resource: Resource) -> RLimit {
+		self.0[resource as usize]
+	}
+
+	/// only privileged task raises hard limit.
+	pub fn set(&mut self, resource: Resource, new: RLimit, privileged: bool) -> Result<(), Errno> {
+		if new.cur > new.max {
+			return Err(Errno::EINVAL);
+		}
+
+		let old = self.get(resource);
+		if new.max > old.max && !privileged {
+			return Err(Errno::EPERM);
+		}
+
+		if matches!(resource, Resource::Nofile) && new.max > NR_OPEN {
+			return Err(Errno::EPERM);
+		}
+
+		self.0[resource as usize] = new;
+		Ok(())
+	}
+}
+
+impl Default for RLimits {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+fn send_limit_signal(task: &Arc<Task>, num: SigNum) {
+	let info = SigInfo {
+		num,
+		pid: 0,
+		uid: 0,
+		code: SigCode::SI_KERNEL,
+		value: 0,
+	};
+
+	let _ = send_signal_to(task, &info);
+}
+
+/// `RLIMIT_NPROC` allows the real user of `task` one more task.
+///
+/// unlike linux, privileged user is limited as well. only its hard limit can be raised.
+pub fn check_nproc(task: &Arc<Task>) -> Result<(), Errno> {
+	let ext = task.user_ext_ok_or(Errno::EAGAIN)?;
+	let limit = ext.lock_rlimits().get(Resource::Nproc).cur;
+	let uid = task.get_uid();
+
+	let count = PROCESS_TREE
+		.lock()
+		.members()
+		.values()
+		.filter(|t| !t.is_kernel() && t.get_uid() == uid)
+		.count();
+
+	match count < limit {
+		true => Ok(()),
+		false => Err(Errno::EAGAIN),
+	}
+}
+
+/// bytes of `len` that may be written at `pos` under `RLIMIT_FSIZE` of current task.
+///
+/// sends `SIGXFSZ` if nothing can be written.
+pub fn limit_file_size(pos: usize, len: usize) -> Result<usize, Errno> {
+	let current = unsafe { CURRENT.get_ref() };
+	let Some(ext) = current.get_user_ext() else {
+		return Ok(len);
+	};
+
+	let limit = ext.lock_rlimits().get(Resource::Fsize).cur;
+
+	if pos >= limit {
+		send_limit_signal(current, SigNum::XFSZ);
+		return Err(Errno::EFBIG);
+	}
+
+	Ok(len.min(limit - pos))
+}
+
+/// check cpu time of the process, every second `task` runs. called by timer interrupt.
+///
+/// `SIGXCPU` is sent every second over the soft limit, and `SIGKILL` over the hard limit.
+pub fn tick_cpu_limit(task: &Arc<Task>) {
+	let usage = *task.lock_usage();
+	if (usage.utime + usage.stime) % TIMER_FREQUENCY_HZ != 0 {
+		return;
+	}
+
+	let Some(ext) = task.get_user_ext() else {
+		return;
+	};
+
+	let limit = ext.lock_rlimits().get(Resource::Cpu);
+	if limit.cur == RLIM_INFINITY {
+		return;
+	}
+
+	let usage = ext.get_thread_group().get_usage();
+	let seconds = (usage.utime + usage.stime) / TIMER_FREQUENCY_HZ;
+
+	if seconds >= limit.max {
+		send_limit_signal(task, SigNum::KILL);
+	} else if seconds >= limit.cur {
+		send_limit_signal(task, SigNum::XCPU);
+	}
+}
+
+fn find_task(pid: usize) -> Result<Arc<Task>, Errno> {
+	let current = unsafe { CURRENT.get_ref() };
+
+	let task = match pid {
+		0 => return Ok(current.clone()),
+		_ => PROCESS_TREE
+			.get_task(Pid::from_raw(pid))
+			.ok_or(Errno::ESRCH)?,
+	};
+
+	// same rule as ptrace.
+	match current.get_cred().can_trace(&task.get_cred()) {
+		true => Ok(task),
+		false => Err(Errno::EPERM),
+	}
+}
+
+/// get old limit and set new one, if given.
+fn do_prlimit(task: &Arc<Task>, resource: Resource, new: Option<RLimit>) -> Result<RLimit, Errno> {
+	let ext = task.user_ext_ok_or(Errno::ESRCH)?;
+	let privileged = unsafe { CURRENT.get_ref() }.is_privileged();
+
+	let old = {
+		let mut rlimits = ext.lock_rlimits();
+		let old = rlimits.get(resource);
+
+		if let Some(new) = new {
+			rlimits.set(resource, new, privileged)?;
+		}
+
+		old
+	};
+
+	if let (Resource::Nofile, Some(new)) = (resource, new) {
+		ext.lock_fd_table().set_limit(new.cur);
+	}
+
+	Ok(old)
+}
+
+pub fn sys_getrlimit(resource: usize, rlim: usize) -> Result<usize, Errno> {
+	let current = unsafe { CURRENT.get_ref() };
+	let resource = Resource::try_from(resource)?;
+
+	*verify_ptr_mut::<RLimit>(rlim, current)? = do_prlimit(current, resource, None)?;
+
+	Ok(0)
+}
+
+pub fn sys_setrlimit(resource: usize, rlim: usize) -> Result<usize, Errno> {
+	let current = unsafe { CURRENT.get_ref() };
+	let resource = Resource::try_from(resource)?;
+	let new = *verify_ptr::<RLimit>(rlim, current)?;
+
+	do_prlimit(current, resource, Some(new)).map(|_| 0)
+}
+
+pub fn sys_prlimit(
+	pid: usize,
+	resource: usize,
+	new_rlim: usize,
+	old_rlim: usize,
+) -> Result<usize, Errno> {
+	let current = unsafe { CURRENT.get_ref() };
+	let resource = Resource::try_from(resource)?;
+
+	let new = match new_rlim {
+		0 => None,
+		ptr => Some(RLimit::from(*verify_ptr::<RLimit64>(ptr, current)?)),
+	};
+	let old_rlim = match old_rlim {
+		0 => None,
+		ptr => Some(verify_ptr_mut::<RLimit64>(ptr, current)?),
+	};
+
+	let task = find_task(pid)?;
+	let old = do_prlimit(&task, resource, new)?;
+
+	if let Some(old_rlim) = old_rlim {
+		*old_rlim = old.into();
+	}
+
+	Ok(0)
+}
+
+mod test {
+	use super::*;
+	use kfs_macro::ktest;
+
+	#[ktest(rlimit)]
+	fn raise_hard_limit() {
+		let mut rlimits = RLimits::new();
+
+		rlimits
+			.set(Resource::Cpu, RLimit::new(10, 20), false)
+			.unwrap();
+		assert!(matches!(
+			rlimits.set(Resource::Cpu, RLimit::new(10, 30), false),
+			Err(Errno::EPERM)
+		));
+		assert!(matches!(
+			rlimits.set(Resource::Cpu, RLimit::new(30, 20), false),
+			Err(Errno::EINVAL)
+		));
+
+		rlimits
+			.set(Resource::Cpu, RLimit::new(10, 30), true)
+			.unwrap();
+		assert_eq!(rlimits.get(Resource::Cpu).max, 30);
+	}
+
+	#[ktest(rlimit)]
+	fn rlimit64_infinity() {
+		let limit = RLimit::new(100, RLIM_INFINITY);
+		let wide = RLimit64::from(limit);
+		assert_eq!(wide.max, u64::MAX);
+
+		let narrow = RLimit::from(RLimit64 {
+			cur: u64::MAX - 1,
+			max: u64::MAX,
+		});
+		assert_eq!(narrow.cur, RLIM_INFINITY);
+		assert_eq!(narrow.max, RLIM_INFINITY);
+	}
+}
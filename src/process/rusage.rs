@@ -0,0 +1,163 @@
+use core::ops::AddAssign;
+
+use crate::config::TIMER_FREQUENCY_HZ;
+
+/// resource usage of a task, or sum of them.
+#[derive(Clone, Copy, Default)]
+pub struct Usage {
+	/// timer ticks spent in user mode.
+	pub utime: usize,
+	/// timer ticks spent in kernel mode.
+	pub stime: usize,
+	/// page faults resolved without I/O.
+	pub minflt: usize,
+	/// page faults that read the page from file or swap.
+	pub majflt: usize,
+	/// context switches by sleeping.
+	pub nvcsw: usize,
+	/// context switches by preemption.
+	pub nivcsw: usize,
+}
+
+impl Usage {
+	pub const fn new() -> Self {
+		Self {
+			utime: 0,
+			stime: 0,
+			minflt: 0,
+			majflt: 0,
+			nvcsw: 0,
+			nivcsw: 0,
+		}
+	}
+
+	pub fn tick(&mut self, user: bool) {
+		match user {
+			true => self.utime += 1,
+			false => self.stime += 1,
+		}
+	}
+
+	pub fn fault(&mut self, major: bool) {
+		match major {
+			true => self.majflt += 1,
+			false => self.minflt += 1,
+		}
+	}
+
+	pub fn context_switch(&mut self, voluntary: bool) {
+		match voluntary {
+			true => self.nvcsw += 1,
+			false => self.nivcsw += 1,
+		}
+	}
+}
+
+impl AddAssign for Usage {
+	fn add_assign(&mut self, rhs: Self) {
+		self.utime += rhs.utime;
+		self.stime += rhs.stime;
+		self.minflt += rhs.minflt;
+		self.majflt += rhs.majflt;
+		self.nvcsw += rhs.nvcsw;
+		self.nivcsw += rhs.nivcsw;
+	}
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+pub struct TimeVal {
+	pub sec: isize,
+	pub usec: isize,
+}
+
+impl TimeVal {
+	pub fn from_ticks(ticks: usize) -> Self {
+		let usec = ticks as u64 * 1_000_000 / TIMER_FREQUENCY_HZ as u64;
+
+		Self {
+			sec: (usec / 1_000_000) as isize,
+			usec: (usec % 1_000_000) as isize,
+		}
+	}
+
+	pub fn from_nano(nano: u64) -> Self {
+		Self {
+			sec: (nano / 1_000_000_000) as isize,
+			usec: (nano % 1_000_000_000 / 1000) as isize,
+		}
+	}
+
+	pub fn nano(&self) -> u64 {
+		self.sec as u64 * 1_000_000_000 + self.usec as u64 * 1000
+	}
+
+	pub fn is_valid(&self) -> bool {
+		self.sec >= 0 && (0..1_000_000).contains(&self.usec)
+	}
+}
+
+/// `struct rusage` of user space.
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+pub struct RUsage {
+	pub utime: TimeVal,
+	pub stime: TimeVal,
+	pub maxrss: isize,
+	pub ixrss: isize,
+	pub idrss: isize,
+	pub isrss: isize,
+	pub minflt: isize,
+	pub majflt: isize,
+	pub nswap: isize,
+	pub inblock: isize,
+	pub oublock: isize,
+	pub msgsnd: isize,
+	pub msgrcv: isize,
+	pub nsignals: isize,
+	pub nvcsw: isize,
+	pub nivcsw: isize,
+}
+
+impl From<Usage> for RUsage {
+	fn from(usage: Usage) -> Self {
+		Self {
+			utime: TimeVal::from_ticks(usage.utime),
+			stime: TimeVal::from_ticks(usage.stime),
+			minflt: usage.minflt as isize,
+			majflt: usage.majflt as isize,
+			nvcsw: usage.nvcsw as isize,
+			nivcsw: usage.nivcsw as isize,
+			..Default::default()
+		}
+	}
+}
+
+mod test {
+	use super::*;
+	use kfs_macro::ktest;
+
+	#[ktest(rusage)]
+	fn ticks_to_timeval() {
+		let tv = TimeVal::from_ticks(TIMER_FREQUENCY_HZ * 3 + TIMER_FREQUENCY_HZ / 2);
+
+		assert_eq!(tv.sec, 3);
+		assert_eq!(tv.usec, 500_000);
+	}
+
+	#[ktest(rusage)]
+	fn sum_usage() {
+		let mut usage = Usage::new();
+		usage.tick(true);
+		usage.fault(true);
+		usage.context_switch(false);
+
+		let mut total = usage;
+		total += usage;
+
+		assert_eq!(total.utime, 2);
+		assert_eq!(total.stime, 0);
+		assert_eq!(total.majflt, 2);
+		assert_eq!(total.nivcsw, 2);
+	}
+}
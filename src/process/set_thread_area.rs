@@ -6,6 +6,14 @@ use crate::{
 
 use super::task::CURRENT;
 
+/// index of TLS slot for GDT `entry_number`.
+pub fn tls_index(entry_number: i32) -> Option<usize> {
+	match entry_number {
+		x @ 6..=8 => Some(x as usize - 6),
+		_ => None,
+	}
+}
+
 pub fn sys_set_thread_area(user_desc: usize) -> Result<usize, Errno> {
 	let current = unsafe { CURRENT.get_ref() };
 
@@ -15,8 +23,7 @@ pub fn sys_set_thread_area(user_desc: usize) -> Result<usize, Errno> {
 
 	let idx = match user_desc.entry_number {
 		-1 => 0 as usize,
-		x @ 6..=8 => x as usize - 6,
-		_ => return Err(Errno::EINVAL),
+		x => tls_index(x).ok_or(Errno::EINVAL)?,
 	};
 
 	let gdt = unsafe { CPU_GDT.get_mut() };
@@ -34,8 +41,12 @@ pub fn sys_set_thread_area(user_desc: usize) -> Result<usize, Errno> {
 	Ok(0)
 }
 
-pub fn sys_set_tid_address(_tid_ptr: usize) -> Result<usize, Errno> {
+pub fn sys_set_tid_address(tid_ptr: usize) -> Result<usize, Errno> {
 	let current = unsafe { CURRENT.get_ref() };
 
+	current
+		.user_ext_ok_or(Errno::EPERM)?
+		.set_clear_child_tid(tid_ptr);
+
 	Ok(current.get_pid().as_raw())
 }
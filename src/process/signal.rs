@@ -5,37 +5,44 @@ pub mod sig_handler;
 pub mod sig_info;
 pub mod sig_mask;
 pub mod sig_num;
+pub mod sig_queue;
 
 use core::{
 	array,
-	mem::{self, size_of, variant_count},
+	mem::{self, align_of, offset_of, size_of, variant_count},
 	ptr::copy_nonoverlapping,
 };
 
-use alloc::{
-	collections::LinkedList,
-	sync::{Arc, Weak},
-};
+use alloc::sync::{Arc, Weak};
 
 use crate::{
 	config::TRAMPOLINE_BASE,
 	interrupt::InterruptFrame,
+	mm::user::{verify::verify_region, vma::AreaFlag},
 	pr_debug,
 	process::{
-		exit::exit_with_signal,
+		coredump::do_coredump,
+		exit::{exit_with_core_dump, exit_with_signal, kill_other_threads, ExitStatus},
 		process_tree::PROCESS_TREE,
+		ptrace,
 		relation::session::Session,
+		rlimit::RLIM_INFINITY,
 		signal::{sig_flag::SigFlag, sig_handler::SigHandler, sig_num::SigNum},
 		task::{Task, CURRENT},
 	},
 	scheduler::sleep::{sleep_and_yield, wake_up, Sleep},
 	sync::{Locked, LockedGuard},
 	syscall::{errno::Errno, signal::is_syscall_restart},
+	x86::fpu::FpuState,
 };
 
 use self::{
-	sig_code::SigCode, sig_ctx::SigCtx, sig_handler::SigAction, sig_info::SigInfo,
+	sig_code::SigCode,
+	sig_ctx::{MContext, SigFrame, SigStack, UContext},
+	sig_handler::SigAction,
+	sig_info::{SigInfo, UserSigInfo},
 	sig_mask::SigMask,
+	sig_queue::SigQueue,
 };
 
 extern "C" {
@@ -57,40 +64,59 @@ pub struct Restart;
 /// 2. mask
 /// 3. queue
 pub struct Signal {
-	queue: Locked<LinkedList<SigInfo>>,
+	queue: Locked<SigQueue>,
 	mask: Locked<SigMask>,
-	pub table: Locked<[SigHandler; variant_count::<SigNum>()]>,
+	altstack: Locked<SigStack>,
+	/// shared by tasks cloned with `CLONE_SIGHAND`
+	pub table: Arc<Locked<[SigHandler; variant_count::<SigNum>()]>>,
 }
 
 impl Signal {
 	pub fn new() -> Self {
 		Self {
 			mask: Locked::new(SigMask::empty()),
-			queue: Locked::new(LinkedList::new()),
-			table: Locked::new(array::from_fn(|i| {
+			queue: Locked::new(SigQueue::new()),
+			altstack: Locked::new(SigStack::disabled()),
+			table: Arc::new(Locked::new(array::from_fn(|i| {
 				SigHandler::default(SigNum::from_usize(i + 1).unwrap())
-			})),
+			}))),
 		}
 	}
 
 	pub fn clone_for_fork(&self) -> Self {
 		Self {
-			queue: Locked::new(LinkedList::new()),
+			queue: Locked::new(SigQueue::new()),
 			mask: self.mask.clone(),
+			altstack: self.altstack.clone(),
+			table: Arc::new(self.table.as_ref().clone()),
+		}
+	}
+
+	/// pending signals and mask are per thread, handlers are shared.
+	/// new thread runs on its own stack, so alternate stack is not inherited.
+	pub fn clone_for_thread(&self) -> Self {
+		Self {
+			queue: Locked::new(SigQueue::new()),
+			mask: self.mask.clone(),
+			altstack: Locked::new(SigStack::disabled()),
 			table: self.table.clone(),
 		}
 	}
 
-	pub fn do_for_exec(&self) {
+	/// secure exec resets ignored signals as well, so that whom executed
+	/// the program cannot make it immune to them.
+	pub fn do_for_exec(&self, secure: bool) {
 		let mut table = self.table.lock();
 
 		*self.mask.lock() = SigMask::empty();
 
 		self.queue.lock().clear();
 
+		*self.altstack.lock() = SigStack::disabled();
+
 		for (i, t) in table.iter_mut().enumerate() {
 			match t {
-				SigHandler::Ignore => {}
+				SigHandler::Ignore if !secure => {}
 				x => *x = SigHandler::default(SigNum::from_usize(i + 1).unwrap()),
 			}
 		}
@@ -104,88 +130,147 @@ impl Signal {
 		self.mask.lock()
 	}
 
-	pub fn recv_signal(&self, info: SigInfo) {
+	pub fn lock_altstack(&self) -> LockedGuard<'_, SigStack> {
+		self.altstack.lock()
+	}
+
+	/// `limit` is the number of signals that can be queued. (`RLIMIT_SIGPENDING`)
+	pub fn recv_signal(&self, info: SigInfo, limit: usize) -> Result<(), Errno> {
 		let table = self.table.lock();
 		if let SigHandler::Ignore = table[info.num.index()] {
-			return;
-		}
-
-		let mask = self.mask.lock();
-		if mask.contains(info.num.into()) {
-			return;
+			return Ok(());
 		}
 
+		// blocked signals stay pending until unblocked or waited for.
 		let mut queue = self.queue.lock();
 
 		use SigNum::*;
 		match info.num {
-			STOP | TSTP | TTIN | TTOU => {
-				let _ = queue.extract_if(|info| info.num == CONT);
-				queue.push_front(info);
-			}
-			CONT => {
-				let _ = queue.extract_if(|info| info.num.is_stop());
-				queue.push_front(info);
-			}
-			KILL => queue.push_front(info),
-			_ => queue.push_back(info),
+			STOP | TSTP | TTIN | TTOU => queue.discard(SigMask::CONT),
+			CONT => queue.discard(SigMask::STOP | SigMask::TSTP | SigMask::TTIN | SigMask::TTOU),
+			_ => {}
 		}
+
+		queue.push(info, limit)
+	}
+
+	/// queue a signal caused by the task itself.
+	///
+	/// it is neither blocked nor ignored. if it was, the default action is taken,
+	/// so that a fault inside its handler doesn't loop forever.
+	pub fn force_signal(&self, info: SigInfo) {
+		let mut table = self.table.lock();
+		let mut mask = self.mask.lock();
+
+		let num = info.num;
+		if mask.contains(num.into()) || matches!(table[num.index()], SigHandler::Ignore) {
+			table[num.index()] = SigHandler::default(num);
+			*mask -= num.into();
+		}
+
+		// standard signal is never refused.
+		let _ = self.queue.lock().push(info, RLIM_INFINITY);
+	}
+
+	pub fn discard(&self, set: SigMask) {
+		self.queue.lock().discard(set);
+	}
+
+	/// pending signals, including blocked ones.
+	pub fn pending(&self) -> SigMask {
+		self.queue.lock().pending()
+	}
+
+	/// take a pending signal in `set`, regardless of the mask.
+	pub fn take_signal(&self, set: SigMask) -> Option<SigInfo> {
+		self.queue.lock().pop(set)
 	}
 
 	pub fn do_signal(&self, frame: &InterruptFrame, syscall_ret: isize) -> Option<Restart> {
-		let info = self.get_signal_event()?;
+		let mut info = self.get_signal_event()?;
+
+		// tracer decides which signal is delivered, if any.
+		let current = unsafe { CURRENT.get_ref() };
+		if info.num != SigNum::KILL && ptrace::is_traced(current) {
+			match ptrace::signal_stop(info.num) {
+				Some(num) if num != info.num => {
+					info = SigInfo {
+						num,
+						pid: current.get_ppid().as_raw(),
+						uid: 0,
+						code: SigCode::SI_USER,
+						value: 0,
+					}
+				}
+				Some(_) => {}
+				None => {
+					return is_syscall_restart(frame.eax, syscall_ret, SigFlag::DEFAULT)
+						.then_some(Restart)
+				}
+			}
+		}
+
 		let handler = self.get_handler(&info.num);
 		match &handler {
 			SigHandler::Some(act) => unsafe {
 				let o_mask = self.replace_mask(act, &info);
-				let sig_ctx = SigCtx::new(frame, o_mask, syscall_ret);
-				self.do_action(act, &info, &sig_ctx)
+				self.do_action(act, &info, frame, o_mask, syscall_ret)
 			},
-			x => self.do_signal_default(x, info.num),
+			x => self.do_signal_default(x, &info, frame),
 		};
 
 		is_syscall_restart(frame.eax, syscall_ret, handler.get_flag()).then_some(Restart)
 	}
 
-	pub fn do_signal_repeat(&self, frame: &InterruptFrame) -> Option<()> {
-		let info = self.get_signal_event()?;
-		let handler = self.get_handler(&info.num);
-		match &handler {
-			SigHandler::Some(act) => unsafe {
-				self.replace_mask(act, &info);
-				self.do_action_repeat(act, &info, frame);
-			},
-			x => self.do_signal_default(x, info.num),
-		}
-	}
+	fn do_signal_default(
+		&self,
+		handler: &SigHandler,
+		info: &SigInfo,
+		frame: &InterruptFrame,
+	) -> Option<()> {
+		let num = info.num;
 
-	fn do_signal_default(&self, handler: &SigHandler, num: SigNum) -> Option<()> {
 		use SigHandler::*;
 		match handler {
 			Terminate | Core => {
 				let current = unsafe { CURRENT.get_ref() };
-				let parent = {
-					let ptree = PROCESS_TREE.lock();
-					let ppid = current.get_ppid();
-					ptree.get(&ppid).cloned()
-				};
-
-				if let Option::Some(parent) = parent {
-					let info = SigInfo {
-						num: SigNum::CHLD,
-						pid: current.get_pid().as_raw(),
-						uid: current.get_uid(),
-						code: SigCode::CLD_EXITED,
-					};
-
-					let _ = send_signal_to(&parent, &info);
+				let group_exiting = current
+					.get_user_ext()
+					.is_some_and(|ext| ext.get_thread_group().get_exit_status().is_some());
+
+				if group_exiting {
+					exit_with_signal(num)
+				}
+
+				// other threads must not change memory while it is dumped.
+				let dumped = matches!(handler, Core)
+					&& kill_other_threads().is_ok()
+					&& do_coredump(info, frame);
+
+				if dumped {
+					exit_with_core_dump(num)
 				}
 
 				exit_with_signal(num)
 			}
 			Ignore | Continue => Option::Some(()),
 			Stop => {
+				let current = unsafe { CURRENT.get_ref() };
+				let group = current.get_user_ext().map(|ext| ext.get_thread_group());
+
+				// reported to `waitpid` with `WUNTRACED` until continued.
+				// every thread stops, but the parent is notified once.
+				let status = ExitStatus::new_stopped(num as u8);
+				let first = group.map_or(true, |g| g.enter_stop(status));
+				if first {
+					notify_parent(current, SigNum::CHLD, SigCode::CLD_STOPPED);
+				}
+
 				sleep_and_yield(Sleep::Deep);
+
+				if let Option::Some(group) = group {
+					group.leave_stop();
+				}
 				Option::Some(())
 			}
 			_ => unreachable!(),
@@ -195,50 +280,67 @@ impl Signal {
 	/// # Safety
 	///
 	/// Must clean up lock and global variable before this function call.
-	unsafe fn do_action(&self, act: &SigAction, info: &SigInfo, ctx: &SigCtx) -> ! {
-		let mut esp = ctx.intr_frame.esp;
-		push_to_user_stack(
-			&mut esp,
-			ctx as *const SigCtx as *const u8,
-			size_of::<SigCtx>(),
-		);
-		push_to_user_stack(
-			&mut esp,
-			info as *const SigInfo as *const u8,
-			size_of::<SigInfo>(),
-		);
-
-		let mut func_frame = [0; 4];
-		make_function_frame(&mut func_frame, esp, info.num);
-		push_to_user_stack(
-			&mut esp,
-			func_frame.as_ptr().cast(),
-			func_frame.len() * size_of::<usize>(),
-		);
-		// pr_debug!("sig_action: go_to_signal_handler: esp {:x}", esp);
-		go_to_signal_handler(&ctx.intr_frame as *const InterruptFrame, esp, act.handler());
-	}
-
-	/// # Safety
-	///
-	/// Must clean up lock and global variable before this function call.
-	unsafe fn do_action_repeat(
+	unsafe fn do_action(
 		&self,
 		act: &SigAction,
 		info: &SigInfo,
 		frame: &InterruptFrame,
+		o_mask: SigMask,
+		syscall_ret: isize,
 	) -> ! {
-		let info_ptr = frame.ecx as *mut SigInfo;
-		copy_nonoverlapping(info as *const SigInfo, info_ptr, 1);
-
-		let mut esp = frame.esp;
-		let trampoline = trampoline_address(signal_trampoline as usize);
-		push_to_user_stack(
-			&mut esp,
-			&trampoline as *const usize as *const u8,
-			size_of::<usize>(),
-		);
-		// pr_debug!("sig_action_repeat: go_to_signal_handler: esp {:x}", esp);
+		let altstack = *self.altstack.lock();
+		let on_altstack = altstack.contains(frame.esp);
+
+		let base = match act.flag().contains(SigFlag::OnStack) && !altstack.is_disabled() {
+			true if !on_altstack => altstack.sp + altstack.size,
+			_ => frame.esp,
+		};
+
+		// fpu state goes above the frame, and `ret` of the frame is placed
+		// as if the handler was called with 16 byte aligned stack.
+		let layout = base
+			.checked_sub(size_of::<FpuState>())
+			.map(|x| x & !(align_of::<FpuState>() - 1))
+			.and_then(|fpstate| {
+				let frame = fpstate.checked_sub(size_of::<SigFrame>())? & !0xf;
+				Some((fpstate, frame.checked_sub(size_of::<usize>())?))
+			});
+
+		let current = CURRENT.get_ref();
+		let layout = layout.filter(|(_, esp)| {
+			verify_region(*esp, base - esp, current, AreaFlag::Writable).is_ok()
+		});
+
+		// no room for the frame. (e.g. stack overflow without alternate stack)
+		let Some((fpstate, esp)) = layout else {
+			exit_with_signal(SigNum::SEGV);
+		};
+
+		let sig_frame = SigFrame {
+			ret: trampoline_address(signal_trampoline as usize),
+			num: info.num as usize,
+			info_ptr: esp + offset_of!(SigFrame, info),
+			uc_ptr: esp + offset_of!(SigFrame, uc),
+			info: UserSigInfo::from(info),
+			uc: UContext {
+				flags: 0,
+				link: 0,
+				stack: altstack.report(frame.esp),
+				mcontext: MContext::new(frame, o_mask, fpstate),
+				sigmask: o_mask,
+			},
+			handler: frame.handler,
+			syscall_ret,
+		};
+
+		let fpu = FpuState::capture();
+		copy_nonoverlapping(&fpu, fpstate as *mut FpuState, 1);
+		copy_nonoverlapping(&sig_frame, esp as *mut SigFrame, 1);
+
+		// handler starts with clean FPU state. (restored by sigreturn)
+		FpuState::new().restore();
+
+		// pr_debug!("sig_action: go_to_signal_handler: esp {:x}", esp);
 		go_to_signal_handler(frame as *const InterruptFrame, esp, act.handler());
 	}
 
@@ -246,7 +348,7 @@ impl Signal {
 		let mut lock = self.mask.lock();
 		let o_mask = *lock;
 		let n_mask = if act.flag().contains(SigFlag::NoDefer) {
-			o_mask | act.mask() - info.num.into()
+			o_mask | (act.mask() - info.num.into())
 		} else {
 			o_mask | act.mask() | info.num.into()
 		};
@@ -258,19 +360,7 @@ impl Signal {
 		let mask = self.mask.lock();
 		let mut queue = self.queue.lock();
 
-		let mut ret = None;
-		let mut not = LinkedList::new();
-		while let Some(info) = queue.pop_front() {
-			if mask.contains(info.num.into()) {
-				not.push_back(info)
-			} else {
-				ret = Some(info);
-				break;
-			}
-		}
-		queue.append(&mut not);
-
-		ret
+		queue.pop(mask.complement())
 	}
 
 	pub fn get_handler(&self, num: &SigNum) -> SigHandler {
@@ -284,25 +374,6 @@ impl Signal {
 	}
 }
 
-/// push data to user stack.
-unsafe fn push_to_user_stack(esp: &mut usize, src: *const u8, len: usize) {
-	*esp -= len;
-	copy_nonoverlapping(src, (*esp) as *mut _, len);
-}
-
-fn make_function_frame(frame: &mut [usize], user_esp: usize, sig_num: SigNum) {
-	let trampoline = trampoline_address(signal_trampoline as usize);
-
-	frame[0] = trampoline;
-	frame[1] = sig_num as usize;
-
-	// SigInfo pointer
-	frame[2] = user_esp;
-
-	// SigCtx pointer
-	frame[3] = user_esp + size_of::<SigInfo>()
-}
-
 /// # Safety
 ///
 /// - CURRENT should be a user task.
@@ -313,15 +384,10 @@ pub unsafe fn poll_signal_queue() -> Result<(), Errno> {
 		.expect("user task")
 		.signal
 		.as_ref();
-	let queue = signal.queue.lock();
 	let mask = signal.mask.lock();
+	let queue = signal.queue.lock();
 
-	let count = queue
-		.iter()
-		.filter(|info| !mask.contains(info.num.into()))
-		.count();
-
-	if count == 0 {
+	if (queue.pending() - *mask).is_empty() {
 		Ok(())
 	} else {
 		// pr_debug!("poll_signal_queue: there is signal!");
@@ -329,6 +395,43 @@ pub unsafe fn poll_signal_queue() -> Result<(), Errno> {
 	}
 }
 
+/// report state change of `current` process to its parent with `num`.
+pub fn notify_parent(current: &Arc<Task>, num: SigNum, code: SigCode) {
+	let parent = {
+		let ptree = PROCESS_TREE.lock();
+		let ppid = current.get_ppid();
+		ptree.get(&ppid).cloned()
+	};
+
+	if let Some(parent) = parent {
+		let info = SigInfo {
+			num,
+			pid: current.get_tgid().as_raw(),
+			uid: current.get_uid(),
+			code,
+			value: 0,
+		};
+
+		let _ = send_signal_to_process(&parent, &info);
+	}
+}
+
+/// deliver a signal for a fault of current user task at `addr`.
+pub fn force_fault_signal(frame: &InterruptFrame, num: SigNum, code: SigCode, addr: usize) {
+	let current = unsafe { CURRENT.get_ref() };
+	let signal = &current.get_user_ext().expect("user task").signal;
+
+	signal.force_signal(SigInfo {
+		num,
+		pid: 0,
+		uid: 0,
+		code,
+		value: addr,
+	});
+
+	signal.do_signal(frame, 0);
+}
+
 pub fn send_signal_to(task: &Arc<Task>, sig_info: &SigInfo) -> Result<(), Errno> {
 	if task.get_pid().as_raw() == 1 {
 		return Ok(()); // ignore signal
@@ -346,6 +449,56 @@ pub fn send_signal_to(task: &Arc<Task>, sig_info: &SigInfo) -> Result<(), Errno>
 	Ok(())
 }
 
+/// send a signal to the process of `task`, which may be an exited leader.
+///
+/// `SIGKILL` and stop signals to be taken by default reach every thread.
+/// others are queued on a thread not blocking it, or any thread if none.
+pub fn send_signal_to_process(task: &Arc<Task>, sig_info: &SigInfo) -> Result<(), Errno> {
+	if task.get_tgid().as_raw() == 1 {
+		return Ok(()); // ignore signal
+	}
+
+	let ext = task.user_ext_ok_or(Errno::EPERM)?;
+	let threads = ext.get_thread_group().threads();
+	let num = sig_info.num;
+
+	let blocks = |t: &Arc<Task>| {
+		t.get_user_ext()
+			.map_or(true, |ext| ext.signal.lock_mask().contains(num.into()))
+	};
+
+	let target = threads
+		.iter()
+		.find(|t| !blocks(t))
+		.or(threads.first())
+		.ok_or(Errno::ESRCH)?;
+
+	let every = num == SigNum::KILL || matches!(ext.signal.get_handler(&num), SigHandler::Stop);
+	if every {
+		for thread in &threads {
+			let _ = send_signal_to(thread, sig_info);
+		}
+		return Ok(());
+	}
+
+	// stopped threads other than `target` resume as well.
+	if num == SigNum::CONT {
+		let stops = SigMask::STOP | SigMask::TSTP | SigMask::TTIN | SigMask::TTOU;
+		let resume = ext.signal.is_default(&SigNum::CONT);
+
+		for thread in threads.iter().filter(|t| !Arc::ptr_eq(t, target)) {
+			if let Some(ext) = thread.get_user_ext() {
+				ext.signal.discard(stops);
+			}
+			if resume {
+				wake_up(thread, Sleep::Deep);
+			}
+		}
+	}
+
+	send_signal_to(target, sig_info)
+}
+
 pub fn send_signal_to_foreground(
 	sess: &Weak<Locked<Session>>,
 	num: SigNum,
@@ -372,8 +525,9 @@ pub fn send_signal_to_foreground(
 				pid: task.get_pid().as_raw(),
 				uid: task.get_uid(),
 				code,
+				value: 0,
 			};
-			let _ = send_signal_to(&task, &sig_info);
+			let _ = send_signal_to_process(&task, &sig_info);
 		}
 	}
 	Ok(())
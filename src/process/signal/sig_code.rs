@@ -30,7 +30,9 @@ pub enum SigCode {
 	CLD_TRAPPED,
 	CLD_STOPPED,
 	CLD_CONTINUED,
-	// SI_QUEUE,
+	SI_QUEUE,
+	TRAP_BRKPT, // Process breakpoint.
+	TRAP_TRACE, // Process trace trap.
 	// SI_ASYNCIO,
 	// ILL_ILLADR,  // Addressing mode
 	// ILL_COPROC,  // Coprocessor error
@@ -39,8 +41,6 @@ pub enum SigCode {
 	// BUS_OBJERR,  // Object-specific hardware error. ?
 	// BUS_MCEERR_AR,
 	// BUS_MCEERR_AO,
-	// TRAP_BRKPT,
-	// TRAP_TRACE,
 	// TRAP_BRANCH,
 	// TRAP_HWBKPT,
 	// POLL_IN,
@@ -51,3 +51,33 @@ pub enum SigCode {
 	// POLL_HUP,
 	// SYS_SECCOMP,
 }
+
+impl SigCode {
+	/// raised by a fault. (`SigInfo::value` is the address)
+	pub fn is_fault(&self) -> bool {
+		use SigCode::*;
+		(ILL_ILLOPC as usize..=BUS_ADRALN as usize).contains(&(*self as usize))
+			|| matches!(self, TRAP_BRKPT | TRAP_TRACE)
+	}
+
+	/// `si_code` value of linux.
+	pub fn as_raw(&self) -> i32 {
+		use SigCode::*;
+		match self {
+			SI_USER => 0,
+			SI_KERNEL => 0x80,
+			SI_QUEUE => -1,
+			SI_TIMER => -2,
+			SI_SIGIO => -5,
+			SI_TKILL => -6,
+			ILL_ILLOPC | FPE_INTDIV | SEGV_MAPERR | BUS_ADRALN | TRAP_BRKPT | CLD_EXITED => 1,
+			ILL_ILLOPN | FPE_INTOVF | SEGV_ACCERR | TRAP_TRACE | CLD_KILLED => 2,
+			FPE_FLTDIV | SEGV_BNDERR | CLD_DUMPED => 3,
+			ILL_ILLTRP | FPE_FLTOVF | CLD_TRAPPED => 4,
+			ILL_PRVOPC | FPE_FLTUND | CLD_STOPPED => 5,
+			ILL_PRVREG | FPE_FLTRES | CLD_CONTINUED => 6,
+			FPE_FLTINV => 7,
+			ILL_BADSTK => 8,
+		}
+	}
+}
@@ -1,27 +1,189 @@
 use crate::interrupt::InterruptFrame;
 
-use super::sig_mask::SigMask;
+use super::{sig_info::UserSigInfo, sig_mask::SigMask};
 
-#[derive(Debug)]
+pub const SS_ONSTACK: i32 = 1;
+pub const SS_DISABLE: i32 = 2;
+
+pub const MINSIGSTKSZ: usize = 2048;
+
+/// `stack_t`
+#[derive(Debug, Clone, Copy)]
 #[repr(C)]
-pub struct SigCtx {
-	pub intr_frame: InterruptFrame,
-	pub mask: SigMask,
-	pub syscall_ret: isize,
+pub struct SigStack {
+	pub sp: usize,
+	pub flags: i32,
+	pub size: usize,
 }
 
-// struct sigcontext {
-//   ...
-//   struct _fpstate * fpstate;
-//   unsigned long cr2;
-// };
+impl SigStack {
+	pub const fn disabled() -> Self {
+		Self {
+			sp: 0,
+			flags: SS_DISABLE,
+			size: 0,
+		}
+	}
+
+	pub fn is_disabled(&self) -> bool {
+		self.flags & SS_DISABLE != 0
+	}
+
+	/// the stack grows down from `sp + size`.
+	pub fn contains(&self, esp: usize) -> bool {
+		!self.is_disabled() && self.sp < esp && esp <= self.sp + self.size
+	}
 
-impl SigCtx {
-	pub fn new(intr_frame: &InterruptFrame, mask: SigMask, syscall_ret: isize) -> Self {
+	/// as reported to user, with `SS_ONSTACK` if `esp` is on it.
+	pub fn report(&self, esp: usize) -> Self {
+		let flags = match self.contains(esp) {
+			true => SS_ONSTACK,
+			false => self.flags,
+		};
+
+		Self { flags, ..*self }
+	}
+}
+
+/// `struct sigcontext` of i386.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct MContext {
+	pub gs: usize,
+	pub fs: usize,
+	pub es: usize,
+	pub ds: usize,
+	pub edi: usize,
+	pub esi: usize,
+	pub ebp: usize,
+	pub esp: usize,
+	pub ebx: usize,
+	pub edx: usize,
+	pub ecx: usize,
+	pub eax: usize,
+	pub trapno: usize,
+	pub err: usize,
+	pub eip: usize,
+	pub cs: usize,
+	pub eflags: usize,
+	pub esp_at_signal: usize,
+	pub ss: usize,
+	pub fpstate: usize,
+	pub oldmask: usize,
+	pub cr2: usize,
+}
+
+impl MContext {
+	/// eflags a handler may change. (others are kept)
+	pub const USER_EFLAGS: usize = 0x50dd5;
+
+	pub fn new(frame: &InterruptFrame, mask: SigMask, fpstate: usize) -> Self {
 		Self {
-			intr_frame: intr_frame.clone(),
-			mask,
-			syscall_ret,
+			gs: frame.gs,
+			fs: frame.fs,
+			es: frame.es,
+			ds: frame.ds,
+			edi: frame.edi,
+			esi: frame.esi,
+			ebp: frame.ebp,
+			esp: frame.esp,
+			ebx: frame.ebx,
+			edx: frame.edx,
+			ecx: frame.ecx,
+			eax: frame.eax,
+			trapno: 0,
+			err: frame.error_code,
+			eip: frame.eip,
+			cs: frame.cs,
+			eflags: frame.eflags,
+			esp_at_signal: frame.esp,
+			ss: frame.ss,
+			fpstate,
+			oldmask: mask.bits() as usize,
+			cr2: 0,
 		}
 	}
+
+	/// write registers back to `frame`.
+	///
+	/// segments and privileged eflags are not taken from user,
+	/// so that the task can't return to another privilege level.
+	pub fn restore(&self, frame: &mut InterruptFrame) {
+		frame.edi = self.edi;
+		frame.esi = self.esi;
+		frame.ebp = self.ebp;
+		frame.esp = self.esp;
+		frame.ebx = self.ebx;
+		frame.edx = self.edx;
+		frame.ecx = self.ecx;
+		frame.eax = self.eax;
+		frame.eip = self.eip;
+		frame.eflags = (frame.eflags & !Self::USER_EFLAGS) | (self.eflags & Self::USER_EFLAGS);
+	}
+}
+
+/// `ucontext_t`
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct UContext {
+	pub flags: usize,
+	pub link: usize,
+	pub stack: SigStack,
+	pub mcontext: MContext,
+	pub sigmask: SigMask,
+}
+
+/// pushed on user stack when a handler is called.
+///
+/// it starts as `rt_sigframe` of linux does, followed by what only the kernel uses.
+/// fpu state is saved above it, where `mcontext.fpstate` points.
+#[derive(Debug, Clone)]
+#[repr(C)]
+pub struct SigFrame {
+	pub ret: usize,
+	pub num: usize,
+	pub info_ptr: usize,
+	pub uc_ptr: usize,
+	pub info: UserSigInfo,
+	pub uc: UContext,
+	pub handler: usize,
+	pub syscall_ret: isize,
+}
+
+mod test {
+	use super::*;
+	use kfs_macro::ktest;
+
+	#[ktest(signal)]
+	fn altstack_contains() {
+		let stack = SigStack {
+			sp: 0x1000,
+			flags: 0,
+			size: MINSIGSTKSZ,
+		};
+
+		assert!(stack.contains(0x1000 + MINSIGSTKSZ));
+		assert!(!stack.contains(0x1000));
+		assert_eq!(stack.report(0x1100).flags, SS_ONSTACK);
+		assert_eq!(stack.report(0x8000).flags, 0);
+		assert!(!SigStack::disabled().contains(0));
+	}
+
+	#[ktest(signal)]
+	fn restore_keeps_privileged() {
+		let frame = InterruptFrame::new_user(0x1000, 0x2000);
+		let mut mcontext = MContext::new(&frame, SigMask::empty(), 0);
+
+		mcontext.eip = 0x3000;
+		mcontext.cs = 0;
+		mcontext.eflags = !0;
+
+		let mut restored = frame.clone();
+		mcontext.restore(&mut restored);
+
+		assert_eq!(restored.eip, 0x3000);
+		assert_eq!(restored.cs, frame.cs);
+		// IOPL and IF are not taken from user.
+		assert_eq!(restored.eflags & 0x3200, frame.eflags & 0x3200);
+	}
 }
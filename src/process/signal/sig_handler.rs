@@ -6,7 +6,6 @@ pub struct SigAction {
 	handler: usize,
 	flag: SigFlag,
 	restorer: usize,
-	mask2: SigMask,
 	mask: SigMask,
 }
 
@@ -17,7 +16,6 @@ impl SigAction {
 			flag: SigFlag::empty(),
 			restorer: 0,
 			mask: SigMask::empty(),
-			mask2: SigMask::empty(),
 		}
 	}
 
@@ -27,7 +25,6 @@ impl SigAction {
 			flag,
 			restorer: 0,
 			mask,
-			mask2: SigMask::empty(),
 		}
 	}
 
@@ -94,6 +91,8 @@ impl SigHandler {
 			XCPU => Core,
 			XFSZ => Core,
 			WINCH => Ignore,
+			// real-time signals
+			_ => Terminate,
 		}
 	}
 
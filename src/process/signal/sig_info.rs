@@ -7,6 +7,47 @@ pub struct SigInfo {
 	pub pid: usize,    /* Sending process ID */
 	pub uid: usize,    /* Real user ID of sending process */
 	pub code: SigCode, /* Signal code: why this signal was sent. */
+	pub value: usize,  /* Signal value (sigval of timers and sigqueue) */
+}
+
+/// `siginfo_t` in the layout of linux, as rt_* syscalls exchange it.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct UserSigInfo {
+	pub signo: i32,
+	pub errno: i32,
+	pub code: i32,
+	pub pid: i32,
+	pub uid: u32,
+	pub value: usize,
+	_pad: [u32; 26],
+}
+
+impl From<&SigInfo> for UserSigInfo {
+	fn from(info: &SigInfo) -> Self {
+		// `si_addr` takes place of `si_pid` for faults.
+		if info.code.is_fault() {
+			return Self {
+				signo: info.num as i32,
+				errno: 0,
+				code: info.code.as_raw(),
+				pid: info.value as i32,
+				uid: 0,
+				value: 0,
+				_pad: [0; 26],
+			};
+		}
+
+		Self {
+			signo: info.num as i32,
+			errno: 0,
+			code: info.code.as_raw(),
+			pid: info.pid as i32,
+			uid: info.uid as u32,
+			value: info.value,
+			_pad: [0; 26],
+		}
+	}
 }
 
 // struct sig_info {
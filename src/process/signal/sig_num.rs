@@ -34,12 +34,49 @@ pub enum SigNum {
 	IO,
 	PWR,
 	SYS,
+	// real-time signals
+	RT32 = 32,
+	RT33,
+	RT34,
+	RT35,
+	RT36,
+	RT37,
+	RT38,
+	RT39,
+	RT40,
+	RT41,
+	RT42,
+	RT43,
+	RT44,
+	RT45,
+	RT46,
+	RT47,
+	RT48,
+	RT49,
+	RT50,
+	RT51,
+	RT52,
+	RT53,
+	RT54,
+	RT55,
+	RT56,
+	RT57,
+	RT58,
+	RT59,
+	RT60,
+	RT61,
+	RT62,
+	RT63,
+	RT64,
 }
 
 impl SigNum {
+	pub const RTMIN: SigNum = SigNum::RT32;
+	pub const RTMAX: SigNum = SigNum::RT64;
+
 	pub fn from_usize(num: usize) -> Option<Self> {
 		use SigNum::*;
-		if HUP as usize <= num && num <= SYS as usize {
+		if HUP as usize <= num && num <= RT64 as usize {
 			Some(unsafe { transmute(num) })
 		} else {
 			None
@@ -55,4 +92,10 @@ impl SigNum {
 		use SigNum::*;
 		*self == STOP || *self == TSTP || *self == TTIN || *self == TTOU
 	}
+
+	/// real-time signals are queued as many times as they are sent.
+	#[inline(always)]
+	pub fn is_rt(&self) -> bool {
+		*self as usize >= Self::RTMIN as usize
+	}
 }
@@ -0,0 +1,175 @@
+use core::{array, mem::variant_count};
+
+use alloc::collections::VecDeque;
+
+use crate::syscall::errno::Errno;
+
+use super::{sig_info::SigInfo, sig_mask::SigMask, sig_num::SigNum};
+
+/// pending signals of a task, one queue for each signal.
+///
+/// a standard signal is pending at most once, while a real-time signal
+/// keeps every `SigInfo` it was sent with.
+pub struct SigQueue {
+	queues: [VecDeque<SigInfo>; variant_count::<SigNum>()],
+	pending: SigMask,
+	/// number of `SigInfo` in `queues`.
+	queued: usize,
+}
+
+impl SigQueue {
+	/// taken before the others, so that a stopped or killed task
+	/// doesn't run handlers first.
+	const URGENT: SigMask = SigMask::KILL
+		.union(SigMask::STOP)
+		.union(SigMask::TSTP)
+		.union(SigMask::TTIN)
+		.union(SigMask::TTOU)
+		.union(SigMask::CONT);
+
+	pub fn new() -> Self {
+		Self {
+			queues: array::from_fn(|_| VecDeque::new()),
+			pending: SigMask::empty(),
+			queued: 0,
+		}
+	}
+
+	pub fn pending(&self) -> SigMask {
+		self.pending
+	}
+
+	/// real-time signal fails with `EAGAIN` if `limit` signals are queued already.
+	/// (`RLIMIT_SIGPENDING`) standard signal is always taken, as it is queued at most once.
+	pub fn push(&mut self, info: SigInfo, limit: usize) -> Result<(), Errno> {
+		let num = info.num;
+		let queue = &mut self.queues[num.index()];
+
+		if !num.is_rt() && !queue.is_empty() {
+			return Ok(());
+		}
+
+		if num.is_rt() && self.queued >= limit {
+			return Err(Errno::EAGAIN);
+		}
+
+		queue.push_back(info);
+		self.queued += 1;
+		self.pending |= num.into();
+
+		Ok(())
+	}
+
+	/// take the oldest instance of the lowest pending signal in `set`.
+	pub fn pop(&mut self, set: SigMask) -> Option<SigInfo> {
+		let avail = self.pending & set;
+		let first = match avail.intersects(Self::URGENT) {
+			true => avail & Self::URGENT,
+			false => avail,
+		};
+
+		let num = SigNum::from_usize(first.bits().trailing_zeros() as usize + 1)?;
+		let queue = &mut self.queues[num.index()];
+		let info = queue.pop_front();
+		self.queued -= info.is_some() as usize;
+
+		if queue.is_empty() {
+			self.pending -= num.into();
+		}
+
+		info
+	}
+
+	/// drop every pending instance of signals in `set`.
+	pub fn discard(&mut self, set: SigMask) {
+		for queue in self.queues.iter_mut() {
+			queue.retain(|info| !set.contains(info.num.into()));
+		}
+
+		self.queued = self.queues.iter().map(|queue| queue.len()).sum();
+
+		self.pending -= set;
+	}
+
+	pub fn clear(&mut self) {
+		self.discard(SigMask::all());
+	}
+}
+
+impl Default for SigQueue {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+mod test {
+	use super::*;
+	use crate::process::signal::sig_code::SigCode;
+	use kfs_macro::ktest;
+
+	fn info(num: SigNum, value: usize) -> SigInfo {
+		SigInfo {
+			num,
+			pid: 0,
+			uid: 0,
+			code: SigCode::SI_USER,
+			value,
+		}
+	}
+
+	#[ktest(signal)]
+	fn standard_signal_coalesces() {
+		let mut queue = SigQueue::new();
+
+		queue.push(info(SigNum::USR1, 1), usize::MAX).unwrap();
+		queue.push(info(SigNum::USR1, 2), usize::MAX).unwrap();
+
+		assert_eq!(queue.pop(SigMask::all()).map(|i| i.value), Some(1));
+		assert!(queue.pop(SigMask::all()).is_none());
+	}
+
+	#[ktest(signal)]
+	fn rt_signal_queues_in_order() {
+		let mut queue = SigQueue::new();
+
+		queue.push(info(SigNum::RTMIN, 1), usize::MAX).unwrap();
+		queue.push(info(SigNum::RTMIN, 2), usize::MAX).unwrap();
+
+		assert_eq!(queue.pop(SigMask::all()).map(|i| i.value), Some(1));
+		assert!(queue.pending().contains(SigNum::RTMIN.into()));
+		assert_eq!(queue.pop(SigMask::all()).map(|i| i.value), Some(2));
+		assert!(queue.pending().is_empty());
+	}
+
+	#[ktest(signal)]
+	fn lowest_unmasked_first() {
+		let mut queue = SigQueue::new();
+
+		queue.push(info(SigNum::RTMAX, 0), usize::MAX).unwrap();
+		queue.push(info(SigNum::TERM, 0), usize::MAX).unwrap();
+		queue.push(info(SigNum::KILL, 0), usize::MAX).unwrap();
+
+		let set = SigMask::all() - SigMask::KILL;
+		assert_eq!(queue.pop(set).map(|i| i.num), Some(SigNum::TERM));
+		assert_eq!(queue.pop(set).map(|i| i.num), Some(SigNum::RTMAX));
+		assert!(queue.pop(set).is_none());
+		assert_eq!(queue.pop(SigMask::all()).map(|i| i.num), Some(SigNum::KILL));
+	}
+
+	#[ktest(signal)]
+	fn rt_signal_limit() {
+		let mut queue = SigQueue::new();
+
+		queue.push(info(SigNum::RTMIN, 1), 2).unwrap();
+		queue.push(info(SigNum::USR1, 0), 2).unwrap();
+
+		assert!(matches!(
+			queue.push(info(SigNum::RTMIN, 2), 2),
+			Err(Errno::EAGAIN)
+		));
+		queue.push(info(SigNum::USR2, 0), 2).unwrap();
+
+		queue.pop(SigMask::all());
+		queue.push(info(SigNum::RTMIN, 3), 3).unwrap();
+	}
+}
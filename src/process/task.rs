@@ -1,10 +1,9 @@
 use core::alloc::AllocError;
-use core::sync::atomic::{AtomicBool, Ordering};
+use core::sync::atomic::{AtomicBool, AtomicIsize, AtomicUsize, Ordering};
 
 use alloc::sync::Arc;
 use alloc::vec::Vec;
 
-use crate::config::USTACK_BASE;
 use crate::elf::Elf;
 use crate::fs::vfs::{VfsDirEntry, ROOT_DIR_ENTRY};
 use crate::fs::{create_task_node, delete_task_node};
@@ -15,20 +14,26 @@ use crate::process::relation::family::zombie::Zombie;
 use crate::process::signal::sig_info::SigInfo;
 use crate::process::signal::sig_num::SigNum;
 use crate::process::signal::Signal;
+use crate::scheduler::fair::SchedEntity;
 use crate::scheduler::sleep::{wake_up, Sleep};
 use crate::sync::CpuLocal;
-use crate::sync::{Locked, LockedGuard};
+use crate::sync::{ArcLockedGuard, Locked, LockedGuard};
 use crate::syscall::errno::Errno;
+use crate::syscall::fork::CloneFlag;
 use crate::syscall::wait::Who;
+use crate::x86::fpu::FpuState;
 use crate::x86::SystemDesc;
 
+use super::cred::Credentials;
 use super::exit::ExitStatus;
 use super::fd_table::FdTable;
-use super::gid::Gid;
+use super::itimer::cancel_real;
 use super::kstack::Stack;
 use super::process_tree::PROCESS_TREE;
 use super::relation::{Pgid, Pid, Relation, Sid};
-use super::uid::Uid;
+use super::rlimit::{RLimits, Resource};
+use super::rusage::Usage;
+use super::thread_group::ThreadGroup;
 
 pub static CURRENT: CpuLocal<Arc<Task>> = CpuLocal::uninit();
 
@@ -48,8 +53,10 @@ pub struct Task {
 	state: Locked<State>,
 	cmd: Locked<Vec<u8>>,
 	pid: Pid,
-	uid: Uid,
-	gid: Gid,
+	cred: Locked<Credentials>,
+	fpu: Locked<FpuState>,
+	sched: Locked<SchedEntity>,
+	usage: Locked<Usage>,
 	user_ext: Option<UserTaskExt>,
 }
 
@@ -58,12 +65,20 @@ unsafe impl Send for Task {}
 
 pub struct UserTaskExt {
 	exec_called: AtomicBool,
-	cwd: Locked<Arc<VfsDirEntry>>,
-	memory: Locked<Memory>,
-	relation: Locked<Relation>,
-	fd_table: Arc<Locked<FdTable>>,
+	cwd: Arc<Locked<Arc<VfsDirEntry>>>,
+	memory: Arc<Locked<Memory>>,
+	relation: Arc<Locked<Relation>>,
+	/// replaced by `unshare_fd_table`. lock it by `lock_fd_table`.
+	fd_table: Locked<Arc<Locked<FdTable>>>,
 	pub signal: Arc<Signal>,
 	tls: Locked<[SystemDesc; 3]>,
+	oom_score_adj: AtomicIsize,
+	/// core can be dumped. cleared by exec with changed credentials.
+	dumpable: AtomicBool,
+	rlimits: Arc<Locked<RLimits>>,
+	thread_group: Arc<ThreadGroup>,
+	/// user address to clear when this thread exits. (CLONE_CHILD_CLEARTID)
+	clear_child_tid: AtomicUsize,
 }
 
 unsafe impl Sync for UserTaskExt {}
@@ -74,6 +89,11 @@ impl UserTaskExt {
 		self.memory.lock()
 	}
 
+	/// identifies the address space. same for threads sharing memory.
+	pub fn memory_id(&self) -> usize {
+		Arc::as_ptr(&self.memory) as usize
+	}
+
 	pub fn lock_cwd(&self) -> LockedGuard<'_, Arc<VfsDirEntry>> {
 		self.cwd.lock()
 	}
@@ -82,14 +102,40 @@ impl UserTaskExt {
 		self.relation.lock()
 	}
 
-	pub fn lock_fd_table(&self) -> LockedGuard<'_, FdTable> {
-		self.fd_table.lock()
+	pub fn lock_fd_table(&self) -> ArcLockedGuard<FdTable> {
+		let fd_table = self.fd_table.lock().clone();
+		fd_table.lock_arc()
+	}
+
+	/// stop sharing the table with other tasks. (`CLONE_FILES`)
+	/// this task gets a copy of it, as `clone_for_fork` does.
+	pub fn unshare_fd_table(&self) {
+		let mut fd_table = self.fd_table.lock();
+		let copy = fd_table.lock().clone_for_fork();
+
+		*fd_table = Arc::new(Locked::new(copy));
 	}
 
 	pub fn lock_tls(&self) -> LockedGuard<'_, [SystemDesc; 3]> {
 		self.tls.lock()
 	}
 
+	pub fn lock_rlimits(&self) -> LockedGuard<'_, RLimits> {
+		self.rlimits.lock()
+	}
+
+	pub fn get_thread_group(&self) -> &Arc<ThreadGroup> {
+		&self.thread_group
+	}
+
+	pub fn get_clear_child_tid(&self) -> usize {
+		self.clear_child_tid.load(Ordering::Relaxed)
+	}
+
+	pub fn set_clear_child_tid(&self, addr: usize) {
+		self.clear_child_tid.store(addr, Ordering::Relaxed);
+	}
+
 	pub fn was_exec_called(&self) -> bool {
 		self.exec_called.load(Ordering::SeqCst)
 	}
@@ -97,6 +143,22 @@ impl UserTaskExt {
 	pub fn set_exec_called(&self) {
 		self.exec_called.store(true, Ordering::SeqCst);
 	}
+
+	pub fn get_oom_score_adj(&self) -> isize {
+		self.oom_score_adj.load(Ordering::Relaxed)
+	}
+
+	pub fn set_oom_score_adj(&self, adj: isize) {
+		self.oom_score_adj.store(adj, Ordering::Relaxed);
+	}
+
+	pub fn is_dumpable(&self) -> bool {
+		self.dumpable.load(Ordering::Relaxed)
+	}
+
+	pub fn set_dumpable(&self, dumpable: bool) {
+		self.dumpable.store(dumpable, Ordering::Relaxed);
+	}
 }
 
 impl Task {
@@ -105,25 +167,38 @@ impl Task {
 	pub(super) fn new_init_task(pid: Pid, elf: Elf<'_>) -> Result<Arc<Self>, Errno> {
 		debug_assert!(pid.as_raw() == 1, "invalid init pid");
 
+		let entry_point = elf.get_entry_point();
+		let memory = Memory::from_elf(
+			elf,
+			StringVec::new_null(),
+			StringVec::new_null(),
+			&Credentials::root(),
+		)?;
 		let kstack =
-			Stack::new_user(elf.get_entry_point(), USTACK_BASE - 32).map_err(|_| Errno::ENOMEM)?;
-		let memory = Memory::from_elf(elf, StringVec::new_null(), StringVec::new_null())?;
+			Stack::new_user(entry_point, memory.get_stack_pointer()).map_err(|_| Errno::ENOMEM)?;
 
 		let task = Arc::new_cyclic(|w| Task {
 			kstack,
 			state: Locked::new(State::Running),
 			pid,
-			uid: Uid::from_raw(0),
-			gid: Gid::from_raw(0),
+			cred: Locked::new(Credentials::root()),
 			cmd: Locked::new(b"init".to_vec()),
+			fpu: Locked::new(FpuState::new()),
+			sched: Locked::new(SchedEntity::new(0)),
+			usage: Locked::new(Usage::new()),
 			user_ext: Some(UserTaskExt {
 				exec_called: AtomicBool::new(false),
-				cwd: Locked::new(ROOT_DIR_ENTRY.lock().as_ref().unwrap().clone()),
-				memory: Locked::new(memory),
-				relation: Locked::new(Relation::new_init(w)),
-				fd_table: Arc::new(Locked::new(FdTable::new())),
+				cwd: Arc::new(Locked::new(ROOT_DIR_ENTRY.lock().as_ref().unwrap().clone())),
+				memory: Arc::new(Locked::new(memory)),
+				relation: Arc::new(Locked::new(Relation::new_init(w))),
+				fd_table: Locked::new(Arc::new(Locked::new(FdTable::new()))),
 				signal: Arc::new(Signal::new()),
 				tls: Locked::new([SystemDesc::new_null(); 3]),
+				oom_score_adj: AtomicIsize::new(0),
+				dumpable: AtomicBool::new(true),
+				rlimits: Arc::new(Locked::new(RLimits::new())),
+				thread_group: ThreadGroup::new(pid, w.clone(), Some(SigNum::CHLD)),
+				clear_child_tid: AtomicUsize::new(0),
 			}),
 		});
 
@@ -149,9 +224,11 @@ impl Task {
 			kstack,
 			state: Locked::new(State::Running),
 			pid,
-			uid: Uid::from_raw(0),
-			gid: Gid::from_raw(0),
+			cred: Locked::new(Credentials::root()),
 			cmd: Locked::new(b"kthread".to_vec()),
+			fpu: Locked::new(FpuState::new()),
+			sched: Locked::new(SchedEntity::new(0)),
+			usage: Locked::new(Usage::new()),
 			user_ext: None,
 		});
 
@@ -171,44 +248,93 @@ impl Task {
 		self.user_ext.as_ref().ok_or(e)
 	}
 
+	/// create a child of this task. (fork / clone)
+	///
+	/// with `CLONE_THREAD`, the child joins this thread group.
+	/// other `flags` decide which of the resources are shared, not copied.
+	/// `exit_signal` is sent to this task when the new process exits.
 	pub fn clone_for_fork(
 		self: &Arc<Self>,
 		frame: *const InterruptFrame,
+		flags: CloneFlag,
+		exit_signal: Option<SigNum>,
 	) -> Result<Arc<Self>, AllocError> {
 		let kstack = self.kstack.clone_for_fork(frame)?;
 		let pid = Pid::allocate();
-		let uid = self.uid.clone();
-		let gid = self.gid.clone();
+		let cred = self.get_cred();
 		let cmd = self.cmd.lock().clone();
+		// parent is running, so its state is in FPU registers.
+		let fpu = FpuState::capture();
+		let sched = self.lock_sched().clone_for_fork();
 
 		let user_ext = self.get_user_ext().unwrap();
-
-		let cwd = user_ext.lock_cwd().clone();
-		let memory = user_ext.lock_memory().clone()?;
-		let fd_table = user_ext.lock_fd_table().clone_for_fork();
-		let signal = user_ext.signal.clone_for_fork();
+		let thread = flags.contains(CloneFlag::Thread);
+
+		let cwd = match flags.contains(CloneFlag::Fs) {
+			true => user_ext.cwd.clone(),
+			false => Arc::new(Locked::new(user_ext.lock_cwd().clone())),
+		};
+		let memory = match flags.contains(CloneFlag::Vm) {
+			true => user_ext.memory.clone(),
+			false => Arc::new(Locked::new(user_ext.lock_memory().clone()?)),
+		};
+		let fd_table = match flags.contains(CloneFlag::Files) {
+			true => user_ext.fd_table.lock().clone(),
+			false => Arc::new(Locked::new(user_ext.lock_fd_table().clone_for_fork())),
+		};
+		let signal = match flags.contains(CloneFlag::SigHand) {
+			true => user_ext.signal.clone_for_thread(),
+			false => user_ext.signal.clone_for_fork(),
+		};
 		let tls = user_ext.tls.lock().clone();
+		let rlimits = match thread {
+			true => user_ext.rlimits.clone(),
+			false => Arc::new(Locked::new(user_ext.lock_rlimits().clone())),
+		};
 
 		let new_task = Arc::new_cyclic(|w| {
-			let relation = user_ext
-				.lock_relation()
-				.clone_for_fork(pid, self.pid, w.clone());
+			let (relation, thread_group) = match thread {
+				true => {
+					let thread_group = user_ext.thread_group.clone();
+					thread_group.insert(pid, w.clone());
+
+					(user_ext.relation.clone(), thread_group)
+				}
+				false => {
+					let relation =
+						user_ext
+							.lock_relation()
+							.clone_for_fork(pid, self.get_tgid(), w.clone());
+
+					(
+						Arc::new(Locked::new(relation)),
+						ThreadGroup::new(pid, w.clone(), exit_signal),
+					)
+				}
+			};
 
 			Task {
 				kstack,
 				state: Locked::new(State::Running),
 				pid,
-				uid,
-				gid,
+				cred: Locked::new(cred),
 				cmd: Locked::new(cmd),
+				fpu: Locked::new(fpu),
+				sched: Locked::new(sched),
+				usage: Locked::new(Usage::new()),
 				user_ext: Some(UserTaskExt {
 					exec_called: AtomicBool::new(false),
-					cwd: Locked::new(cwd),
-					memory: Locked::new(memory),
-					relation: Locked::new(relation),
-					fd_table: Arc::new(Locked::new(fd_table)),
+					cwd,
+					memory,
+					relation,
+					fd_table: Locked::new(fd_table),
 					signal: Arc::new(signal),
 					tls: Locked::new(tls),
+					oom_score_adj: AtomicIsize::new(user_ext.get_oom_score_adj()),
+					dumpable: AtomicBool::new(user_ext.is_dumpable()),
+					rlimits,
+					thread_group,
+					clear_child_tid: AtomicUsize::new(0),
 				}),
 			}
 		});
@@ -225,40 +351,59 @@ impl Task {
 		self.cmd.lock()
 	}
 
+	/// saved FPU state. valid only while the task is not running.
+	pub fn lock_fpu(&self) -> LockedGuard<'_, FpuState> {
+		self.fpu.lock()
+	}
+
+	pub fn lock_sched(&self) -> LockedGuard<'_, SchedEntity> {
+		self.sched.lock()
+	}
+
+	pub fn lock_usage(&self) -> LockedGuard<'_, Usage> {
+		self.usage.lock()
+	}
+
 	#[inline]
 	pub fn get_pid(&self) -> Pid {
 		self.pid
 	}
 
+	/// pid of the thread group. (process id seen by user space)
+	pub fn get_tgid(&self) -> Pid {
+		self.get_user_ext()
+			.map(|ext| ext.thread_group.get_tgid())
+			.unwrap_or(self.pid)
+	}
+
 	pub fn get_ppid(&self) -> Pid {
 		self.get_user_ext()
 			.map(|ext| ext.lock_relation().get_ppid())
 			.unwrap_or_else(|| Pid::from_raw(0))
 	}
 
-	pub fn get_uid(&self) -> usize {
-		self.uid.as_raw()
+	pub fn lock_cred(&self) -> LockedGuard<'_, Credentials> {
+		self.cred.lock()
 	}
 
-	pub fn get_gid(&self) -> usize {
-		self.gid.as_raw()
+	pub fn get_cred(&self) -> Credentials {
+		self.cred.lock().clone()
 	}
 
-	pub fn set_uid(&self, new_uid: usize) -> Result<(), Errno> {
-		self.uid.set(new_uid)
+	pub fn get_uid(&self) -> usize {
+		self.cred.lock().uid
 	}
 
-	pub fn set_gid(&self, new_gid: usize) -> Result<(), Errno> {
-		if self.get_gid() == new_gid {
-			return Ok(());
-		}
+	pub fn get_euid(&self) -> usize {
+		self.cred.lock().euid
+	}
 
-		if self.get_uid() == 0 {
-			self.gid.set(new_gid);
-			return Ok(());
-		}
+	pub fn get_gid(&self) -> usize {
+		self.cred.lock().gid
+	}
 
-		Err(Errno::EPERM)
+	pub fn get_egid(&self) -> usize {
+		self.cred.lock().egid
 	}
 
 	pub fn get_pgid(&self) -> Pgid {
@@ -285,19 +430,51 @@ impl Task {
 		self.user_ext.is_none()
 	}
 
+	/// leave the thread group. the last one out makes the process a zombie.
+	///
+	/// leader of the group stays in process tree until then,
+	/// so that the process can still be found by its pid.
 	pub fn exit(&self, status: ExitStatus) {
-		let _ = delete_task_node(self.get_pid());
+		let Some(ref ext) = self.user_ext else {
+			let _ = delete_task_node(self.pid);
+
+			let mut state = self.lock_state();
+			PROCESS_TREE.lock().remove(&self.pid);
+			Pid::deallocate(self.pid);
+
+			*state = State::Exited;
+			return;
+		};
+
+		let tgid = ext.thread_group.get_tgid();
+		ext.thread_group.add_exited_usage(*self.lock_usage());
+		let last = ext.thread_group.remove(self.pid);
+
+		if self.pid != tgid {
+			let _ = delete_task_node(self.pid);
+		}
+		if last {
+			let _ = delete_task_node(tgid);
+		}
 
 		let mut state = self.lock_state();
-		PROCESS_TREE.lock().remove(&self.pid);
 
-		if let Some(ref ext) = self.user_ext {
+		if self.pid != tgid {
+			PROCESS_TREE.lock().remove(&self.pid);
+			// pid of the group is released by waitpid.
+			Pid::deallocate(self.pid);
+		}
+
+		if last {
+			cancel_real(tgid);
+			ext.thread_group.lock_posix_timers().clear();
+			PROCESS_TREE.lock().remove(&tgid);
+
+			let status = ext.thread_group.get_exit_status().unwrap_or(status);
 			let mut rel = ext.lock_relation();
-			rel.exit(self.pid, status);
+			rel.exit(tgid, status, ext.thread_group.get_total_usage());
 			let pgrp = &mut rel.pgroup;
-			pgrp.lock_members().remove(&self.pid);
-		} else {
-			Pid::deallocate(self.get_pid());
+			pgrp.lock_members().remove(&tgid);
 		}
 
 		*state = State::Exited;
@@ -312,13 +489,19 @@ impl Task {
 		let result = relation.waitpid(who);
 		if let Ok(z) = result.as_ref() {
 			Pid::deallocate(z.pid);
+			self.get_user_ext()
+				.unwrap()
+				.thread_group
+				.add_children_usage(z.usage);
 		}
 
 		result
 	}
 
 	pub fn recv_signal(self: &Arc<Self>, info: SigInfo) -> Result<(), Errno> {
-		let signal = &self.user_ext_ok_or(Errno::EPERM)?.signal;
+		let ext = self.user_ext_ok_or(Errno::EPERM)?;
+		let signal = &ext.signal;
+		let limit = ext.lock_rlimits().get(Resource::Sigpending).cur;
 
 		let kill = info.num == SigNum::KILL;
 		let cont = info.num == SigNum::CONT && signal.is_default(&SigNum::CONT);
@@ -327,11 +510,10 @@ impl Task {
 			wake_up(self, Sleep::Deep);
 		}
 
-		signal.recv_signal(info);
-		Ok(())
+		signal.recv_signal(info, limit)
 	}
 
 	pub fn is_privileged(&self) -> bool {
-		self.is_kernel() || self.get_uid() == 0
+		self.is_kernel() || self.lock_cred().is_privileged()
 	}
 }
@@ -0,0 +1,190 @@
+use alloc::{
+	collections::BTreeMap,
+	sync::{Arc, Weak},
+	vec::Vec,
+};
+
+use crate::sync::{Locked, LockedGuard};
+
+use super::{
+	exit::ExitStatus, itimer::ITimers, posix_timer::PosixTimers, relation::Pid, rusage::Usage,
+	signal::sig_num::SigNum, task::Task,
+};
+
+/// threads created by `clone(CLONE_THREAD)` share one thread group.
+///
+/// the group is identified by pid of the first thread (leader),
+/// which is what user space sees as process id.
+pub struct ThreadGroup {
+	tgid: Pid,
+	threads: Locked<BTreeMap<Pid, Weak<Task>>>,
+	/// set by `exit_group` or a fatal signal. every thread exits with it.
+	exit_status: Locked<Option<ExitStatus>>,
+	/// stop by a signal not yet reported to `waitpid`.
+	stop_status: Locked<Option<ExitStatus>>,
+	/// threads stopped by a signal.
+	nr_stopped: Locked<usize>,
+	/// usage of threads already exited.
+	exited_usage: Locked<Usage>,
+	/// usage of reaped children. (and their reaped children)
+	children_usage: Locked<Usage>,
+	itimers: Locked<ITimers>,
+	posix_timers: Locked<PosixTimers>,
+	/// sent to the parent when the group exits. (low byte of clone flags)
+	exit_signal: Option<SigNum>,
+}
+
+impl ThreadGroup {
+	pub fn new(leader: Pid, weak: Weak<Task>, exit_signal: Option<SigNum>) -> Arc<Self> {
+		Arc::new(Self {
+			tgid: leader,
+			threads: Locked::new(BTreeMap::from([(leader, weak)])),
+			exit_status: Locked::new(None),
+			stop_status: Locked::new(None),
+			nr_stopped: Locked::new(0),
+			exited_usage: Locked::new(Usage::new()),
+			children_usage: Locked::new(Usage::new()),
+			itimers: Locked::new(ITimers::new()),
+			posix_timers: Locked::new(PosixTimers::new()),
+			exit_signal,
+		})
+	}
+
+	pub fn get_tgid(&self) -> Pid {
+		self.tgid
+	}
+
+	pub fn get_exit_signal(&self) -> Option<SigNum> {
+		self.exit_signal
+	}
+
+	pub fn insert(&self, pid: Pid, weak: Weak<Task>) {
+		self.threads.lock().insert(pid, weak);
+	}
+
+	/// returns true if `pid` was the last thread.
+	pub fn remove(&self, pid: Pid) -> bool {
+		let mut threads = self.threads.lock();
+		threads.remove(&pid);
+
+		threads.is_empty()
+	}
+
+	pub fn contains(&self, pid: Pid) -> bool {
+		self.threads.lock().contains_key(&pid)
+	}
+
+	pub fn nr_threads(&self) -> usize {
+		self.threads.lock().len()
+	}
+
+	/// live threads, ordered by pid.
+	pub fn threads(&self) -> Vec<Arc<Task>> {
+		self.threads
+			.lock()
+			.values()
+			.filter_map(|weak| weak.upgrade())
+			.collect()
+	}
+
+	/// live threads except `pid`.
+	pub fn others(&self, pid: Pid) -> Vec<Arc<Task>> {
+		self.threads
+			.lock()
+			.iter()
+			.filter(|(other, _)| **other != pid)
+			.filter_map(|(_, weak)| weak.upgrade())
+			.collect()
+	}
+
+	/// start exiting the whole group with `status`.
+	/// returns false if the group is already exiting.
+	pub fn set_exit_status(&self, status: ExitStatus) -> bool {
+		let mut exit_status = self.exit_status.lock();
+		if exit_status.is_some() {
+			return false;
+		}
+
+		*exit_status = Some(status);
+		true
+	}
+
+	pub fn clear_exit_status(&self) {
+		*self.exit_status.lock() = None;
+	}
+
+	pub fn get_exit_status(&self) -> Option<ExitStatus> {
+		*self.exit_status.lock()
+	}
+
+	pub fn set_stop_status(&self, status: Option<ExitStatus>) {
+		*self.stop_status.lock() = status;
+	}
+
+	/// a thread is stopped by a signal.
+	/// returns true if it is the first one, which reports the stop.
+	pub fn enter_stop(&self, status: ExitStatus) -> bool {
+		let mut nr_stopped = self.nr_stopped.lock();
+		*nr_stopped += 1;
+
+		let first = *nr_stopped == 1;
+		if first {
+			self.set_stop_status(Some(status));
+		}
+
+		first
+	}
+
+	/// a thread is continued. the stop is gone with the last one.
+	pub fn leave_stop(&self) {
+		let mut nr_stopped = self.nr_stopped.lock();
+		*nr_stopped -= 1;
+
+		if *nr_stopped == 0 {
+			self.set_stop_status(None);
+		}
+	}
+
+	pub fn take_stop_status(&self) -> Option<ExitStatus> {
+		self.stop_status.lock().take()
+	}
+
+	pub fn add_exited_usage(&self, usage: Usage) {
+		*self.exited_usage.lock() += usage;
+	}
+
+	pub fn add_children_usage(&self, usage: Usage) {
+		*self.children_usage.lock() += usage;
+	}
+
+	/// usage of every thread in the group, live or exited.
+	pub fn get_usage(&self) -> Usage {
+		let mut usage = *self.exited_usage.lock();
+
+		for thread in self.threads.lock().values().filter_map(Weak::upgrade) {
+			usage += *thread.lock_usage();
+		}
+
+		usage
+	}
+
+	pub fn get_children_usage(&self) -> Usage {
+		*self.children_usage.lock()
+	}
+
+	/// what the parent accounts to its children when reaping this process.
+	pub fn get_total_usage(&self) -> Usage {
+		let mut usage = self.get_usage();
+		usage += self.get_children_usage();
+
+		usage
+	}
+
+	pub fn lock_itimers(&self) -> LockedGuard<'_, ITimers> {
+		self.itimers.lock()
+	}
+
+	pub fn lock_posix_timers(&self) -> LockedGuard<'_, PosixTimers> {
+		self.posix_timers.lock()
+	}
+}
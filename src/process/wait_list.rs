@@ -11,9 +11,19 @@ use crate::{
 	trace_feature,
 };
 
+/// tag of waiters registered without one. matches every tag.
+pub const TAG_ANY: u32 = !0;
+
+#[derive(Debug)]
+struct Waiter {
+	task: Weak<Task>,
+	/// waiters can be woken selectively by tag. (e.g. futex bitset)
+	tag: u32,
+}
+
 #[derive(Debug)]
 pub struct WaitList {
-	list: Vec<Weak<Task>>,
+	list: Vec<Waiter>,
 }
 
 impl WaitList {
@@ -21,11 +31,28 @@ impl WaitList {
 		Self { list: Vec::new() }
 	}
 
+	pub fn is_empty(&self) -> bool {
+		self.list.is_empty()
+	}
+
 	pub fn register(&mut self) {
+		self.register_tagged(TAG_ANY);
+	}
+
+	pub fn register_tagged(&mut self, tag: u32) {
 		let current = unsafe { CURRENT.get_ref() };
 		let w = Arc::downgrade(current);
 
-		self.list.push(w);
+		self.list.push(Waiter { task: w, tag });
+	}
+
+	/// returns false if `task` was not waiting here.
+	pub fn unregister(&mut self, task: &Arc<Task>) -> bool {
+		let len = self.list.len();
+		self.list
+			.retain(|waiter| waiter.task.as_ptr() != Arc::as_ptr(task));
+
+		self.list.len() != len
 	}
 
 	pub fn wake_up_all(&mut self) {
@@ -35,16 +62,49 @@ impl WaitList {
 			"waitlist",
 			"wake up: {}",
 			list.iter()
-				.filter_map(|w| w.upgrade().map(|t| t.get_pid()))
+				.filter_map(|w| w.task.upgrade().map(|t| t.get_pid()))
 				.collect::<Vec<_>>()
 		);
 
 		list.into_iter().for_each(|w| {
-			if let Some(task) = w.upgrade() {
+			if let Some(task) = w.task.upgrade() {
 				wake_up_deep_sleep(&task)
 			}
 		})
 	}
+
+	/// wake up at most `count` waiters, in the order of registration,
+	/// which tag has common bits with `tag`.
+	///
+	/// returns the number of tasks woken up.
+	pub fn wake_up_tagged(&mut self, count: usize, tag: u32) -> usize {
+		let mut woken = 0;
+
+		self.list.retain(|waiter| {
+			if woken == count || waiter.tag & tag == 0 {
+				return true;
+			}
+
+			if let Some(task) = waiter.task.upgrade() {
+				wake_up_deep_sleep(&task);
+				woken += 1;
+			}
+			false
+		});
+
+		woken
+	}
+
+	/// move at most `count` waiters to `other` without waking them.
+	///
+	/// returns the number of waiters moved.
+	pub fn requeue(&mut self, other: &mut WaitList, count: usize) -> usize {
+		let count = count.min(self.list.len());
+
+		other.list.extend(self.list.drain(..count));
+
+		count
+	}
 }
 
 impl Drop for WaitList {
@@ -1,25 +1,153 @@
+pub mod affinity;
+pub mod balance;
 pub mod context;
+pub mod fair;
+pub mod futex;
 pub mod nano_sleep;
 pub mod preempt;
+pub mod priority;
 pub mod sleep;
+pub mod timer;
 pub mod work;
 
-use alloc::{collections::LinkedList, sync::Arc};
+use alloc::sync::Arc;
 
-use crate::{process::task::Task, sync::Locked, syscall::errno::Errno};
+use crate::{
+	config::NR_CPUS,
+	driver::{apic::ipi::send_reschedule, hpet::get_timestamp_nano},
+	process::task::{State, Task, CURRENT},
+	smp::{set_cpu_online, smp_id},
+	sync::Locked,
+	syscall::errno::Errno,
+};
 
+use self::balance::{select_cpu, steal_task};
 use self::context::yield_now;
+use self::fair::RunQueue;
 
-static TASK_QUEUE: Locked<LinkedList<Arc<Task>>> = Locked::new(LinkedList::new());
+#[allow(clippy::declare_interior_mutable_const)]
+const EMPTY_RUN_QUEUE: Locked<RunQueue> = Locked::new(RunQueue::new());
 
-pub fn schedule_first(task: Arc<Task>) {
-	let mut q = TASK_QUEUE.lock();
-	q.push_front(task);
-}
+/// run queue of each cpu, indexed by cpu id.
+static RUN_QUEUES: [Locked<RunQueue>; NR_CPUS] = [EMPTY_RUN_QUEUE; NR_CPUS];
 
+/// make `task` runnable. used for new and woken up tasks.
+///
+/// the task may be queued on other cpu, which is notified by an IPI.
 pub fn schedule_last(task: Arc<Task>) {
-	let mut q = TASK_QUEUE.lock();
-	q.push_back(task);
+	let prev = task.lock_sched().cpu();
+	let cpu = select_cpu(&task);
+
+	{
+		let from_min = RUN_QUEUES[prev].lock().min_vruntime();
+		let mut rq = RUN_QUEUES[cpu].lock();
+
+		if cpu != prev {
+			rq.adopt(&task, from_min, cpu);
+		}
+		rq.enqueue(task);
+	}
+
+	if cpu != smp_id() {
+		send_reschedule(cpu);
+	}
+}
+
+/// `task` runs on this cpu when nothing else is runnable.
+/// from now on, this cpu takes tasks.
+pub fn set_idle_task(task: Arc<Task>) {
+	let cpu = smp_id();
+
+	{
+		let mut sched = task.lock_sched();
+		sched.set_allowed(1 << cpu);
+		sched.start(get_timestamp_nano(), cpu);
+	}
+	RUN_QUEUES[cpu].lock().set_idle(task);
+	set_cpu_online(cpu);
+}
+
+/// whether current task has run long enough to be preempted.
+pub fn need_resched() -> bool {
+	let curr = unsafe { CURRENT.get_ref() };
+	let vruntime = {
+		let mut sched = curr.lock_sched();
+		sched.update(get_timestamp_nano());
+		sched.vruntime()
+	};
+
+	RUN_QUEUES[smp_id()].lock().should_preempt(curr, vruntime)
+}
+
+/// task to switch to from `curr`. `None` if `curr` should keep running.
+///
+/// if nothing is queued here and `curr` can't run anymore,
+/// a task is taken from other cpu before falling back to idle.
+fn pick_next(curr: &Arc<Task>) -> Option<Arc<Task>> {
+	let cpu = smp_id();
+
+	if let Some(next) = RUN_QUEUES[cpu].lock().pop() {
+		return Some(next);
+	}
+
+	let is_idle = RUN_QUEUES[cpu].lock().is_idle(curr);
+	if !is_idle && *curr.lock_state() == State::Running {
+		return None;
+	}
+
+	match steal_task(cpu) {
+		Some(next) => Some(next),
+		None if is_idle => None,
+		None => RUN_QUEUES[cpu].lock().idle(),
+	}
+}
+
+/// called while switching from `prev` to `next`.
+fn switch_accounting(prev: &Arc<Task>, next: &Arc<Task>) {
+	let now = get_timestamp_nano();
+	let cpu = smp_id();
+
+	prev.lock_sched().update(now);
+	next.lock_sched().start(now, cpu);
+
+	let voluntary = *prev.lock_state() != State::Running;
+	prev.lock_usage().context_switch(voluntary);
+
+	RUN_QUEUES[cpu].lock().set_running(next);
+}
+
+/// queue `prev` again after it was switched out while runnable.
+fn put_prev(prev: Arc<Task>) {
+	let cpu = smp_id();
+
+	if prev.lock_sched().is_allowed_on(cpu) {
+		RUN_QUEUES[cpu].lock().requeue(prev);
+	} else {
+		schedule_last(prev);
+	}
+}
+
+/// restrict `task` to cpus in `mask`.
+///
+/// a task queued on a cpu not in `mask` is moved right away.
+/// a running one is moved when it is switched out.
+pub fn set_affinity(task: &Arc<Task>, mask: usize) {
+	let cpu = {
+		let mut sched = task.lock_sched();
+		sched.set_allowed(mask);
+		sched.cpu()
+	};
+
+	if mask & (1 << cpu) != 0 {
+		return;
+	}
+
+	let queued = RUN_QUEUES[cpu].lock().remove(task);
+	if queued {
+		schedule_last(task.clone());
+	} else if Arc::ptr_eq(task, unsafe { CURRENT.get_ref() }) {
+		yield_now();
+	}
 }
 
 pub fn sys_sched_yield() -> Result<usize, Errno> {
@@ -0,0 +1,65 @@
+use core::{cmp::min, mem::size_of};
+
+use alloc::sync::Arc;
+
+use crate::{
+	mm::user::verify::{verify_buffer, verify_buffer_mut},
+	process::{
+		process_tree::PROCESS_TREE,
+		relation::Pid,
+		task::{Task, CURRENT},
+	},
+	smp::{online_cpus, ALL_CPUS},
+	syscall::errno::Errno,
+};
+
+use super::set_affinity;
+
+/// size of cpu mask in user space. (a single `unsigned long`)
+const CPU_MASK_SIZE: usize = size_of::<usize>();
+
+fn find_task(pid: usize) -> Result<Arc<Task>, Errno> {
+	match pid {
+		0 => Ok(unsafe { CURRENT.get_ref() }.clone()),
+		_ => PROCESS_TREE
+			.get_task(Pid::from_raw(pid))
+			.ok_or(Errno::ESRCH),
+	}
+}
+
+pub fn sys_sched_setaffinity(pid: usize, len: usize, mask_ptr: usize) -> Result<usize, Errno> {
+	let current = unsafe { CURRENT.get_ref() };
+
+	let len = min(len, CPU_MASK_SIZE);
+	let mut raw = [0; CPU_MASK_SIZE];
+	raw[..len].copy_from_slice(verify_buffer(mask_ptr, len, current)?);
+
+	let mask = usize::from_ne_bytes(raw) & ALL_CPUS;
+	if mask & online_cpus() == 0 {
+		return Err(Errno::EINVAL);
+	}
+
+	let task = find_task(pid)?;
+	if !current.get_cred().is_owner_of(&task.get_cred()) {
+		return Err(Errno::EPERM);
+	}
+
+	set_affinity(&task, mask);
+
+	Ok(0)
+}
+
+/// returns size of the mask written.
+pub fn sys_sched_getaffinity(pid: usize, len: usize, mask_ptr: usize) -> Result<usize, Errno> {
+	let current = unsafe { CURRENT.get_ref() };
+
+	if len < CPU_MASK_SIZE || len % CPU_MASK_SIZE != 0 {
+		return Err(Errno::EINVAL);
+	}
+
+	let mask = find_task(pid)?.lock_sched().allowed() & online_cpus();
+
+	verify_buffer_mut(mask_ptr, CPU_MASK_SIZE, current)?.copy_from_slice(&mask.to_ne_bytes());
+
+	Ok(CPU_MASK_SIZE)
+}
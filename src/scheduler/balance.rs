@@ -0,0 +1,70 @@
+//! spreading tasks over cpus.
+//!
+//! woken tasks are queued on the least loaded cpu they may run on.
+//! a cpu about to go idle takes a task from the busiest one, and every
+//! `BALANCE_INTERVAL` ticks a cpu pulls a task if it is clearly less loaded.
+
+use alloc::sync::Arc;
+
+use crate::{
+	config::TIMER_FREQUENCY_HZ,
+	process::task::Task,
+	smp::{cpus_in, online_cpus, smp_id},
+};
+
+use super::RUN_QUEUES;
+
+/// ticks between periodic balancing. (100ms)
+const BALANCE_INTERVAL: usize = TIMER_FREQUENCY_HZ / 10;
+
+/// cpu to queue `task` on.
+///
+/// the least loaded cpu among allowed ones. the cpu it ran on wins a tie.
+pub fn select_cpu(task: &Arc<Task>) -> usize {
+	let (prev, allowed) = {
+		let sched = task.lock_sched();
+		(sched.cpu(), sched.allowed())
+	};
+
+	cpus_in(allowed & online_cpus())
+		.min_by_key(|&cpu| (RUN_QUEUES[cpu].lock().load(), cpu != prev))
+		.unwrap_or(prev)
+}
+
+/// take a task from the busiest other cpu that has more load than `threshold`.
+/// returned task is ready to be queued (or run) on `cpu`.
+fn steal_from_busiest(cpu: usize, threshold: usize) -> Option<Arc<Task>> {
+	let busiest = cpus_in(online_cpus() & !(1 << cpu))
+		.map(|other| (RUN_QUEUES[other].lock().load(), other))
+		.filter(|(load, _)| *load > threshold)
+		.max()
+		.map(|(_, other)| other)?;
+
+	let (task, from_min) = {
+		let mut rq = RUN_QUEUES[busiest].lock();
+		(rq.steal(cpu)?, rq.min_vruntime())
+	};
+
+	RUN_QUEUES[cpu].lock().adopt(&task, from_min, cpu);
+
+	Some(task)
+}
+
+/// idle time stealing. the task is to be run right away on `cpu`.
+pub fn steal_task(cpu: usize) -> Option<Arc<Task>> {
+	steal_from_busiest(cpu, 0)
+}
+
+/// periodic balancing. called on every timer tick.
+pub fn balance_tick(jiffies: usize) {
+	if jiffies % BALANCE_INTERVAL != 0 {
+		return;
+	}
+
+	let cpu = smp_id();
+	let load = RUN_QUEUES[cpu].lock().load();
+
+	if let Some(task) = steal_from_busiest(cpu, load + 1) {
+		RUN_QUEUES[cpu].lock().requeue(task);
+	}
+}
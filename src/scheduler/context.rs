@@ -6,8 +6,9 @@ use crate::{
 	interrupt::save_interrupt_context,
 	process::task::{State, Task, CURRENT},
 	scheduler::{
+		pick_next,
 		preempt::{get_preempt_count, preemptable},
-		TASK_QUEUE,
+		put_prev, switch_accounting,
 	},
 	x86::{CPU_GDT, CPU_TASK_STATE},
 };
@@ -22,18 +23,14 @@ pub fn yield_now() {
 	);
 	let _ctx = save_interrupt_context();
 
-	let next = {
-		let mut task_q = TASK_QUEUE.lock();
-
-		match task_q.pop_front() {
-			Some(x) => x,
-			None => return,
-		}
-	};
-
 	// safety: IRQ is disabled.
 	let curr = unsafe { CURRENT.get_mut() }.clone();
 
+	let next = match pick_next(&curr) {
+		Some(x) => x,
+		None => return,
+	};
+
 	let curr_task = Arc::into_raw(curr);
 	let next_task = Arc::into_raw(next);
 
@@ -57,11 +54,21 @@ pub unsafe extern "fastcall" fn switch_task_finish(curr: *const Task, next: *con
 		.get_mut()
 		.change_kernel_stack(next.kstack_base());
 
+	if curr.get_user_ext().is_some() {
+		curr.lock_fpu().save();
+	}
+
+	switch_accounting(&curr, &next);
+
 	{
+		// checked under the state lock, so that a waker either sees `curr` off cpu
+		// and queues it, or leaves it to be queued here.
 		let state_lock = curr.lock_state();
+		curr.lock_sched().leave_cpu();
+
 		if *state_lock == State::Running {
 			mem::drop(state_lock);
-			TASK_QUEUE.lock().push_back(curr);
+			put_prev(curr);
 		};
 	};
 
@@ -72,6 +79,7 @@ pub unsafe extern "fastcall" fn switch_task_finish(curr: *const Task, next: *con
 		gdt.pick_up();
 
 		user.lock_memory().pick_up();
+		next.lock_fpu().restore();
 	}
 
 	let _ = mem::replace(CURRENT.get_mut(), next);
@@ -0,0 +1,319 @@
+//! weighted fair scheduling.
+//!
+//! every task accumulates virtual runtime: its real runtime scaled by
+//! `NICE_0_WEIGHT / weight`. the runnable task with the smallest virtual
+//! runtime runs next, so cpu time is shared in proportion to the weights.
+
+use core::cmp::max;
+
+use alloc::{collections::BTreeMap, sync::Arc};
+
+use crate::process::task::Task;
+use crate::smp::ALL_CPUS;
+
+pub const NICE_MIN: i32 = -20;
+pub const NICE_MAX: i32 = 19;
+
+const NICE_0_WEIGHT: u64 = 1024;
+
+/// weight of nice -20 ..= 19. (same as linux)
+/// each nice level is about 10% of cpu time relative to the neighbor level.
+const NICE_TO_WEIGHT: [u64; 40] = [
+	88761, 71755, 56483, 46273, 36291, 29154, 23254, 18705, 14949, 11916, 9548, 7620, 6100, 4904,
+	3906, 3121, 2501, 1991, 1586, 1277, 1024, 820, 655, 526, 423, 335, 272, 215, 172, 137, 110, 87,
+	70, 56, 45, 36, 29, 23, 18, 15,
+];
+
+/// current task is preempted only if it is ahead of the next task
+/// by more than this. (ns of virtual runtime)
+const PREEMPT_GRANULARITY: u64 = 1_000_000;
+
+/// how much virtual runtime a waking task may lag behind the queue.
+/// lets sleepers (e.g. interactive shell) run soon after wakeup,
+/// without being able to monopolize cpu.
+const SLEEPER_CREDIT: u64 = 6_000_000;
+
+pub struct SchedEntity {
+	nice: i32,
+	vruntime: u64,
+	exec_start: u64,
+	/// cpu the task ran on or is queued on.
+	cpu: usize,
+	/// bitmask of cpus the task may run on.
+	allowed: usize,
+	/// running on `cpu`, or switching out of it. its kernel stack is still in use.
+	///
+	/// a task is queued only while this is cleared, so that it never runs on two cpus.
+	on_cpu: bool,
+}
+
+impl SchedEntity {
+	pub const fn new(nice: i32) -> Self {
+		Self {
+			nice,
+			vruntime: 0,
+			exec_start: 0,
+			cpu: 0,
+			allowed: ALL_CPUS,
+			on_cpu: false,
+		}
+	}
+
+	pub fn clone_for_fork(&self) -> Self {
+		Self {
+			nice: self.nice,
+			vruntime: self.vruntime,
+			exec_start: 0,
+			cpu: self.cpu,
+			allowed: self.allowed,
+			on_cpu: false,
+		}
+	}
+
+	pub fn nice(&self) -> i32 {
+		self.nice
+	}
+
+	pub fn set_nice(&mut self, nice: i32) {
+		self.nice = nice.clamp(NICE_MIN, NICE_MAX);
+	}
+
+	pub fn vruntime(&self) -> u64 {
+		self.vruntime
+	}
+
+	pub fn cpu(&self) -> usize {
+		self.cpu
+	}
+
+	pub fn allowed(&self) -> usize {
+		self.allowed
+	}
+
+	pub fn is_allowed_on(&self, cpu: usize) -> bool {
+		self.allowed & (1 << cpu) != 0
+	}
+
+	pub fn set_allowed(&mut self, mask: usize) {
+		self.allowed = mask;
+	}
+
+	fn weight(&self) -> u64 {
+		NICE_TO_WEIGHT[(self.nice - NICE_MIN) as usize]
+	}
+
+	/// start accounting from `now`. called when the task is switched in on `cpu`.
+	pub fn start(&mut self, now: u64, cpu: usize) {
+		self.exec_start = now;
+		self.cpu = cpu;
+		self.on_cpu = true;
+	}
+
+	pub fn is_on_cpu(&self) -> bool {
+		self.on_cpu
+	}
+
+	/// called when the task is switched out and its kernel stack is no longer used.
+	pub fn leave_cpu(&mut self) {
+		self.on_cpu = false;
+	}
+
+	/// charge time ran since last update to virtual runtime.
+	pub fn update(&mut self, now: u64) {
+		let delta = now.saturating_sub(self.exec_start);
+
+		self.exec_start = now;
+		self.vruntime += delta * NICE_0_WEIGHT / self.weight();
+	}
+
+	/// don't let a task that slept for long come back with too small vruntime.
+	fn place(&mut self, min_vruntime: u64) {
+		self.vruntime = max(self.vruntime, min_vruntime.saturating_sub(SLEEPER_CREDIT));
+	}
+
+	/// keep the lag relative to queue while moving to another cpu's queue.
+	fn migrate(&mut self, from_min: u64, to_min: u64, to_cpu: usize) {
+		self.vruntime = self.vruntime.saturating_sub(from_min) + to_min;
+		self.cpu = to_cpu;
+	}
+}
+
+pub struct RunQueue {
+	/// runnable tasks ordered by (vruntime, enqueue sequence)
+	tasks: BTreeMap<(u64, usize), Arc<Task>>,
+	seq: usize,
+	min_vruntime: u64,
+	/// whether a task other than idle is running on the cpu.
+	busy: bool,
+	/// runs only if nothing else is runnable. never queued.
+	idle: Option<Arc<Task>>,
+}
+
+impl RunQueue {
+	pub const fn new() -> Self {
+		Self {
+			tasks: BTreeMap::new(),
+			seq: 0,
+			min_vruntime: 0,
+			busy: false,
+			idle: None,
+		}
+	}
+
+	pub fn set_idle(&mut self, task: Arc<Task>) {
+		self.idle = Some(task);
+	}
+
+	pub fn idle(&self) -> Option<Arc<Task>> {
+		self.idle.clone()
+	}
+
+	pub fn is_idle(&self, task: &Arc<Task>) -> bool {
+		self.idle
+			.as_ref()
+			.is_some_and(|idle| Arc::ptr_eq(idle, task))
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.tasks.is_empty()
+	}
+
+	pub fn len(&self) -> usize {
+		self.tasks.len()
+	}
+
+	/// number of tasks queued or running, except idle.
+	pub fn load(&self) -> usize {
+		self.tasks.len() + self.busy as usize
+	}
+
+	pub fn set_running(&mut self, task: &Arc<Task>) {
+		self.busy = !self.is_idle(task);
+	}
+
+	pub fn min_vruntime(&self) -> u64 {
+		self.min_vruntime
+	}
+
+	/// put back a task that was preempted or yielded.
+	pub fn requeue(&mut self, task: Arc<Task>) {
+		self.insert(task);
+	}
+
+	/// enqueue a task that was woken up or newly created.
+	pub fn enqueue(&mut self, task: Arc<Task>) {
+		task.lock_sched().place(self.min_vruntime);
+		self.insert(task);
+	}
+
+	/// rebase vruntime of `task` from other queue, which `min_vruntime` is
+	/// `from_min`, to this queue of `cpu`.
+	pub fn adopt(&self, task: &Arc<Task>, from_min: u64, cpu: usize) {
+		task.lock_sched().migrate(from_min, self.min_vruntime, cpu);
+	}
+
+	fn insert(&mut self, task: Arc<Task>) {
+		if self.is_idle(&task) {
+			return;
+		}
+
+		let vruntime = task.lock_sched().vruntime();
+
+		self.seq = self.seq.wrapping_add(1);
+		self.tasks.insert((vruntime, self.seq), task);
+	}
+
+	/// take the task with the smallest vruntime.
+	pub fn pop(&mut self) -> Option<Arc<Task>> {
+		let ((vruntime, _), task) = self.tasks.pop_first()?;
+		self.min_vruntime = max(self.min_vruntime, vruntime);
+
+		Some(task)
+	}
+
+	/// remove `task` if it is queued here.
+	pub fn remove(&mut self, task: &Arc<Task>) -> bool {
+		let len = self.tasks.len();
+		self.tasks.retain(|_, queued| !Arc::ptr_eq(queued, task));
+
+		self.tasks.len() != len
+	}
+
+	/// take a task that may run on `cpu`, to be moved there.
+	///
+	/// the one with the largest vruntime is taken, since it would wait
+	/// the longest here.
+	pub fn steal(&mut self, cpu: usize) -> Option<Arc<Task>> {
+		let key = self
+			.tasks
+			.iter()
+			.rev()
+			.find(|(_, task)| task.lock_sched().is_allowed_on(cpu))
+			.map(|(key, _)| *key)?;
+
+		self.tasks.remove(&key)
+	}
+
+	/// whether `curr` that has `vruntime` should give cpu to a queued task.
+	pub fn should_preempt(&self, curr: &Arc<Task>, vruntime: u64) -> bool {
+		let Some((&(first, _), _)) = self.tasks.first_key_value() else {
+			return false;
+		};
+
+		self.is_idle(curr) || vruntime > first + PREEMPT_GRANULARITY
+	}
+}
+
+mod test {
+	use super::*;
+	use kfs_macro::ktest;
+
+	#[ktest(sched)]
+	fn weighted_vruntime() {
+		let mut nice_0 = SchedEntity::new(0);
+		let mut nice_5 = SchedEntity::new(5);
+		let mut nice_neg = SchedEntity::new(-5);
+
+		for entity in [&mut nice_0, &mut nice_5, &mut nice_neg] {
+			entity.start(0, 0);
+			entity.update(1_000_000);
+		}
+
+		assert_eq!(nice_0.vruntime(), 1_000_000);
+		assert!(nice_5.vruntime() > nice_0.vruntime());
+		assert!(nice_neg.vruntime() < nice_0.vruntime());
+	}
+
+	#[ktest(sched)]
+	fn clamp_nice() {
+		let mut entity = SchedEntity::new(0);
+
+		entity.set_nice(100);
+		assert_eq!(entity.nice(), NICE_MAX);
+
+		entity.set_nice(-100);
+		assert_eq!(entity.nice(), NICE_MIN);
+	}
+
+	#[ktest(sched)]
+	fn place_sleeper() {
+		let mut entity = SchedEntity::new(0);
+
+		entity.place(SLEEPER_CREDIT * 10);
+		assert_eq!(entity.vruntime(), SLEEPER_CREDIT * 9);
+
+		entity.place(0);
+		assert_eq!(entity.vruntime(), SLEEPER_CREDIT * 9);
+	}
+
+	#[ktest(sched)]
+	fn migrate_keeps_lag() {
+		let mut entity = SchedEntity::new(0);
+
+		entity.place(SLEEPER_CREDIT * 10);
+		entity.migrate(SLEEPER_CREDIT * 10, SLEEPER_CREDIT, 1);
+
+		assert_eq!(entity.vruntime(), 0);
+		assert_eq!(entity.cpu(), 1);
+	}
+}
@@ -0,0 +1,313 @@
+//! fast user-space locking.
+//!
+//! waiters are queued by the physical location of the futex word, so that
+//! processes mapping the same shared page wait on the same queue.
+//! private futexes are keyed by address space and virtual address instead.
+
+use core::{mem::size_of, ptr::read_volatile};
+
+use alloc::{collections::BTreeMap, sync::Arc};
+
+use crate::{
+	driver::hpet::{get_time_elapsed, get_timestamp_nano},
+	fs::vfs::TimeSpec,
+	mm::{
+		alloc::virt::{kmap, kunmap},
+		constant::PAGE_SIZE,
+		user::{memory::prefault, verify::verify_ptr, vma::AreaFlag},
+	},
+	process::{
+		signal::poll_signal_queue,
+		task::{Task, UserTaskExt, CURRENT},
+		wait_list::{WaitList, TAG_ANY},
+	},
+	sync::{Locked, LockedGuard},
+	syscall::errno::Errno,
+};
+
+use super::{
+	nano_sleep::ALARM,
+	sleep::{sleep_and_yield_lock, Sleep},
+};
+
+type FutexTable = BTreeMap<FutexKey, WaitList>;
+
+static FUTEX_TABLE: Locked<FutexTable> = Locked::new(BTreeMap::new());
+
+const FUTEX_PRIVATE_FLAG: usize = 128;
+const FUTEX_CLOCK_REALTIME: usize = 256;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum FutexKey {
+	Private { mm: usize, addr: usize },
+	Shared { paddr: usize },
+}
+
+impl FutexKey {
+	fn new(addr: usize, private: bool, task: &Arc<Task>) -> Result<Self, Errno> {
+		if addr % 4 != 0 {
+			return Err(Errno::EINVAL);
+		}
+
+		verify_ptr::<u32>(addr, task)?;
+
+		let ext = task.get_user_ext().expect("must be user process");
+		let mut memory = ext.lock_memory();
+
+		if private || !memory.query_flags_range(addr, 4, AreaFlag::Shared) {
+			return Ok(Self::Private {
+				mm: ext.memory_id(),
+				addr,
+			});
+		}
+
+		let page = memory.get_pd().lookup(addr).ok_or(Errno::EFAULT)?;
+
+		Ok(Self::Shared {
+			paddr: page + addr % PAGE_SIZE,
+		})
+	}
+}
+
+enum FutexOp {
+	Wait,
+	Wake,
+	Requeue,
+	CmpRequeue,
+	WaitBitset,
+	WakeBitset,
+}
+
+impl TryFrom<usize> for FutexOp {
+	type Error = Errno;
+
+	fn try_from(value: usize) -> Result<Self, Self::Error> {
+		match value {
+			0 => Ok(Self::Wait),
+			1 => Ok(Self::Wake),
+			3 => Ok(Self::Requeue),
+			4 => Ok(Self::CmpRequeue),
+			9 => Ok(Self::WaitBitset),
+			10 => Ok(Self::WakeBitset),
+			_ => Err(Errno::ENOSYS),
+		}
+	}
+}
+
+/// read the futex word through its physical page without page fault.
+///
+/// returns `None` if the page is not resident. (swapped out since verified)
+fn read_word(ext: &UserTaskExt, addr: usize) -> Result<Option<u32>, Errno> {
+	let mut memory = ext.lock_memory();
+
+	let Some(page) = memory.get_pd().lookup(addr) else {
+		return Ok(None);
+	};
+
+	let ptr = kmap(page).map_err(|_| Errno::ENOMEM)?;
+	let word = unsafe { read_volatile(ptr.as_ptr().add(addr % PAGE_SIZE) as *const u32) };
+	kunmap(ptr.as_ptr() as usize);
+
+	Ok(Some(word))
+}
+
+/// lock `FUTEX_TABLE` and read the futex word at `addr` under the lock.
+///
+/// the page can't be faulted in while holding the lock, as it may sleep.
+/// instead, the lock is released and the word is prefaulted again.
+fn lock_and_read(addr: usize) -> Result<(LockedGuard<'static, FutexTable>, u32), Errno> {
+	let current = unsafe { CURRENT.get_ref() };
+	let ext = current.get_user_ext().expect("must be user process");
+
+	loop {
+		let table = FUTEX_TABLE.lock();
+
+		if let Some(word) = read_word(ext, addr)? {
+			return Ok((table, word));
+		}
+
+		drop(table);
+		prefault(ext, addr, size_of::<u32>(), false)?;
+	}
+}
+
+/// convert user timeout to the target time of `ALARM`. (realtime, ns)
+fn deadline(timeout: usize, absolute: bool, realtime: bool) -> Result<Option<u64>, Errno> {
+	if timeout == 0 {
+		return Ok(None);
+	}
+
+	let current = unsafe { CURRENT.get_ref() };
+	let time = verify_ptr::<TimeSpec>(timeout, current)?.nano();
+
+	let target = match (absolute, realtime) {
+		(false, _) => get_timestamp_nano() + time,
+		(true, true) => time,
+		// monotonic clock counts from boot.
+		(true, false) => (time + get_timestamp_nano()).saturating_sub(get_time_elapsed()),
+	};
+
+	Ok(Some(target))
+}
+
+fn futex_wait(
+	key: FutexKey,
+	addr: usize,
+	val: u32,
+	bitset: u32,
+	deadline: Option<u64>,
+) -> Result<usize, Errno> {
+	let current = unsafe { CURRENT.get_ref() };
+
+	if bitset == 0 {
+		return Err(Errno::EINVAL);
+	}
+
+	// checked under the lock, so that a waker changing the word
+	// and calling `FUTEX_WAKE` after this can't be missed.
+	let (mut table, word) = lock_and_read(addr)?;
+	if word != val {
+		return Err(Errno::EAGAIN);
+	}
+
+	if let Some(target) = deadline {
+		ALARM.lock().register(target);
+	}
+
+	table
+		.entry(key)
+		.or_insert_with(WaitList::new)
+		.register_tagged(bitset);
+
+	sleep_and_yield_lock(Sleep::Light, table);
+
+	// still queued unless woken by `FUTEX_WAKE`. (possibly requeued to other key)
+	let mut table = FUTEX_TABLE.lock();
+	let queued = table.values_mut().any(|list| list.unregister(current));
+	table.retain(|_, list| !list.is_empty());
+	drop(table);
+
+	if !queued {
+		return Ok(0);
+	}
+
+	if deadline.is_some_and(|target| target <= get_timestamp_nano()) {
+		return Err(Errno::ETIMEDOUT);
+	}
+
+	unsafe { poll_signal_queue() }.map(|_| 0)
+}
+
+fn wake_key(key: FutexKey, count: usize, bitset: u32) -> usize {
+	let mut table = FUTEX_TABLE.lock();
+
+	let Some(list) = table.get_mut(&key) else {
+		return 0;
+	};
+
+	let woken = list.wake_up_tagged(count, bitset);
+	if list.is_empty() {
+		table.remove(&key);
+	}
+
+	woken
+}
+
+fn futex_wake(key: FutexKey, count: usize, bitset: u32) -> Result<usize, Errno> {
+	if bitset == 0 {
+		return Err(Errno::EINVAL);
+	}
+
+	Ok(wake_key(key, count, bitset))
+}
+
+/// wake up `count` waiters on `key` and move at most `requeue` others to `key2`.
+fn futex_requeue(
+	key: FutexKey,
+	key2: FutexKey,
+	count: usize,
+	requeue: usize,
+	cmp: Option<(usize, u32)>,
+) -> Result<usize, Errno> {
+	let mut table = match cmp {
+		Some((addr, val)) => {
+			let (table, word) = lock_and_read(addr)?;
+			if word != val {
+				return Err(Errno::EAGAIN);
+			}
+			table
+		}
+		None => FUTEX_TABLE.lock(),
+	};
+
+	let Some(mut list) = table.remove(&key) else {
+		return Ok(0);
+	};
+
+	let woken = list.wake_up_tagged(count, TAG_ANY);
+
+	if key == key2 {
+		table.insert(key, list);
+		return Ok(woken);
+	}
+
+	if requeue > 0 {
+		let list2 = table.entry(key2).or_insert_with(WaitList::new);
+		list.requeue(list2, requeue);
+	}
+
+	if !list.is_empty() {
+		table.insert(key, list);
+	}
+
+	Ok(woken)
+}
+
+/// wake up a waiter on `addr`, as the exiting thread cleared its tid there.
+pub fn wake_child_tid(addr: usize) {
+	let current = unsafe { CURRENT.get_ref() };
+
+	if let Ok(key) = FutexKey::new(addr, false, current) {
+		wake_key(key, 1, TAG_ANY);
+	}
+}
+
+pub fn sys_futex(
+	uaddr: usize,
+	op: usize,
+	val: usize,
+	timeout: usize,
+	uaddr2: usize,
+	val3: usize,
+) -> Result<usize, Errno> {
+	let current = unsafe { CURRENT.get_ref() };
+
+	let private = op & FUTEX_PRIVATE_FLAG != 0;
+	let realtime = op & FUTEX_CLOCK_REALTIME != 0;
+	let op = FutexOp::try_from(op & !(FUTEX_PRIVATE_FLAG | FUTEX_CLOCK_REALTIME))?;
+
+	let key = FutexKey::new(uaddr, private, current)?;
+
+	match op {
+		FutexOp::Wait => {
+			let deadline = deadline(timeout, false, realtime)?;
+			futex_wait(key, uaddr, val as u32, TAG_ANY, deadline)
+		}
+		FutexOp::WaitBitset => {
+			let deadline = deadline(timeout, true, realtime)?;
+			futex_wait(key, uaddr, val as u32, val3 as u32, deadline)
+		}
+		FutexOp::Wake => futex_wake(key, val, TAG_ANY),
+		FutexOp::WakeBitset => futex_wake(key, val, val3 as u32),
+		FutexOp::Requeue | FutexOp::CmpRequeue => {
+			let key2 = FutexKey::new(uaddr2, private, current)?;
+			let cmp = match op {
+				FutexOp::CmpRequeue => Some((uaddr, val3 as u32)),
+				_ => None,
+			};
+
+			// for requeue, `timeout` argument carries the number to requeue.
+			futex_requeue(key, key2, val, timeout, cmp)
+		}
+	}
+}
@@ -34,7 +34,7 @@ impl Alarm {
 		}
 	}
 
-	fn register(&mut self, target_time: u64) {
+	pub fn register(&mut self, target_time: u64) {
 		let current = unsafe { CURRENT.get_ref() };
 
 		self.by_time
@@ -0,0 +1,117 @@
+use alloc::{sync::Arc, vec::Vec};
+
+use crate::{
+	process::{
+		process_tree::PROCESS_TREE,
+		relation::Pid,
+		task::{Task, CURRENT},
+	},
+	syscall::errno::Errno,
+};
+
+use super::fair::{NICE_MAX, NICE_MIN};
+
+enum Which {
+	Process,
+	PGroup,
+	User,
+}
+
+impl TryFrom<usize> for Which {
+	type Error = Errno;
+
+	fn try_from(value: usize) -> Result<Self, Self::Error> {
+		match value {
+			0 => Ok(Self::Process),
+			1 => Ok(Self::PGroup),
+			2 => Ok(Self::User),
+			_ => Err(Errno::EINVAL),
+		}
+	}
+}
+
+/// tasks selected by `which` and `who`. (`who` = 0 means current one)
+fn targets(which: Which, who: usize) -> Result<Vec<Arc<Task>>, Errno> {
+	let current = unsafe { CURRENT.get_ref() }.clone();
+
+	let tasks: Vec<_> = match which {
+		Which::Process if who == 0 => Vec::from([current]),
+		Which::Process => PROCESS_TREE
+			.get_task(Pid::from_raw(who))
+			.into_iter()
+			.collect(),
+		Which::PGroup | Which::User => {
+			let all: Vec<_> = PROCESS_TREE.lock().members().values().cloned().collect();
+
+			all.into_iter()
+				.filter(|task| !task.is_kernel())
+				.filter(|task| match which {
+					Which::PGroup if who == 0 => task.get_pgid() == current.get_pgid(),
+					Which::PGroup => task.get_pgid().as_raw() == who,
+					_ if who == 0 => task.get_uid() == current.get_uid(),
+					_ => task.get_uid() == who,
+				})
+				.collect()
+		}
+	};
+
+	match tasks.is_empty() {
+		true => Err(Errno::ESRCH),
+		false => Ok(tasks),
+	}
+}
+
+fn set_nice(task: &Task, nice: i32) -> Result<(), Errno> {
+	let current = unsafe { CURRENT.get_ref() };
+
+	if !current.get_cred().is_owner_of(&task.get_cred()) {
+		return Err(Errno::EPERM);
+	}
+
+	let mut sched = task.lock_sched();
+	if nice < sched.nice() && !current.is_privileged() {
+		return Err(Errno::EACCES);
+	}
+
+	sched.set_nice(nice);
+	Ok(())
+}
+
+pub fn sys_nice(inc: isize) -> Result<usize, Errno> {
+	let current = unsafe { CURRENT.get_ref() };
+
+	let inc = inc.clamp(
+		(NICE_MIN - NICE_MAX) as isize,
+		(NICE_MAX - NICE_MIN) as isize,
+	);
+	let mut sched = current.lock_sched();
+
+	if inc < 0 && !current.is_privileged() {
+		return Err(Errno::EPERM);
+	}
+
+	let nice = sched.nice() + inc as i32;
+	sched.set_nice(nice);
+	Ok(0)
+}
+
+/// returns `20 - nice` of the highest priority among selected tasks,
+/// so that the result is always positive. (libc converts it back)
+pub fn sys_getpriority(which: usize, who: usize) -> Result<usize, Errno> {
+	let nice = targets(which.try_into()?, who)?
+		.iter()
+		.map(|task| task.lock_sched().nice())
+		.min()
+		.unwrap_or_default();
+
+	Ok((20 - nice) as usize)
+}
+
+pub fn sys_setpriority(which: usize, who: usize, prio: i32) -> Result<usize, Errno> {
+	let nice = prio.clamp(NICE_MIN, NICE_MAX);
+
+	targets(which.try_into()?, who)?
+		.iter()
+		.map(|task| set_nice(task, nice))
+		.fold(Ok(0), |result, r| r.and(result))
+}
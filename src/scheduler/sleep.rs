@@ -73,21 +73,32 @@ pub fn sleep_and_yield_atomic_optional(sleep: Sleep, atomic: Option<AtomicOps>)
 	}
 }
 
+/// make sleeping `task` runnable.
+///
+/// a task still on cpu (going to sleep, but not switched out yet) is not queued.
+/// it keeps running, or is queued by `switch_task_finish` as it is runnable again.
+fn wake_locked(task: &Arc<Task>, mut state_lock: LockedGuard<'_, State>) {
+	*state_lock = State::Running;
+
+	if task.lock_sched().is_on_cpu() {
+		return;
+	}
+
+	drop(state_lock);
+	schedule_last(task.clone());
+}
+
 pub fn wake_up_deep_sleep(task: &Arc<Task>) {
-	let mut state_lock = task.lock_state();
+	let state_lock = task.lock_state();
 	if *state_lock == State::DeepSleep || *state_lock == State::Sleeping {
-		*state_lock = State::Running;
-		drop(state_lock);
-		schedule_last(task.clone());
+		wake_locked(task, state_lock);
 	}
 }
 
 pub fn wake_up_sleep(task: &Arc<Task>) {
-	let mut state_lock = task.lock_state();
+	let state_lock = task.lock_state();
 	if *state_lock == State::Sleeping {
-		*state_lock = State::Running;
-		drop(state_lock);
-		schedule_last(task.clone());
+		wake_locked(task, state_lock);
 	}
 }
 
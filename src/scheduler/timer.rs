@@ -0,0 +1,294 @@
+//! kernel timers.
+//!
+//! timers are hashed into `WHEEL_SIZE` slots by expiry time, one slot per
+//! timer tick. every tick runs the slots passed since the last tick, so
+//! adding and removing a timer doesn't depend on the number of timers.
+//! a timer further than one revolution away stays in its slot until then.
+//!
+//! expiry times are on the realtime clock. (`get_timestamp_nano`)
+
+use alloc::{collections::BTreeMap, sync::Weak, vec::Vec};
+
+use crate::{config::TIMER_FREQUENCY_HZ, driver::hpet::get_timestamp_nano, sync::Locked};
+
+const WHEEL_SIZE: usize = 256;
+const NANO_PER_SLOT: u64 = 1_000_000_000 / TIMER_FREQUENCY_HZ as u64;
+
+static TIMER_WHEEL: Locked<TimerWheel> = Locked::new(TimerWheel::new());
+
+pub trait Expire: Send + Sync {
+	/// called from timer interrupt when timer `id` expired at `now`.
+	///
+	/// returns next expiry time to rearm the timer with same `id`.
+	fn expire(&self, id: usize, now: u64) -> Option<u64>;
+}
+
+struct Timer {
+	id: usize,
+	expires: u64,
+	handler: Weak<dyn Expire>,
+}
+
+struct TimerWheel {
+	slots: [Vec<Timer>; WHEEL_SIZE],
+	/// slot of timers by id.
+	index: BTreeMap<usize, usize>,
+	/// next tick to run. (absolute, in slots)
+	clock: u64,
+	next_id: usize,
+}
+
+impl TimerWheel {
+	const fn new() -> Self {
+		const EMPTY: Vec<Timer> = Vec::new();
+
+		Self {
+			slots: [EMPTY; WHEEL_SIZE],
+			index: BTreeMap::new(),
+			clock: 0,
+			next_id: 1,
+		}
+	}
+
+	fn alloc_id(&mut self) -> usize {
+		let id = self.next_id;
+		self.next_id += 1;
+
+		id
+	}
+
+	fn insert(&mut self, timer: Timer) {
+		// a timer already expired runs on the next tick.
+		let tick = (timer.expires / NANO_PER_SLOT).max(self.clock);
+		let slot = tick as usize % WHEEL_SIZE;
+
+		self.index.insert(timer.id, slot);
+		self.slots[slot].push(timer);
+	}
+
+	fn remove(&mut self, id: usize) -> bool {
+		let Some(slot) = self.index.remove(&id) else {
+			return false;
+		};
+
+		self.slots[slot].retain(|timer| timer.id != id);
+		true
+	}
+
+	/// take timers expired at `now`.
+	fn expired(&mut self, now: u64) -> Vec<Timer> {
+		let target = now / NANO_PER_SLOT;
+		let mut expired = Vec::new();
+
+		if target < self.clock {
+			return expired;
+		}
+
+		// after a long gap, one revolution visits every slot.
+		let ticks = (target - self.clock + 1).min(WHEEL_SIZE as u64);
+
+		for tick in target + 1 - ticks..=target {
+			let slot = &mut self.slots[tick as usize % WHEEL_SIZE];
+
+			let (due, remain) = slot.drain(..).partition(|timer| timer.expires <= now);
+			*slot = remain;
+			expired.extend::<Vec<_>>(due);
+		}
+
+		for timer in expired.iter() {
+			self.index.remove(&timer.id);
+		}
+
+		self.clock = target + 1;
+		expired
+	}
+}
+
+/// arm a timer to call `handler` at `expires`. returns id of the timer.
+pub fn add_timer(expires: u64, handler: Weak<dyn Expire>) -> usize {
+	let mut wheel = TIMER_WHEEL.lock();
+	let id = wheel.alloc_id();
+
+	wheel.insert(Timer {
+		id,
+		expires,
+		handler,
+	});
+
+	id
+}
+
+/// returns false if timer `id` already expired or removed.
+pub fn del_timer(id: usize) -> bool {
+	TIMER_WHEEL.lock().remove(id)
+}
+
+/// run expired timers. called by timer interrupt.
+pub fn run_timers() {
+	let now = get_timestamp_nano();
+	let expired = TIMER_WHEEL.lock().expired(now);
+
+	// handlers may add or remove timers, so they run without the lock.
+	for timer in expired {
+		let Some(handler) = timer.handler.upgrade() else {
+			continue;
+		};
+
+		if let Some(expires) = handler.expire(timer.id, now) {
+			TIMER_WHEEL.lock().insert(Timer { expires, ..timer });
+		}
+	}
+}
+
+/// a one-shot or periodic timer on the wheel, for handlers to embed.
+pub struct IntervalTimer {
+	/// id of the armed timer on the wheel.
+	id: Option<usize>,
+	expires: u64,
+	interval: u64,
+	/// expirations missed at the last expiry. (e.g. interrupts disabled)
+	overrun: usize,
+}
+
+impl IntervalTimer {
+	pub const fn new() -> Self {
+		Self {
+			id: None,
+			expires: 0,
+			interval: 0,
+			overrun: 0,
+		}
+	}
+
+	/// (re)arm to expire at `expires` and every `interval` ns after that.
+	/// `expires` of zero disarms the timer.
+	pub fn arm(&mut self, expires: u64, interval: u64, handler: Weak<dyn Expire>) {
+		self.disarm();
+
+		if expires != 0 {
+			self.id = Some(add_timer(expires, handler));
+			self.expires = expires;
+			self.interval = interval;
+		}
+	}
+
+	pub fn disarm(&mut self) {
+		if let Some(id) = self.id.take() {
+			del_timer(id);
+		}
+		self.interval = 0;
+	}
+
+	/// time to next expiry and interval at `now`. zero time means disarmed.
+	pub fn remaining(&self, now: u64) -> (u64, u64) {
+		match self.id {
+			Some(_) => (self.expires.saturating_sub(now).max(1), self.interval),
+			None => (0, self.interval),
+		}
+	}
+
+	pub fn overrun(&self) -> usize {
+		self.overrun
+	}
+
+	/// handle expiry of wheel timer `id`.
+	///
+	/// returns number of expirations including missed ones, and next expiry
+	/// time, or `None` if `id` is stale. (disarmed or rearmed meanwhile)
+	pub fn expire(&mut self, id: usize, now: u64) -> Option<(usize, Option<u64>)> {
+		if self.id != Some(id) {
+			return None;
+		}
+
+		if self.interval == 0 {
+			self.id = None;
+			self.overrun = 0;
+			return Some((1, None));
+		}
+
+		let missed = (now.saturating_sub(self.expires) / self.interval) as usize;
+
+		self.overrun = missed;
+		self.expires += (missed as u64 + 1) * self.interval;
+		Some((missed + 1, Some(self.expires)))
+	}
+}
+
+impl Drop for IntervalTimer {
+	fn drop(&mut self) {
+		self.disarm();
+	}
+}
+
+mod test {
+	use super::*;
+	use kfs_macro::ktest;
+
+	use alloc::sync::Arc;
+
+	struct Nothing;
+
+	impl Expire for Nothing {
+		fn expire(&self, _id: usize, _now: u64) -> Option<u64> {
+			None
+		}
+	}
+
+	fn timer(id: usize, expires: u64, handler: &Arc<Nothing>) -> Timer {
+		let handler: Arc<dyn Expire> = handler.clone();
+
+		Timer {
+			id,
+			expires,
+			handler: Arc::downgrade(&handler),
+		}
+	}
+
+	#[ktest(timer)]
+	fn expire_in_order() {
+		let handler = Arc::new(Nothing);
+		let mut wheel = TimerWheel::new();
+		let revolution = NANO_PER_SLOT * WHEEL_SIZE as u64;
+
+		wheel.insert(timer(1, NANO_PER_SLOT * 3, &handler));
+		wheel.insert(timer(2, NANO_PER_SLOT * 3 + revolution, &handler));
+
+		assert!(wheel.expired(NANO_PER_SLOT * 2).is_empty());
+
+		let ids: Vec<_> = wheel
+			.expired(NANO_PER_SLOT * 4)
+			.iter()
+			.map(|t| t.id)
+			.collect();
+		assert_eq!(ids, [1]);
+
+		let ids: Vec<_> = wheel.expired(revolution * 2).iter().map(|t| t.id).collect();
+		assert_eq!(ids, [2]);
+	}
+
+	#[ktest(timer)]
+	fn remove_timer() {
+		let handler = Arc::new(Nothing);
+		let mut wheel = TimerWheel::new();
+
+		wheel.insert(timer(1, NANO_PER_SLOT, &handler));
+
+		assert!(wheel.remove(1));
+		assert!(!wheel.remove(1));
+		assert!(wheel.expired(NANO_PER_SLOT * 2).is_empty());
+	}
+
+	#[ktest(timer)]
+	fn interval_overrun() {
+		let mut timer = IntervalTimer::new();
+		timer.id = Some(1);
+		timer.expires = 100;
+		timer.interval = 10;
+
+		assert_eq!(timer.expire(2, 100), None);
+		assert_eq!(timer.expire(1, 125), Some((3, Some(130))));
+		assert_eq!(timer.overrun(), 2);
+
+		timer.id = None;
+	}
+}
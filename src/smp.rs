@@ -1,5 +1,30 @@
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::config::NR_CPUS;
 use crate::driver::apic::local::LOCAL_APIC;
 
+/// bitmask of every cpu the kernel can handle.
+pub const ALL_CPUS: usize = (1 << NR_CPUS) - 1;
+
+/// bitmask of cpus that are running the scheduler.
+///
+/// note: application processors are not started yet. (no INIT/SIPI is sent)
+/// so only the bootstrap processor is online, and a cpu joins once it calls `set_idle_task`.
+static ONLINE_CPUS: AtomicUsize = AtomicUsize::new(0);
+
 pub fn smp_id() -> usize {
 	LOCAL_APIC.id()
 }
+
+pub fn set_cpu_online(id: usize) {
+	ONLINE_CPUS.fetch_or(1 << id, Ordering::Relaxed);
+}
+
+pub fn online_cpus() -> usize {
+	ONLINE_CPUS.load(Ordering::Relaxed)
+}
+
+/// cpu ids in the `mask`.
+pub fn cpus_in(mask: usize) -> impl Iterator<Item = usize> {
+	(0..NR_CPUS).filter(move |id| mask & (1 << id) != 0)
+}
@@ -7,6 +7,6 @@ mod raw_lock;
 pub use cpu_local::CpuLocal;
 pub use local_locked::{LocalLocked, LocalLockedGuard};
 pub use lock_rw::{LockRW, ReadLockGuard, WriteLockGuard};
-pub use locked::{Locked, LockedGuard};
+pub use locked::{ArcLockedGuard, Locked, LockedGuard};
 pub use raw_lock::get_lock_depth;
 pub use raw_lock::TryLockFail;
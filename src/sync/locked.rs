@@ -4,6 +4,8 @@ use core::{
 	ops::{Deref, DerefMut},
 };
 
+use alloc::sync::Arc;
+
 use super::raw_lock::{GlobalSpinLock, TryLockFail};
 
 #[derive(Debug)]
@@ -75,6 +77,13 @@ impl<T: ?Sized> Locked<T> {
 			.map(|_| unsafe { LockedGuard::new(self) })
 	}
 
+	/// the guard owns `self`, so it stays valid even if the `Arc` is
+	/// replaced by its owner meanwhile.
+	pub fn lock_arc(self: Arc<Self>) -> ArcLockedGuard<T> {
+		self.inner.lock();
+		ArcLockedGuard { locked: self }
+	}
+
 	pub unsafe fn lock_manual(&self) -> &mut T {
 		self.inner.lock();
 		&mut *self.value.get()
@@ -118,3 +127,27 @@ impl<'lock, T: ?Sized> DerefMut for LockedGuard<'lock, T> {
 		unsafe { &mut *self.locked.value.get() }
 	}
 }
+
+pub struct ArcLockedGuard<T: ?Sized> {
+	locked: Arc<Locked<T>>,
+}
+
+impl<T: ?Sized> Drop for ArcLockedGuard<T> {
+	fn drop(&mut self) {
+		self.locked.inner.unlock();
+	}
+}
+
+impl<T: ?Sized> Deref for ArcLockedGuard<T> {
+	type Target = T;
+
+	fn deref(&self) -> &Self::Target {
+		unsafe { &*self.locked.value.get() }
+	}
+}
+
+impl<T: ?Sized> DerefMut for ArcLockedGuard<T> {
+	fn deref_mut(&mut self) -> &mut Self::Target {
+		unsafe { &mut *self.locked.value.get() }
+	}
+}
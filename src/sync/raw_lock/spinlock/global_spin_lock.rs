@@ -1,3 +1,6 @@
+use core::hint::spin_loop;
+
+use crate::driver::apic::ipi::flush_tlb_requests;
 use crate::interrupt::in_interrupt_context;
 use crate::interrupt::irq_disable;
 use crate::interrupt::irq_enable;
@@ -36,7 +39,13 @@ impl GlobalSpinLock {
 
 	pub fn lock(&self) {
 		irq_disable();
-		self.raw.lock();
+
+		// holder may be waiting for this cpu to do TLB shootdown.
+		while self.raw.try_lock().is_err() {
+			flush_tlb_requests();
+			spin_loop();
+		}
+
 		inc_lock_depth();
 	}
 
@@ -11,50 +11,74 @@ pub mod wait;
 
 mod dup;
 mod reboot;
+mod rusage;
 mod uname;
 
 use core::fmt::{self, Display};
-use core::mem::transmute;
+use core::mem::{size_of, transmute};
 
-use crate::driver::pipe::sys_pipe;
+use crate::driver::pipe::{sys_pipe, sys_pipe2};
 use crate::driver::terminal::{sys_attach_tty, sys_deteach_tty};
 use crate::driver::vga::sys_draw_buffer;
 use crate::elf::syscall::*;
 use crate::fs::syscall::*;
+use crate::fs::timerfd::{sys_timerfd_create, sys_timerfd_gettime, sys_timerfd_settime};
 use crate::input::keyboard::sys_get_key_state;
 use crate::interrupt::InterruptFrame;
+use crate::mm::swap::{sys_swapoff, sys_swapon};
 use crate::mm::user::brk::sys_brk;
-use crate::mm::user::mmap::{sys_mmap, sys_munmap};
+use crate::mm::user::mmap::{
+	sys_madvise, sys_mincore, sys_mmap, sys_mprotect, sys_mremap, sys_msync, sys_munmap,
+};
 
 use crate::net::syscall::*;
-use crate::process::exit::sys_exit;
-use crate::process::gid::{sys_getgid, sys_setgid};
+use crate::process::cred::{
+	sys_getgroups, sys_getresgid, sys_getresuid, sys_setgroups, sys_setregid, sys_setresgid,
+	sys_setresuid, sys_setreuid,
+};
+use crate::process::exit::{sys_exit, sys_exit_group};
+use crate::process::gid::{sys_getegid, sys_getgid, sys_setgid};
+use crate::process::itimer::{sys_alarm, sys_getitimer, sys_setitimer};
+use crate::process::posix_timer::{
+	sys_timer_create, sys_timer_delete, sys_timer_getoverrun, sys_timer_gettime, sys_timer_settime,
+};
+use crate::process::ptrace::{self, sys_ptrace};
+use crate::process::rlimit::{sys_getrlimit, sys_prlimit, sys_setrlimit};
 use crate::process::set_thread_area::{sys_set_thread_area, sys_set_tid_address};
 use crate::process::signal::sig_handler::SigAction;
+use crate::process::signal::sig_mask::SigMask;
 use crate::process::task::CURRENT;
-use crate::process::uid::{sys_getuid, sys_setuid};
+use crate::process::uid::{sys_geteuid, sys_getuid, sys_setuid};
+use crate::scheduler::affinity::{sys_sched_getaffinity, sys_sched_setaffinity};
+use crate::scheduler::futex::sys_futex;
 use crate::scheduler::nano_sleep::sys_nanosleep;
+use crate::scheduler::priority::{sys_getpriority, sys_nice, sys_setpriority};
 use crate::scheduler::sys_sched_yield;
 use crate::{pr_warn, trace_feature};
 
 use self::clock::sys_clock_gettime;
-use self::dup::{sys_dup, sys_dup2};
+use self::dup::{sys_dup, sys_dup2, sys_dup3};
 use self::errno::Errno;
 use self::exec::*;
-use self::fork::sys_fork;
-use self::kill::sys_kill;
+use self::fork::{sys_clone, sys_fork};
+use self::kill::{sys_kill, sys_sigqueueinfo};
 use self::poll::sys_poll;
 use self::reboot::sys_reboot;
 use self::relation::{
-	sys_getpgid, sys_getpgrp, sys_getpid, sys_getppid, sys_getsid, sys_setpgid, sys_setsid,
+	sys_getpgid, sys_getpgrp, sys_getpid, sys_getppid, sys_getsid, sys_gettid, sys_setpgid,
+	sys_setsid,
 };
+use self::rusage::{sys_getrusage, sys_times};
 use self::sendfile::sys_sendfile;
-use self::signal::{sys_sigaction, sys_signal, sys_sigprocmask, sys_sigreturn, sys_sigsuspend};
+use self::signal::{
+	sys_sigaction, sys_sigaltstack, sys_signal, sys_sigpending, sys_sigprocmask, sys_sigreturn,
+	sys_sigsuspend, sys_sigtimedwait,
+};
 use self::uname::sys_uname;
-use self::wait::sys_waitpid;
+use self::wait::{sys_wait4, sys_waitpid};
 
 /// `syscall no` must be sorted.
-const IGNORE_SYSCALL_RESTART: [usize; 2] = [162, 179];
+const IGNORE_SYSCALL_RESTART: [usize; 3] = [162, 177, 179];
 
 #[no_mangle]
 pub extern "C" fn handle_syscall_impl(mut frame: InterruptFrame) {
@@ -67,6 +91,8 @@ pub extern "C" fn handle_syscall_impl(mut frame: InterruptFrame) {
 			.as_ref()
 	};
 
+	ptrace::syscall_enter(&mut frame);
+
 	let mut ret;
 	loop {
 		let mut restart = false;
@@ -86,10 +112,9 @@ pub extern "C" fn handle_syscall_impl(mut frame: InterruptFrame) {
 	// Because of signal system, This can be `return` from timer interrupt.
 	// To preserve previous `eax` value, We should check where this `return` go.
 	if frame.handler == handle_syscall_impl as usize {
-		unsafe {
-			((&mut frame.eax) as *mut _ as usize as *mut isize)
-				.write_volatile(syscall_return_to_isize(&ret))
-		};
+		let ret = ptrace::syscall_exit(&mut frame, syscall_return_to_isize(&ret));
+
+		unsafe { ((&mut frame.eax) as *mut _ as usize as *mut isize).write_volatile(ret) };
 	}
 }
 
@@ -477,6 +502,7 @@ fn get_syscall_info(n: usize) -> (&'static str, usize) {
 		383 => ("statx", 5),
 		384 => ("arch_prctl", 6),
 		403 => ("clock_gettime64", 2),
+		436 => ("close_range", 3),
 		_ => ("unknown", 6),
 	}
 }
@@ -542,7 +568,7 @@ fn __syscall(frame: &mut InterruptFrame, restart: &mut bool) -> Result<usize, Er
 		4 => sys_write(frame.ebx as isize, frame.ecx, frame.edx),
 		5 => sys_open(frame.ebx, frame.ecx as i32, frame.edx as u32),
 		6 => sys_close(frame.ebx as isize),
-		7 => sys_waitpid(frame.ebx as isize, frame.ecx as *mut isize, frame.edx),
+		7 => sys_waitpid(frame.ebx as isize, frame.ecx, frame.edx),
 		8 => sys_creat(frame.ebx, frame.ecx as u32),
 		9 => sys_link(frame.ebx, frame.ecx),
 		10 => sys_unlink(frame.ebx),
@@ -551,16 +577,22 @@ fn __syscall(frame: &mut InterruptFrame, restart: &mut bool) -> Result<usize, Er
 		15 => sys_chmod(frame.ebx, frame.ecx as u32),
 		19 => sys_lseek(frame.ebx as isize, frame.ecx as isize, frame.edx as isize),
 		20 => sys_getpid(),
-		21 => sys_mount(frame.ebx, frame.ecx, frame.edx),
+		21 => sys_mount(frame.ebx, frame.ecx, frame.edx, frame.esi),
 		22 => sys_umount(frame.ebx),
+		26 => sys_ptrace(frame.ebx, frame.ecx, frame.edx, frame.esi),
+		27 => sys_alarm(frame.ebx),
+		34 => sys_nice(frame.ebx as isize),
 		37 => sys_kill(frame.ebx as isize, frame.ecx as isize),
 		38 => sys_rename(frame.ebx, frame.ecx),
 		39 => sys_mkdir(frame.ebx, frame.ecx as u32),
 		40 => sys_rmdir(frame.ebx),
 		41 => sys_dup(frame.ebx),
 		42 => sys_pipe(frame.ebx),
+		43 => sys_times(frame.ebx),
 		45 => sys_brk(frame.ebx),
 		48 => sys_signal(frame.ebx, frame.ecx),
+		49 | 201 => sys_geteuid(),
+		50 | 202 => sys_getegid(),
 		// todo: umount2
 		52 => sys_umount(frame.ebx),
 		54 => sys_ioctl(frame.ebx as isize, frame.ecx, frame.edx),
@@ -570,16 +602,24 @@ fn __syscall(frame: &mut InterruptFrame, restart: &mut bool) -> Result<usize, Er
 		64 => sys_getppid(),
 		65 => sys_getpgrp(),
 		66 => sys_setsid(),
-		// sigaction / rt_sigaction
-		67 | 174 => sys_sigaction(
+		67 => sys_sigaction(
 			frame.ebx,
 			frame.ecx as *const SigAction,
 			frame.edx as *mut SigAction,
-			frame.esi,
+			size_of::<SigMask>(),
 		),
-		80 => sys_reboot(frame.ebx),
+		70 | 203 => sys_setreuid(frame.ebx, frame.ecx),
+		71 | 204 => sys_setregid(frame.ebx, frame.ecx),
+		75 => sys_setrlimit(frame.ebx, frame.ecx),
+		// getrlimit / ugetrlimit
+		76 | 191 => sys_getrlimit(frame.ebx, frame.ecx),
+		77 => sys_getrusage(frame.ebx as isize, frame.ecx),
+		80 | 205 => sys_getgroups(frame.ebx, frame.ecx),
+		81 | 206 => sys_setgroups(frame.ebx, frame.ecx),
 		83 => sys_symlink(frame.ebx, frame.ecx),
 		85 => sys_readlink(frame.ebx, frame.ecx, frame.edx),
+		87 => sys_swapon(frame.ebx, frame.ecx),
+		88 => sys_reboot(frame.ebx),
 		// mmap / mmap2 TODO: proper mmap2 handling
 		90 | 192 => sys_mmap(
 			frame.ebx,
@@ -592,12 +632,23 @@ fn __syscall(frame: &mut InterruptFrame, restart: &mut bool) -> Result<usize, Er
 		.map_err(|_| Errno::EPERM), // FIXME: proper return type
 		91 => sys_munmap(frame.ebx, frame.ecx),
 		92 => sys_truncate(frame.ebx, frame.ecx as isize),
-		// TODO: wait4
-		114 => sys_waitpid(frame.ebx as isize, frame.ecx as *mut isize, frame.edx),
-		119 => sys_sigreturn(frame, restart),
-		// TODO: clone
-		// 120 => sys_fork(frame),
+		96 => sys_getpriority(frame.ebx, frame.ecx),
+		97 => sys_setpriority(frame.ebx, frame.ecx, frame.edx as i32),
+		104 => sys_setitimer(frame.ebx, frame.ecx, frame.edx),
+		105 => sys_getitimer(frame.ebx, frame.ecx),
+		114 => sys_wait4(frame.ebx as isize, frame.ecx, frame.edx, frame.esi),
+		115 => sys_swapoff(frame.ebx),
+		119 | 173 => sys_sigreturn(frame, restart),
+		120 => sys_clone(
+			frame,
+			frame.ebx as u32,
+			frame.ecx,
+			frame.edx,
+			frame.esi,
+			frame.edi,
+		),
 		122 => sys_uname(frame.ebx),
+		125 => sys_mprotect(frame.ebx, frame.ecx, frame.edx as i32),
 		128 => sys_init_module(frame.ebx),
 		129 => sys_cleanup_module(frame.ebx),
 		132 => sys_getpgid(frame.ebx),
@@ -611,29 +662,55 @@ fn __syscall(frame: &mut InterruptFrame, restart: &mut bool) -> Result<usize, Er
 			frame.edi as isize,
 		),
 		141 => sys_getdents(frame.ebx as isize, frame.ecx, frame.edx),
+		144 => sys_msync(frame.ebx, frame.ecx, frame.edx as i32),
 		145 => sys_readv(frame.ebx as isize, frame.ecx, frame.edx),
 		146 => sys_writev(frame.ebx as isize, frame.ecx, frame.edx),
 		147 => sys_getsid(frame.ebx),
 		158 => sys_sched_yield(),
 		162 => sys_nanosleep(frame.ebx, frame.ecx),
+		163 => sys_mremap(frame.ebx, frame.ecx, frame.edx, frame.esi as i32, frame.edi),
+		164 | 208 => sys_setresuid(frame.ebx, frame.ecx, frame.edx),
+		165 | 209 => sys_getresuid(frame.ebx, frame.ecx, frame.edx),
 		168 => sys_poll(frame.ebx, frame.ecx, frame.edx),
-		// TODO: rt_sigprocmask
-		175 => sys_sigprocmask(frame.ebx, frame.ecx, frame.edx),
-		// TODO: rt_sigsuspend
-		179 => sys_sigsuspend(frame.ebx),
+		170 | 210 => sys_setresgid(frame.ebx, frame.ecx, frame.edx),
+		171 | 211 => sys_getresgid(frame.ebx, frame.ecx, frame.edx),
+		174 => sys_sigaction(
+			frame.ebx,
+			frame.ecx as *const SigAction,
+			frame.edx as *mut SigAction,
+			frame.esi,
+		),
+		175 => sys_sigprocmask(frame.ebx, frame.ecx, frame.edx, frame.esi),
+		176 => sys_sigpending(frame.ebx, frame.ecx),
+		177 => sys_sigtimedwait(frame.ebx, frame.ecx, frame.edx, frame.esi),
+		178 => sys_sigqueueinfo(frame.ebx, frame.ecx, frame.edx),
+		179 => sys_sigsuspend(frame.ebx, frame.ecx),
 		183 => sys_getcwd(frame.ebx, frame.ecx),
+		186 => sys_sigaltstack(frame.ebx, frame.ecx, frame.esp),
 		199 => sys_getuid(),
 		200 => sys_getgid(),
 		212 => sys_chown(frame.ebx, frame.ecx, frame.edx),
 		213 => sys_setuid(frame.ebx),
 		214 => sys_setgid(frame.ebx),
+		218 => sys_mincore(frame.ebx, frame.ecx, frame.edx),
+		219 => sys_madvise(frame.ebx, frame.ecx, frame.edx as i32),
 		220 => sys_getdents(frame.ebx as isize, frame.ecx, frame.edx),
+		224 => sys_gettid(),
 		239 => sys_sendfile(frame.ebx as isize, frame.ecx as isize, frame.edx, frame.esi),
+		240 => sys_futex(
+			frame.ebx, frame.ecx, frame.edx, frame.esi, frame.edi, frame.ebp,
+		),
+		241 => sys_sched_setaffinity(frame.ebx, frame.ecx, frame.edx),
+		242 => sys_sched_getaffinity(frame.ebx, frame.ecx, frame.edx),
 		243 => sys_set_thread_area(frame.ebx),
-		// TODO: exit_group
-		252 => sys_exit(frame.ebx),
+		252 => sys_exit_group(frame.ebx),
 		// TODO: set_tid_address
 		258 => sys_set_tid_address(frame.ebx),
+		259 => sys_timer_create(frame.ebx, frame.ecx, frame.edx),
+		260 => sys_timer_settime(frame.ebx, frame.ecx, frame.edx, frame.esi),
+		261 => sys_timer_gettime(frame.ebx, frame.ecx),
+		262 => sys_timer_getoverrun(frame.ebx),
+		263 => sys_timer_delete(frame.ebx),
 		265 => sys_clock_gettime(frame.ebx, frame.ecx),
 		268 => sys_statfs64(frame.ebx, frame.ecx, frame.edx),
 		359 => sys_socket(frame.ebx as i32, frame.ecx as i32, frame.edx as i32),
@@ -660,8 +737,12 @@ fn __syscall(frame: &mut InterruptFrame, restart: &mut bool) -> Result<usize, Er
 		// tkill
 		238 => sys_kill(frame.ebx as isize, frame.ecx as isize),
 		320 => sys_utimensat(frame.ebx as isize, frame.ecx, frame.edx, frame.esi),
-		// TODO: pipe2
-		331 => sys_pipe(frame.ebx),
+		322 => sys_timerfd_create(frame.ebx, frame.ecx),
+		325 => sys_timerfd_settime(frame.ebx, frame.ecx, frame.edx, frame.esi),
+		326 => sys_timerfd_gettime(frame.ebx, frame.ecx),
+		330 => sys_dup3(frame.ebx, frame.ecx, frame.edx as i32),
+		331 => sys_pipe2(frame.ebx, frame.ecx as i32),
+		340 => sys_prlimit(frame.ebx, frame.ecx, frame.edx, frame.esi),
 		// statx
 		383 => sys_statx(
 			frame.ebx as isize,
@@ -672,6 +753,7 @@ fn __syscall(frame: &mut InterruptFrame, restart: &mut bool) -> Result<usize, Er
 		),
 		// clock_gettime64
 		403 => Err(Errno::ENOSYS),
+		436 => sys_close_range(frame.ebx, frame.ecx, frame.edx),
 		10000 => sys_draw_buffer(frame.ebx),
 		10001 => sys_get_key_state(frame.ebx),
 		10002 => sys_deteach_tty(),
@@ -10,18 +10,43 @@ use crate::{
 use super::errno::Errno;
 
 #[repr(u8)]
-enum ClockId {
+#[derive(Clone, Copy)]
+pub enum ClockId {
 	Realtime = 0,
 	Monotonic = 1,
 }
 
 impl ClockId {
-	fn from_usize(value: usize) -> Option<Self> {
+	pub fn from_usize(value: usize) -> Option<Self> {
 		match value {
 			x @ 0..=1 => Some(unsafe { transmute(x as u8) }),
 			_ => None,
 		}
 	}
+
+	pub fn now(&self) -> u64 {
+		match self {
+			ClockId::Realtime => get_timestamp_nano(),
+			ClockId::Monotonic => get_time_elapsed(),
+		}
+	}
+
+	/// convert time on this clock to realtime, which kernel timers run on.
+	pub fn to_realtime(&self, time: u64) -> u64 {
+		match self {
+			ClockId::Realtime => time,
+			ClockId::Monotonic => time + get_timestamp_nano().saturating_sub(get_time_elapsed()),
+		}
+	}
+
+	pub fn from_realtime(&self, time: u64) -> u64 {
+		match self {
+			ClockId::Realtime => time,
+			ClockId::Monotonic => {
+				time.saturating_sub(get_timestamp_nano().saturating_sub(get_time_elapsed()))
+			}
+		}
+	}
 }
 
 pub fn sys_clock_gettime(clk_id: usize, tp: usize) -> Result<usize, Errno> {
@@ -29,10 +54,7 @@ pub fn sys_clock_gettime(clk_id: usize, tp: usize) -> Result<usize, Errno> {
 	let clk_id = ClockId::from_usize(clk_id).ok_or(Errno::EINVAL)?;
 	let tp = verify_ptr_mut::<TimeSpec>(tp, current)?;
 
-	*tp = match clk_id {
-		ClockId::Realtime => TimeSpec::from(get_timestamp_nano()),
-		ClockId::Monotonic => TimeSpec::from(get_time_elapsed()),
-	};
+	*tp = TimeSpec::from(clk_id.now());
 
 	Ok(0)
 }
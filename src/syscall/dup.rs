@@ -1,19 +1,48 @@
-use crate::process::{fd_table::Fd, task::CURRENT};
+use crate::fs::vfs::CreationFlag;
+use crate::process::{
+	fd_table::{Fd, FdFlag},
+	task::CURRENT,
+};
 
 use super::errno::Errno;
 
 pub fn sys_dup2(fd1: usize, fd2: usize) -> Result<usize, Errno> {
 	let current = unsafe { CURRENT.get_ref() };
 
+	// nothing to do but check `fd1` is opened.
+	if fd1 == fd2 {
+		let fd = Fd::from(fd1).ok_or(Errno::EBADF)?;
+		let fd_table = current
+			.get_user_ext()
+			.expect("must be user process")
+			.lock_fd_table();
+
+		return fd_table.get_file(fd).map(|_| fd2).ok_or(Errno::EBADF);
+	}
+
+	do_dup3(fd1, fd2, FdFlag::empty())
+}
+
+/// same as `dup2`, but `O_CLOEXEC` can be given and `fd1` must differ from `fd2`.
+pub fn sys_dup3(fd1: usize, fd2: usize, flags: i32) -> Result<usize, Errno> {
+	if flags & !CreationFlag::O_CLOEXEC.bits() != 0 || fd1 == fd2 {
+		return Err(Errno::EINVAL);
+	}
+
+	do_dup3(fd1, fd2, CreationFlag::from_bits_truncate(flags).into())
+}
+
+fn do_dup3(fd1: usize, fd2: usize, flags: FdFlag) -> Result<usize, Errno> {
+	let current = unsafe { CURRENT.get_ref() };
+
 	let fd1 = Fd::from(fd1).ok_or(Errno::EBADF)?;
 	let fd2 = Fd::from(fd2).ok_or(Errno::EBADF)?;
 
-	let mut fd_table = current
+	let old_handle = current
 		.get_user_ext()
 		.expect("must be user process")
-		.lock_fd_table();
-
-	let old_handle = fd_table.dup2(fd1, fd2.clone())?;
+		.lock_fd_table()
+		.dup2(fd1, fd2.clone(), flags)?;
 
 	if let Some(old) = old_handle {
 		old.close()?;
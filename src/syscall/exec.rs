@@ -5,23 +5,28 @@ use alloc::borrow::ToOwned;
 use alloc::sync::Arc;
 
 use crate::elf::Elf;
+use crate::fs::delete_fd_node;
 use crate::fs::path::Path;
-use crate::fs::vfs::{lookup_entry_follow, AccessFlag, Entry, IOFlag, Permission};
+use crate::fs::vfs::{
+	lookup_entry_follow, AccessFlag, Entry, IOFlag, MountFlag, Permission, VfsFileEntry,
+};
 use crate::interrupt::InterruptFrame;
 use crate::mm::user::memory::Memory;
 use crate::mm::user::string_vec::StringVec;
 use crate::mm::user::verify::verify_path;
+use crate::process::cred::Credentials;
+use crate::process::exit::kill_other_threads;
+use crate::process::ptrace;
 use crate::process::task::{Task, CURRENT};
 use crate::ptr::VirtPageBox;
 use crate::syscall::errno::Errno;
 use crate::syscall::SyscallSnapshot;
 use crate::trace_feature;
+use crate::x86::fpu::FpuState;
 
 const PATH_MAX: usize = 128;
 
-pub fn read_user_binary(path: Path, task: &Arc<Task>) -> Result<VirtPageBox, Errno> {
-	let entry = lookup_entry_follow(&path, task).and_then(|x| x.downcast_file())?;
-
+fn read_entry(entry: &Arc<VfsFileEntry>, task: &Arc<Task>) -> Result<VirtPageBox, Errno> {
 	entry.access(Permission::ANY_EXECUTE, task)?;
 
 	let stat = entry.statx()?;
@@ -34,6 +39,46 @@ pub fn read_user_binary(path: Path, task: &Arc<Task>) -> Result<VirtPageBox, Err
 	Ok(buffer)
 }
 
+pub fn read_user_binary(path: Path, task: &Arc<Task>) -> Result<VirtPageBox, Errno> {
+	let entry = lookup_entry_follow(&path, task).and_then(|x| x.downcast_file())?;
+
+	read_entry(&entry, task)
+}
+
+/// credentials of the program in `entry`.
+///
+/// set-user-ID and set-group-ID bits are ignored on `nosuid` mounts and
+/// for traced tasks.
+fn exec_credentials(entry: &Arc<VfsFileEntry>, task: &Arc<Task>) -> Result<Credentials, Errno> {
+	let stat = entry.statx()?;
+	let perm = stat.get_perm();
+
+	let nosuid = entry
+		.parent_weak()
+		.upgrade()
+		.map_or(true, |dir| dir.mount_flags().contains(MountFlag::MS_NOSUID));
+	let ignored = nosuid || ptrace::is_traced(task);
+
+	// set-group-ID without group execute bit is not a set-group-ID program.
+	let owner = perm.contains(Permission::S_ISUID).then_some(stat.uid);
+	let group = perm
+		.contains(Permission::S_ISGID | Permission::S_IXGRP)
+		.then_some(stat.gid);
+
+	let mut cred = task.get_cred();
+	match ignored {
+		true => cred.exec(None, None),
+		false => cred.exec(owner, group),
+	}
+
+	Ok(cred)
+}
+
+/// variables for the dynamic loader, which a secure program must not trust.
+fn is_loader_env(var: &[u8]) -> bool {
+	var.starts_with(b"LD_")
+}
+
 /// execute new user binary
 /// do not call from kernel thread!!
 pub fn sys_execve(
@@ -55,20 +100,42 @@ pub fn sys_execve(
 		path
 	);
 
-	let raw_bin = read_user_binary(path, current)?;
+	let entry = lookup_entry_follow(&path, current).and_then(|x| x.downcast_file())?;
+	let raw_bin = read_entry(&entry, current)?;
 	let elf = Elf::new(raw_bin.as_slice()).map_err(|_| Errno::ENOEXEC)?;
 
+	let cred = exec_credentials(&entry, current)?;
+	let secure = cred.is_secure_exec();
+
 	let argv = StringVec::new(argv, current)?;
-	let envp = StringVec::new(envp, current)?;
+	let mut envp = StringVec::new(envp, current)?;
+	if secure {
+		envp.retain(|var| !is_loader_env(var));
+	}
 
 	let new_cmd = CStr::from_bytes_until_nul(&argv.data)
 		.map(|s| s.to_owned().into_bytes())
 		.unwrap_or_default();
 
-	let new_memory = Memory::from_elf(elf, argv, envp)?;
+	let new_memory = Memory::from_elf(elf, argv, envp, &cred)?;
 
-	let signal = &current.user_ext_ok_or(Errno::EPERM)?.signal;
-	signal.do_for_exec();
+	// memory is shared with other threads, they must be gone before it is replaced.
+	kill_other_threads()?;
+
+	let user_ext = current.user_ext_ok_or(Errno::EPERM)?;
+	user_ext.signal.do_for_exec(secure);
+	// memory of the program may hold secrets of the new credentials.
+	user_ext.set_dumpable(!secure);
+	user_ext.get_thread_group().lock_posix_timers().clear();
+
+	let closed = user_ext.lock_fd_table().take_cloexec();
+	for (fd, handle) in closed {
+		let _ = handle.close();
+		let _ = delete_fd_node(current.get_pid(), fd);
+	}
+
+	// new program starts with clean FPU state.
+	FpuState::new().restore();
 
 	new_memory.pick_up();
 
@@ -87,6 +154,9 @@ pub fn sys_execve(
 	mem::drop(mem::replace(&mut *memory, new_memory));
 
 	*current.lock_cmd() = new_cmd;
+	*current.lock_cred() = cred;
+
+	ptrace::exec_notify();
 
 	Ok(0)
 }
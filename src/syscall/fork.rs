@@ -1,12 +1,43 @@
+use bitflags::bitflags;
+
 use crate::{
-	interrupt::InterruptFrame, process::task::CURRENT, scheduler::schedule_last,
+	interrupt::InterruptFrame,
+	mm::user::verify::{verify_ptr, verify_ptr_mut},
+	process::{
+		rlimit::check_nproc, set_thread_area::tls_index, signal::sig_num::SigNum, task::CURRENT,
+	},
+	scheduler::schedule_last,
 	syscall::errno::Errno,
+	x86::UserDesc,
 };
 
+bitflags! {
+	#[repr(transparent)]
+	#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+	pub struct CloneFlag: u32 {
+		const Vm = 0x100;
+		const Fs = 0x200;
+		const Files = 0x400;
+		const SigHand = 0x800;
+		const Vfork = 0x4000;
+		const Thread = 0x10000;
+		const SysVSem = 0x40000;
+		const SetTls = 0x80000;
+		const ParentSetTid = 0x100000;
+		const ChildClearTid = 0x200000;
+		const Detached = 0x400000;
+	}
+}
+
+/// low byte of clone flags: signal sent to parent on exit.
+const CSIGNAL: u32 = 0xff;
+
 pub fn sys_fork(frame: *const InterruptFrame) -> Result<usize, Errno> {
 	let current = unsafe { CURRENT.get_mut() };
 
-	if let Ok(forked) = current.clone_for_fork(frame) {
+	check_nproc(current)?;
+
+	if let Ok(forked) = current.clone_for_fork(frame, CloneFlag::empty(), Some(SigNum::CHLD)) {
 		let pid = forked.get_pid().as_raw();
 
 		schedule_last(forked);
@@ -16,3 +47,83 @@ pub fn sys_fork(frame: *const InterruptFrame) -> Result<usize, Errno> {
 		Err(Errno::ENOMEM)
 	}
 }
+
+/// # Arguments
+///
+/// - `stack`: stack pointer of the child. (same as parent's if 0)
+/// - `tls`: pointer to `UserDesc` for `CLONE_SETTLS`
+pub fn sys_clone(
+	frame: &InterruptFrame,
+	flags: u32,
+	stack: usize,
+	parent_tid: usize,
+	tls: usize,
+	child_tid: usize,
+) -> Result<usize, Errno> {
+	let current = unsafe { CURRENT.get_mut() };
+
+	let exit_signal = match (flags & CSIGNAL) as usize {
+		0 => None,
+		num => Some(SigNum::from_usize(num).ok_or(Errno::EINVAL)?),
+	};
+	let flags = CloneFlag::from_bits(flags & !CSIGNAL).ok_or(Errno::EINVAL)?;
+
+	if flags.contains(CloneFlag::Thread) && !flags.contains(CloneFlag::SigHand)
+		|| flags.contains(CloneFlag::SigHand) && !flags.contains(CloneFlag::Vm)
+	{
+		return Err(Errno::EINVAL);
+	}
+
+	// exec replaces memory in place, which must not be seen by other processes.
+	// so memory is shared only inside a thread group.
+	// vfork child gets a copy of memory instead, as `vfork` does.
+	let flags = match flags.contains(CloneFlag::Vm) && !flags.contains(CloneFlag::Thread) {
+		true if flags.contains(CloneFlag::Vfork) => flags - CloneFlag::Vm,
+		true => return Err(Errno::EINVAL),
+		false => flags,
+	};
+
+	let tls = match flags.contains(CloneFlag::SetTls) {
+		true => {
+			let user_desc = verify_ptr::<UserDesc>(tls, current)?;
+			let idx = tls_index(user_desc.entry_number).ok_or(Errno::EINVAL)?;
+
+			Some((idx, user_desc.parse_into_system_desc()?))
+		}
+		false => None,
+	};
+
+	let parent_tid = match flags.contains(CloneFlag::ParentSetTid) {
+		true => Some(verify_ptr_mut::<u32>(parent_tid, current)?),
+		false => None,
+	};
+
+	check_nproc(current)?;
+
+	let mut child_frame = frame.clone();
+	if stack != 0 {
+		child_frame.esp = stack;
+	}
+
+	let child = current
+		.clone_for_fork(&child_frame, flags, exit_signal)
+		.map_err(|_| Errno::ENOMEM)?;
+	let child_ext = child.get_user_ext().expect("user task");
+	let tid = child.get_pid().as_raw();
+
+	if let Some((idx, desc)) = tls {
+		child_ext.lock_tls()[idx] = desc;
+	}
+
+	if let Some(parent_tid) = parent_tid {
+		*parent_tid = tid as u32;
+	}
+
+	if flags.contains(CloneFlag::ChildClearTid) {
+		child_ext.set_clear_child_tid(child_tid);
+	}
+
+	schedule_last(child);
+
+	Ok(tid)
+}
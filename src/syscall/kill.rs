@@ -1,11 +1,12 @@
 use alloc::sync::Arc;
 
+use crate::mm::user::verify::verify_ptr;
 use crate::process::process_tree::PROCESS_TREE;
 use crate::process::relation::pgroup::ProcessGroup;
 use crate::process::relation::{Pgid, Pid};
-use crate::process::signal::send_signal_to;
+use crate::process::signal::send_signal_to_process;
 use crate::process::signal::sig_code::SigCode;
-use crate::process::signal::sig_info::SigInfo;
+use crate::process::signal::sig_info::{SigInfo, UserSigInfo};
 use crate::process::signal::sig_num::SigNum;
 use crate::process::task::{Task, CURRENT};
 use crate::syscall::errno::Errno;
@@ -17,9 +18,10 @@ pub fn sys_kill(pid: isize, sig: isize) -> Result<usize, Errno> {
 
 	let siginfo = SigInfo {
 		num,
-		pid: current.get_pid().as_raw(),
+		pid: current.get_tgid().as_raw(),
 		uid: current.get_uid(),
 		code: SigCode::SI_USER,
+		value: 0,
 	};
 
 	match pid {
@@ -32,6 +34,29 @@ pub fn sys_kill(pid: isize, sig: isize) -> Result<usize, Errno> {
 	.map(|_| 0)
 }
 
+/// send a signal with data. (`sigqueue`)
+pub fn sys_sigqueueinfo(tgid: usize, sig: usize, info: usize) -> Result<usize, Errno> {
+	let current = unsafe { CURRENT.get_ref() };
+
+	let num = SigNum::from_usize(sig).ok_or(Errno::EINVAL)?;
+	let info = verify_ptr::<UserSigInfo>(info, current)?;
+
+	// only the kernel sends non-negative codes to other processes.
+	if info.code >= 0 && tgid != current.get_tgid().as_raw() {
+		return Err(Errno::EPERM);
+	}
+
+	let siginfo = SigInfo {
+		num,
+		pid: current.get_tgid().as_raw(),
+		uid: current.get_uid(),
+		code: SigCode::SI_QUEUE,
+		value: info.value,
+	};
+
+	kill_pid(Pid::from_raw(tgid), &siginfo).map(|_| 0)
+}
+
 fn kill_pid(pid: Pid, siginfo: &SigInfo) -> Result<(), Errno> {
 	let ptree = PROCESS_TREE.lock();
 	let task = ptree.get(&pid).ok_or_else(|| Errno::ESRCH)?;
@@ -58,11 +83,12 @@ fn kill_process(target: &Arc<Task>, siginfo: &SigInfo) -> Result<(), Errno> {
 		return Err(Errno::EPERM);
 	}
 
-	if siginfo.uid != 0 && siginfo.uid != target.get_uid() {
+	let cred = unsafe { CURRENT.get_ref() }.get_cred();
+	if !cred.can_signal(&target.get_cred()) {
 		return Err(Errno::EPERM);
 	}
 
-	send_signal_to(target, siginfo)
+	send_signal_to_process(target, siginfo)
 }
 
 fn kill_pgroup(pgroup: &Arc<ProcessGroup>, siginfo: &SigInfo) -> Result<(), Errno> {
@@ -78,8 +104,9 @@ fn kill_pgroup(pgroup: &Arc<ProcessGroup>, siginfo: &SigInfo) -> Result<(), Errn
 }
 
 fn kill_everyone(siginfo: &SigInfo) -> Result<(), Errno> {
+	// process tree has every thread. the signal is sent once per process.
 	for (_, task) in PROCESS_TREE.lock().members() {
-		if task.get_pid().as_raw() != siginfo.pid {
+		if task.get_pid() == task.get_tgid() && task.get_pid().as_raw() != siginfo.pid {
 			let _ = kill_process(task, siginfo);
 		}
 	}
@@ -82,6 +82,12 @@ pub fn sys_setsid() -> Result<usize, Errno> {
 pub fn sys_getpid() -> Result<usize, Errno> {
 	let current = unsafe { CURRENT.get_mut() };
 
+	Ok(current.get_tgid().as_raw())
+}
+
+pub fn sys_gettid() -> Result<usize, Errno> {
+	let current = unsafe { CURRENT.get_mut() };
+
 	Ok(current.get_pid().as_raw())
 }
 
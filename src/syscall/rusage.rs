@@ -0,0 +1,81 @@
+use crate::{
+	config::TIMER_FREQUENCY_HZ,
+	driver::hpet::get_time_elapsed,
+	mm::user::verify::verify_ptr_mut,
+	process::{
+		rusage::{RUsage, Usage},
+		task::CURRENT,
+	},
+};
+
+use super::errno::Errno;
+
+/// unit of `clock_t`. (`sysconf(_SC_CLK_TCK)` of libc)
+const USER_HZ: usize = 100;
+
+fn ticks_to_clock(ticks: usize) -> usize {
+	(ticks as u64 * USER_HZ as u64 / TIMER_FREQUENCY_HZ as u64) as usize
+}
+
+#[repr(C)]
+struct Tms {
+	utime: usize,
+	stime: usize,
+	cutime: usize,
+	cstime: usize,
+}
+
+enum Who {
+	SelfProcess,
+	Children,
+	Thread,
+}
+
+impl TryFrom<isize> for Who {
+	type Error = Errno;
+
+	fn try_from(value: isize) -> Result<Self, Self::Error> {
+		match value {
+			0 => Ok(Self::SelfProcess),
+			-1 => Ok(Self::Children),
+			1 => Ok(Self::Thread),
+			_ => Err(Errno::EINVAL),
+		}
+	}
+}
+
+/// returns elapsed time since boot in `clock_t`.
+pub fn sys_times(buf: usize) -> Result<usize, Errno> {
+	let current = unsafe { CURRENT.get_ref() };
+	let group = current.user_ext_ok_or(Errno::EPERM)?.get_thread_group();
+
+	if buf != 0 {
+		let buf = verify_ptr_mut::<Tms>(buf, current)?;
+		let usage = group.get_usage();
+		let children = group.get_children_usage();
+
+		*buf = Tms {
+			utime: ticks_to_clock(usage.utime),
+			stime: ticks_to_clock(usage.stime),
+			cutime: ticks_to_clock(children.utime),
+			cstime: ticks_to_clock(children.stime),
+		};
+	}
+
+	Ok((get_time_elapsed() / (1_000_000_000 / USER_HZ as u64)) as usize)
+}
+
+pub fn sys_getrusage(who: isize, usage: usize) -> Result<usize, Errno> {
+	let current = unsafe { CURRENT.get_ref() };
+	let group = current.user_ext_ok_or(Errno::EPERM)?.get_thread_group();
+
+	let result: Usage = match Who::try_from(who)? {
+		Who::SelfProcess => group.get_usage(),
+		Who::Children => group.get_children_usage(),
+		Who::Thread => *current.lock_usage(),
+	};
+
+	*verify_ptr_mut::<RUsage>(usage, current)? = result.into();
+
+	Ok(0)
+}
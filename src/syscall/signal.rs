@@ -1,16 +1,22 @@
 use core::mem::{self, size_of};
-use core::ptr::copy_nonoverlapping;
+use core::ptr::{copy_nonoverlapping, read_unaligned};
 
+use crate::driver::hpet::get_timestamp_nano;
+use crate::fs::vfs::TimeSpec;
 use crate::interrupt::InterruptFrame;
-use crate::mm::user::verify::{verify_ptr, verify_ptr_mut};
+use crate::mm::user::verify::{verify_ptr, verify_ptr_mut, verify_region};
+use crate::mm::user::vma::AreaFlag;
+use crate::process::exit::exit_with_signal;
 use crate::process::signal::poll_signal_queue;
-use crate::process::signal::sig_ctx::SigCtx;
+use crate::process::signal::sig_ctx::{SigFrame, SigStack, MINSIGSTKSZ, SS_DISABLE};
 use crate::process::signal::sig_flag::SigFlag;
 use crate::process::signal::sig_handler::{SigAction, SigHandler};
-use crate::process::signal::sig_info::SigInfo;
+use crate::process::signal::sig_info::UserSigInfo;
 use crate::process::signal::sig_mask::SigMask;
 use crate::process::signal::sig_num::SigNum;
-use crate::scheduler::sleep::{sleep_and_yield, Sleep};
+use crate::scheduler::nano_sleep::ALARM;
+use crate::scheduler::sleep::{sleep_and_yield, sleep_and_yield_lock, Sleep};
+use crate::x86::fpu::FpuState;
 use crate::{process::task::CURRENT, syscall::errno::Errno};
 
 use super::*;
@@ -48,8 +54,9 @@ pub fn sys_sigaction(
 	num: usize,
 	act: *const SigAction,
 	old: *mut SigAction,
-	_ssize: usize,
+	sigsetsize: usize,
 ) -> Result<usize, Errno> {
+	check_sigsetsize(sigsetsize)?;
 	validate_user_addr(act as usize)?;
 	validate_user_addr(old as usize)?;
 	let num = validate_sig_num(num)?;
@@ -84,33 +91,91 @@ pub fn sys_sigaction(
 }
 
 pub fn sys_sigreturn(frame: &InterruptFrame, restart: &mut bool) -> Result<usize, Errno> {
-	let sig_ctx = frame.ebx as *const SigCtx;
-	let sig_info = frame.ecx as *const SigInfo;
-	unsafe {
-		// pr_debug!("sigreturn: {:p}, {}", sig_ctx, (*sig_ctx).intr);
-		let current = CURRENT.get_mut();
-		let signal = current.user_ext_ok_or(Errno::ENOENT)?.signal.as_ref();
-
-		// restore the sig mask of the task.signal.
-		signal.overwrite_mask((*sig_ctx).mask);
-
-		// go to the next signal handler if the sig queue is not empty.
-		signal.do_signal_repeat(frame);
-
-		// else
-		let syscall_ret = (*sig_ctx).syscall_ret;
-		let flag = signal.get_handler(&(*sig_info).num).get_flag();
-		*restart = is_syscall_restart((*sig_ctx).intr_frame.eax, syscall_ret, flag);
-
-		restore_interrupt_frame(&(*sig_ctx).intr_frame);
-		restore_syscall_return((*sig_ctx).syscall_ret)
+	let current = unsafe { CURRENT.get_ref() };
+	let signal = current.user_ext_ok_or(Errno::ENOENT)?.signal.as_ref();
+
+	// `ret` of the frame is popped by returning from the handler.
+	let sig_frame = frame.esp - size_of::<usize>();
+	if verify_region(
+		sig_frame,
+		size_of::<SigFrame>(),
+		current,
+		AreaFlag::Readable,
+	)
+	.is_err()
+	{
+		exit_with_signal(SigNum::SEGV);
+	}
+
+	// handler may have changed it. (e.g. registers in `uc.mcontext`)
+	let sig_frame = unsafe { read_unaligned(sig_frame as *const SigFrame) };
+	let uc = &sig_frame.uc;
+
+	// restore the sig mask of the task.signal.
+	signal.overwrite_mask(uc.sigmask - SigMask::UNBLOCKABLE);
+
+	let syscall_ret = sig_frame.syscall_ret;
+	let flag = SigNum::from_usize(sig_frame.num)
+		.map(|num| signal.get_handler(&num).get_flag())
+		.unwrap_or(SigFlag::empty());
+	*restart = is_syscall_restart(uc.mcontext.eax, syscall_ret, flag);
+
+	let fpstate = uc.mcontext.fpstate;
+	if verify_region(fpstate, size_of::<FpuState>(), current, AreaFlag::Readable).is_ok() {
+		// fpu state on user stack could be misaligned or corrupted by user.
+		let mut fpstate = unsafe { read_unaligned(fpstate as *const FpuState) };
+		fpstate.sanitize();
+		fpstate.restore();
 	}
+
+	let mut intr_frame = frame.clone();
+	uc.mcontext.restore(&mut intr_frame);
+	intr_frame.handler = sig_frame.handler;
+
+	unsafe { restore_interrupt_frame(&intr_frame) };
+	restore_syscall_return(syscall_ret)
 }
 
-pub fn sys_sigsuspend(new_mask: usize) -> Result<usize, Errno> {
+/// set or get alternate stack for handlers with `SA_ONSTACK`.
+pub fn sys_sigaltstack(ss: usize, old_ss: usize, user_esp: usize) -> Result<usize, Errno> {
 	let current = unsafe { CURRENT.get_ref() };
 
-	let new_mask = *verify_ptr::<[SigMask; 2]>(new_mask, current)?;
+	let new = match ss {
+		0 => None,
+		_ => Some(*verify_ptr::<SigStack>(ss, current)?),
+	};
+
+	let signal = &current.get_user_ext().expect("must be user process").signal;
+	let mut altstack = signal.lock_altstack();
+
+	if old_ss != 0 {
+		*verify_ptr_mut::<SigStack>(old_ss, current)? = altstack.report(user_esp);
+	}
+
+	let Some(new) = new else {
+		return Ok(0);
+	};
+
+	if altstack.contains(user_esp) {
+		return Err(Errno::EPERM);
+	}
+
+	*altstack = match new.flags {
+		SS_DISABLE => SigStack::disabled(),
+		0 if new.size < MINSIGSTKSZ => return Err(Errno::ENOMEM),
+		0 => new,
+		_ => return Err(Errno::EINVAL),
+	};
+
+	Ok(0)
+}
+
+pub fn sys_sigsuspend(new_mask: usize, sigsetsize: usize) -> Result<usize, Errno> {
+	let current = unsafe { CURRENT.get_ref() };
+
+	check_sigsetsize(sigsetsize)?;
+
+	let new_mask = *verify_ptr::<SigMask>(new_mask, current)? - SigMask::UNBLOCKABLE;
 
 	let old_mask = {
 		let mut curr_mask = current
@@ -119,7 +184,7 @@ pub fn sys_sigsuspend(new_mask: usize) -> Result<usize, Errno> {
 			.signal
 			.lock_mask();
 
-		mem::replace(&mut *curr_mask, new_mask[1])
+		mem::replace(&mut *curr_mask, new_mask)
 	};
 
 	loop {
@@ -155,12 +220,23 @@ impl TryFrom<usize> for SigProcMaskHow {
 	}
 }
 
-pub fn sys_sigprocmask(how: usize, set: usize, oldset: usize) -> Result<usize, Errno> {
+pub fn sys_sigprocmask(
+	how: usize,
+	set: usize,
+	oldset: usize,
+	sigsetsize: usize,
+) -> Result<usize, Errno> {
 	let current = unsafe { CURRENT.get_ref() };
 
+	check_sigsetsize(sigsetsize)?;
+
 	let how = SigProcMaskHow::try_from(how)?;
 
-	let set = verify_ptr::<[SigMask; 2]>(set, current)?;
+	// null `set` only queries the mask.
+	let set = match set {
+		0 => None,
+		_ => Some(*verify_ptr::<SigMask>(set, current)?),
+	};
 
 	let mut mask = current
 		.get_user_ext()
@@ -171,29 +247,105 @@ pub fn sys_sigprocmask(how: usize, set: usize, oldset: usize) -> Result<usize, E
 	let old_mask = *mask;
 
 	if oldset != 0 {
-		let oldset = verify_ptr_mut::<[SigMask; 2]>(oldset, current)?;
-
-		oldset[1] = old_mask;
+		*verify_ptr_mut::<SigMask>(oldset, current)? = old_mask;
 	}
 
+	let Some(set) = set else {
+		return Ok(0);
+	};
+
 	use SigProcMaskHow::*;
 	let new_mask = match how {
-		SigBlock => old_mask | set[1],
-		SigUnblock => old_mask - set[1],
-		SigSetMask => set[1],
+		SigBlock => old_mask | set,
+		SigUnblock => old_mask - set,
+		SigSetMask => set,
 	};
 
-	*mask = new_mask;
+	*mask = new_mask - SigMask::UNBLOCKABLE;
+
+	Ok(0)
+}
+
+/// blocked signals that are pending.
+pub fn sys_sigpending(set: usize, sigsetsize: usize) -> Result<usize, Errno> {
+	let current = unsafe { CURRENT.get_ref() };
+
+	check_sigsetsize(sigsetsize)?;
+
+	let set = verify_ptr_mut::<SigMask>(set, current)?;
+	let signal = &current.get_user_ext().expect("must be user process").signal;
+
+	*set = signal.pending() & *signal.lock_mask();
 
 	Ok(0)
 }
 
+/// take a pending signal in `set` without running its handler.
+pub fn sys_sigtimedwait(
+	set: usize,
+	info: usize,
+	timeout: usize,
+	sigsetsize: usize,
+) -> Result<usize, Errno> {
+	let current = unsafe { CURRENT.get_ref() };
+
+	check_sigsetsize(sigsetsize)?;
+
+	let set = *verify_ptr::<SigMask>(set, current)? - SigMask::UNBLOCKABLE;
+	let info = match info {
+		0 => None,
+		_ => Some(verify_ptr_mut::<UserSigInfo>(info, current)?),
+	};
+	let deadline = match timeout {
+		0 => None,
+		_ => {
+			let timeout = verify_ptr::<TimeSpec>(timeout, current)?;
+			if !timeout.is_valid() {
+				return Err(Errno::EINVAL);
+			}
+			Some(get_timestamp_nano() + timeout.nano())
+		}
+	};
+
+	let signal = &current.get_user_ext().expect("must be user process").signal;
+
+	loop {
+		// interrupts stay disabled until sleeping, so no signal is missed.
+		let mut alarm = ALARM.lock();
+
+		if let Some(sig_info) = signal.take_signal(set) {
+			drop(alarm);
+			if let Some(info) = info {
+				*info = UserSigInfo::from(&sig_info);
+			}
+			return Ok(sig_info.num as usize);
+		}
+
+		unsafe { poll_signal_queue() }?;
+
+		match deadline {
+			Some(target) if target <= get_timestamp_nano() => return Err(Errno::EAGAIN),
+			Some(target) => alarm.register(target),
+			None => {}
+		}
+
+		sleep_and_yield_lock(Sleep::Light, alarm);
+	}
+}
+
 unsafe fn restore_interrupt_frame(backup_frame: &InterruptFrame /* user stack */) {
 	let current = CURRENT.get_mut();
 	let frame = (current.kstack_base() - size_of::<InterruptFrame>()) as *mut InterruptFrame;
 	copy_nonoverlapping(backup_frame as *const InterruptFrame, frame, 1);
 }
 
+fn check_sigsetsize(sigsetsize: usize) -> Result<(), Errno> {
+	match sigsetsize == size_of::<SigMask>() {
+		true => Ok(()),
+		false => Err(Errno::EINVAL),
+	}
+}
+
 fn validate_sig_num(num: usize) -> Result<SigNum, Errno> {
 	let num = SigNum::from_usize(num).ok_or(Errno::EINVAL)?;
 	if let SigNum::KILL | SigNum::STOP = num {
@@ -203,8 +355,6 @@ fn validate_sig_num(num: usize) -> Result<SigNum, Errno> {
 }
 
 fn validate_user_addr(addr: usize) -> Result<(), Errno> {
-	use crate::mm::user::vma::AreaFlag;
-
 	if addr == SIG_DFL || addr == SIG_IGN {
 		return Ok(());
 	}
@@ -1,11 +1,15 @@
-use core::mem::size_of;
+use alloc::sync::Arc;
 
 use crate::{
-	mm::user::vma::AreaFlag,
+	mm::user::verify::verify_ptr_mut,
 	process::{
+		exit::ExitStatus,
+		process_tree::PROCESS_TREE,
+		ptrace,
 		relation::{Pgid, Pid},
+		rusage::RUsage,
 		signal::poll_signal_queue,
-		task::CURRENT,
+		task::{Task, CURRENT},
 	},
 	scheduler::context::yield_now,
 	syscall::errno::Errno,
@@ -20,22 +24,61 @@ pub enum Who {
 
 mod wait_option {
 	pub const WNOHANG: usize = 1 << 0;
-	pub const WUNTRACED: usize = 1 << 1; // not implemented.
-	pub const IMPLEMENTED_MASK: usize = WNOHANG + WUNTRACED; // TODO implement untraced
+	pub const WUNTRACED: usize = 1 << 1;
+	pub const IMPLEMENTED_MASK: usize = WNOHANG + WUNTRACED;
 }
 
-pub fn sys_waitpid(cpid: isize, stat_loc: *mut isize, option: usize) -> Result<usize, Errno> {
-	let current = unsafe { CURRENT.get_mut() };
+/// a child stopped by a signal, not yet reported.
+fn wait_stopped_child(current: &Arc<Task>, who: Who) -> Option<(Pid, ExitStatus)> {
+	let children = current.get_user_ext()?.lock_relation().children();
+
+	for pid in children {
+		let Some(child) = PROCESS_TREE.get_task(pid) else {
+			continue;
+		};
+
+		let matched = match who {
+			Who::Any => true,
+			Who::Pid(x) => x == pid,
+			Who::Pgid(x) => child.get_pgid() == x,
+		};
 
-	if !current
-		.get_user_ext()
-		.expect("must be user process")
-		.lock_memory()
-		.query_flags_range(stat_loc as usize, size_of::<isize>(), AreaFlag::Writable)
-	{
-		return Err(Errno::EFAULT);
+		let group = child.get_user_ext().map(|ext| ext.get_thread_group());
+		if let Some(status) = group.filter(|_| matched).and_then(|g| g.take_stop_status()) {
+			return Some((pid, status));
+		}
 	}
 
+	None
+}
+
+pub fn sys_waitpid(cpid: isize, stat_loc: usize, option: usize) -> Result<usize, Errno> {
+	sys_wait4(cpid, stat_loc, option, 0)
+}
+
+/// store `value` at user pointer `ptr`, unless it is null.
+///
+/// verified right before the write, since the memory could have been
+/// changed by other threads while waiting.
+fn put_user<T>(ptr: usize, value: T, current: &Arc<Task>) -> Result<(), Errno> {
+	if ptr != 0 {
+		*verify_ptr_mut::<T>(ptr, current)? = value;
+	}
+
+	Ok(())
+}
+
+/// `waitpid` that also reports resource usage of the reaped child.
+///
+/// `stat_loc` and `rusage` can be null. (e.g. `wait(NULL)`)
+pub fn sys_wait4(
+	cpid: isize,
+	stat_loc: usize,
+	option: usize,
+	rusage: usize,
+) -> Result<usize, Errno> {
+	let current = unsafe { CURRENT.get_mut() };
+
 	// unknown option
 	if (option & wait_option::IMPLEMENTED_MASK) != option {
 		return Err(Errno::EINVAL);
@@ -50,11 +93,22 @@ pub fn sys_waitpid(cpid: isize, stat_loc: *mut isize, option: usize) -> Result<u
 	};
 
 	let non_block = (option & wait_option::WNOHANG) != 0;
+	let untraced = (option & wait_option::WUNTRACED) != 0;
 
 	let ret = loop {
+		// stops are reported before exits, as they don't reap the child.
+		let stopped = ptrace::wait_stopped(who)
+			.or_else(|| untraced.then(|| wait_stopped_child(current, who)).flatten());
+
+		if let Some((pid, status)) = stopped {
+			put_user(stat_loc, status.as_raw() as isize, current)?;
+			break Ok(pid.as_raw());
+		}
+
 		let result = current.waitpid(who);
 		if let Ok(z) = result.as_ref() {
-			unsafe { stat_loc.write(z.exit_status.as_raw() as isize) };
+			put_user(stat_loc, z.exit_status.as_raw() as isize, current)?;
+			put_user::<RUsage>(rusage, z.usage.into(), current)?;
 		}
 
 		let ret = result.map(|z| z.pid.as_raw());
@@ -6,6 +6,7 @@ pub mod bitrange;
 pub mod endian;
 pub mod lazy_constant;
 pub mod lcg;
+pub mod random;
 
 pub struct LazyInit<T> {
 	value: Option<T>,
@@ -0,0 +1,140 @@
+//! kernel random number generator.
+//!
+//! unlike `LCG`, outputs are not reproducible. state is seeded from `rdrand`
+//! (if cpu supports it) and time stamp counter, and time stamp counter is mixed
+//! into the state on every request.
+
+use core::arch::asm;
+
+use crate::sync::Locked;
+
+use super::arch::cpuid::CPUID;
+
+static RNG: Locked<Xoshiro128> = Locked::new(Xoshiro128::new());
+
+/// xoshiro128** generator.
+struct Xoshiro128 {
+	state: [u32; 4],
+	seeded: bool,
+}
+
+impl Xoshiro128 {
+	const fn new() -> Self {
+		Self {
+			state: [0; 4],
+			seeded: false,
+		}
+	}
+
+	fn seed(&mut self) {
+		let mut seed = rdtsc() ^ rdrand().unwrap_or_default();
+
+		for s in self.state.iter_mut() {
+			*s = splitmix(&mut seed);
+		}
+
+		self.seeded = true;
+	}
+
+	fn mix(&mut self, entropy: u64) {
+		self.state[0] ^= entropy as u32;
+		self.state[1] ^= (entropy >> 32) as u32;
+
+		// all zero state never escapes.
+		if self.state == [0; 4] {
+			self.state[3] = 1;
+		}
+	}
+
+	fn next(&mut self) -> u32 {
+		let s = &mut self.state;
+		let result = s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+		let t = s[1] << 9;
+
+		s[2] ^= s[0];
+		s[3] ^= s[1];
+		s[1] ^= s[2];
+		s[0] ^= s[3];
+		s[2] ^= t;
+		s[3] = s[3].rotate_left(11);
+
+		result
+	}
+}
+
+fn splitmix(x: &mut u64) -> u32 {
+	*x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+
+	let mut z = *x;
+	z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+	z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+
+	(z ^ (z >> 31)) as u32
+}
+
+fn rdtsc() -> u64 {
+	let (high, low): (u32, u32);
+
+	unsafe { asm!("rdtsc", out("eax") low, out("edx") high) };
+
+	(high as u64) << 32 | low as u64
+}
+
+fn rdrand() -> Option<u64> {
+	const CPUID_ECX_RDRAND: usize = 1 << 30;
+
+	if CPUID::run(1, 0).ecx & CPUID_ECX_RDRAND == 0 {
+		return None;
+	}
+
+	let (high, low, ok): (u32, u32, u8);
+	unsafe {
+		asm!(
+			"rdrand {low}",
+			"rdrand {high}",
+			"setc {ok}",
+			low = out(reg) low,
+			high = out(reg) high,
+			ok = out(reg_byte) ok,
+		)
+	};
+
+	(ok != 0).then_some((high as u64) << 32 | low as u64)
+}
+
+pub fn get_random_u32() -> u32 {
+	let mut rng = RNG.lock();
+
+	if !rng.seeded {
+		rng.seed();
+	}
+
+	rng.mix(rdtsc());
+	rng.next()
+}
+
+/// random number in [0, bound).
+pub fn get_random_below(bound: usize) -> usize {
+	debug_assert!(bound > 0, "get_random_below: empty range");
+
+	get_random_u32() as usize % bound
+}
+
+mod test {
+	use super::*;
+	use kfs_macro::ktest;
+
+	#[ktest(random)]
+	fn not_constant() {
+		let first = get_random_u32();
+
+		assert!((0..16).any(|_| get_random_u32() != first));
+	}
+
+	#[ktest(random)]
+	fn below_bound() {
+		for bound in 1..64 {
+			assert!(get_random_below(bound) < bound);
+		}
+	}
+}
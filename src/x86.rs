@@ -1,3 +1,5 @@
+pub mod fpu;
+
 use core::{
 	arch::asm,
 	fmt::{self, Display},
@@ -513,4 +515,6 @@ pub unsafe fn init() {
 	gdt.load_kernel_code();
 	gdt.load_kernel_data();
 	gdt.load_tr();
+
+	fpu::init();
 }
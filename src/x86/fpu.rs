@@ -0,0 +1,100 @@
+//! x87 FPU / SSE state of user tasks.
+//!
+//! kernel itself never touches FPU registers (soft-float), so they always hold
+//! the state of the user task running on the cpu. the state is saved and restored
+//! eagerly on every task switch. (see `switch_task_finish`)
+
+use core::arch::asm;
+use core::sync::atomic::{AtomicU32, Ordering};
+
+const CR0_MP: usize = 1 << 1;
+const CR0_EM: usize = 1 << 2;
+const CR0_TS: usize = 1 << 3;
+const CR0_NE: usize = 1 << 5;
+
+const CR4_OSFXSR: usize = 1 << 9;
+const CR4_OSXMMEXCPT: usize = 1 << 10;
+
+const FCW_DEFAULT: u16 = 0x037f;
+const MXCSR_DEFAULT: u32 = 0x1f80;
+
+/// offsets in `fxsave` image.
+const MXCSR_OFFSET: usize = 24;
+const MXCSR_MASK_OFFSET: usize = 28;
+
+/// MXCSR bits supported by cpu. writing others with `fxrstor` raises #GP.
+static MXCSR_MASK: AtomicU32 = AtomicU32::new(0xffbf);
+
+/// memory image of `fxsave`.
+#[repr(C, align(16))]
+#[derive(Clone, Copy, Debug)]
+pub struct FpuState([u8; 512]);
+
+impl FpuState {
+	/// state right after `fninit`, with all SIMD exceptions masked.
+	pub const fn new() -> Self {
+		let mut image = [0; 512];
+		let fcw = FCW_DEFAULT.to_le_bytes();
+		let mxcsr = MXCSR_DEFAULT.to_le_bytes();
+
+		image[0] = fcw[0];
+		image[1] = fcw[1];
+		image[MXCSR_OFFSET] = mxcsr[0];
+		image[MXCSR_OFFSET + 1] = mxcsr[1];
+
+		Self(image)
+	}
+
+	/// state in FPU registers of current cpu.
+	pub fn capture() -> Self {
+		let mut state = Self::new();
+		state.save();
+		state
+	}
+
+	pub fn save(&mut self) {
+		unsafe { asm!("fxsave [{}]", in(reg) self.0.as_mut_ptr()) };
+	}
+
+	pub fn restore(&self) {
+		unsafe { asm!("fxrstor [{}]", in(reg) self.0.as_ptr()) };
+	}
+
+	fn read_u32(&self, offset: usize) -> u32 {
+		let mut bytes = [0; 4];
+		bytes.copy_from_slice(&self.0[offset..offset + 4]);
+		u32::from_le_bytes(bytes)
+	}
+
+	/// clear MXCSR bits which are not supported by cpu,
+	/// so that state given by user can be restored safely.
+	pub fn sanitize(&mut self) {
+		let mxcsr = self.read_u32(MXCSR_OFFSET) & MXCSR_MASK.load(Ordering::Relaxed);
+
+		self.0[MXCSR_OFFSET..MXCSR_OFFSET + 4].copy_from_slice(&mxcsr.to_le_bytes());
+	}
+}
+
+/// enable FPU and SSE on current cpu.
+pub fn init() {
+	unsafe {
+		let mut cr0: usize;
+		let mut cr4: usize;
+
+		asm!("mov {}, cr0", out(reg) cr0);
+		cr0 = (cr0 & !(CR0_EM | CR0_TS)) | CR0_MP | CR0_NE;
+		asm!("mov cr0, {}", in(reg) cr0);
+
+		asm!("mov {}, cr4", out(reg) cr4);
+		cr4 |= CR4_OSFXSR | CR4_OSXMMEXCPT;
+		asm!("mov cr4, {}", in(reg) cr4);
+
+		asm!("fninit");
+	}
+
+	// zero means cpu doesn't report it. (default mask is used)
+	let mask = FpuState::capture().read_u32(MXCSR_MASK_OFFSET);
+	if mask != 0 {
+		MXCSR_MASK.store(mask, Ordering::Relaxed);
+	}
+}
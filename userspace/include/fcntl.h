@@ -20,6 +20,15 @@
 #define O_NONBLOCK (04000)
 #define O_SYNC (010000)
 
+#define F_DUPFD (0)
+#define F_GETFD (1)
+#define F_SETFD (2)
+#define F_GETFL (3)
+#define F_SETFL (4)
+#define F_DUPFD_CLOEXEC (1030)
+
+#define FD_CLOEXEC (1)
+
 #define AT_FDCWD (-100)
 #define AT_EMPTY_PATH (0x1000)
 #define AT_SYMLINK_NOFOLLOW (0x100)
@@ -48,5 +57,6 @@ static inline int open(const char *path, int flags, ...) {
 }
 
 DEFINE_SYSCALL(creat, 8, int, const char *, path, int, mode);
+DEFINE_SYSCALL(fcntl, 55, int, int, fildes, int, cmd, long, arg);
 
 #endif // _FCNTL_H
@@ -39,59 +39,94 @@
 #define SIG_DFL (void *)0
 #define SIG_IGN (void *)1
 
-#define sigmask(m) (1 << ((m)-1))
+#define sigmask(m) (1ULL << ((m)-1))
 
-#define SA_ONSTACK 0x0001   /* take signal on signal stack */
-#define SA_RESTART 0x0002   /* restart system on signal return */
-#define SA_RESETHAND 0x0004 /* reset to SIG_DFL when taking signal */
-#define SA_NOCLDSTOP 0x0008 /* do not generate SIGCHLD on child stop */
-#define SA_NODEFER 0x0010   /* don't mask the signal we're delivering */
-#define SA_NOCLDWAIT 0x0020 /* don't keep zombies around */
-#define SA_SIGINFO 0x0040   /* signal handler with SA_SIGINFO args */
+#define SIGRTMIN 32
+#define SIGRTMAX 64
+
+#define SA_NOCLDSTOP 0x00000001 /* do not generate SIGCHLD on child stop */
+#define SA_NOCLDWAIT 0x00000002 /* don't keep zombies around */
+#define SA_SIGINFO 0x00000004   /* signal handler with SA_SIGINFO args */
+#define SA_ONSTACK 0x08000000   /* take signal on signal stack */
+#define SA_RESTART 0x10000000   /* restart system on signal return */
+#define SA_NODEFER 0x40000000   /* don't mask the signal we're delivering */
+#define SA_RESETHAND 0x80000000 /* reset to SIG_DFL when taking signal */
+
+#define SS_ONSTACK 1
+#define SS_DISABLE 2
+#define MINSIGSTKSZ 2048
+#define SIGSTKSZ 8192
 
 typedef void (*sighandler_t)(int);
-typedef size_t sigset_t;
+typedef unsigned long long sigset_t;
 
 typedef struct siginfo {
-	size_t num;
-	size_t pid;
-	size_t uid;
-	size_t code;
+	int si_signo;
+	int si_errno;
+	int si_code;
+	union {
+		int si_pid;
+		void *si_addr; /* faulting address */
+	};
+	unsigned int si_uid;
+	size_t si_value;
+	int __pad[26];
 } siginfo_t;
 
-typedef struct ucontext {
-	size_t ebp;
+typedef struct {
+	void *ss_sp;
+	int ss_flags;
+	size_t ss_size;
+} stack_t;
+
+typedef struct {
+	size_t gs;
+	size_t fs;
+	size_t es;
+	size_t ds;
 	size_t edi;
 	size_t esi;
+	size_t ebp;
+	size_t esp;
+	size_t ebx;
 	size_t edx;
 	size_t ecx;
-	size_t ebx;
 	size_t eax;
-	size_t ds;
-	size_t es;
-	size_t fs;
-	size_t gs;
-	size_t handler;
-	size_t error_code;
+	size_t trapno;
+	size_t err;
 	size_t eip;
 	size_t cs;
 	size_t eflags;
-	size_t esp;
+	size_t esp_at_signal;
 	size_t ss;
-	sigset_t mask;
-	ssize_t syscall_ret;
+	void *fpstate;
+	size_t oldmask;
+	size_t cr2;
+} mcontext_t;
+
+/* registers in uc_mcontext are restored on return from the handler. */
+typedef struct ucontext {
+	size_t uc_flags;
+	struct ucontext *uc_link;
+	stack_t uc_stack;
+	mcontext_t uc_mcontext;
+	sigset_t uc_sigmask;
 } ucontext_t;
 
 struct sigaction {
-	void (*sa_handler)(int);
-	void (*sa_sigaction)(int, siginfo_t *, void *);
+	union {
+		void (*sa_handler)(int);
+		void (*sa_sigaction)(int, siginfo_t *, void *);
+	};
+	unsigned long sa_flags;
+	void (*sa_restorer)(void);
 	sigset_t sa_mask;
-	int sa_flags;
 };
 
 DEFINE_SYSCALL(signal, 48, sighandler_t, int, signum, sighandler_t, handler);
 DEFINE_SYSCALL(sigaction, 67, int, int, signum, const struct sigaction *, act, struct sigaction *,
 	       oldact);
 DEFINE_SYSCALL(kill, 37, int, pid_t, pid, int, sig);
+DEFINE_SYSCALL(sigaltstack, 186, int, const stack_t *, ss, stack_t *, old_ss);
 
 #endif // _SIGNAL_H
@@ -4,8 +4,10 @@
 #include "kfs/internal/prelude.h"
 #include "kfs/syscall.h"
 
+#define MS_NOSUID 2
+
 DEFINE_SYSCALL(mount, 21, int, const char *, dev_path, const char *, mount_point, const char *,
-	       fs_name);
+	       fs_name, unsigned long, flags);
 DEFINE_SYSCALL(umount, 22, int, const char *, path);
 
 #endif // _SYS_MOUNT_H
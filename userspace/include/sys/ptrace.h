@@ -0,0 +1,44 @@
+#ifndef _SYS_PTRACE_H
+#define _SYS_PTRACE_H
+
+#include "kfs/internal/prelude.h"
+#include "kfs/syscall.h"
+
+#define PTRACE_TRACEME 0
+#define PTRACE_PEEKTEXT 1
+#define PTRACE_PEEKDATA 2
+#define PTRACE_POKETEXT 4
+#define PTRACE_POKEDATA 5
+#define PTRACE_CONT 7
+#define PTRACE_KILL 8
+#define PTRACE_SINGLESTEP 9
+#define PTRACE_GETREGS 12
+#define PTRACE_SETREGS 13
+#define PTRACE_ATTACH 16
+#define PTRACE_DETACH 17
+#define PTRACE_SYSCALL 24
+
+struct user_regs_struct {
+	long ebx;
+	long ecx;
+	long edx;
+	long esi;
+	long edi;
+	long ebp;
+	long eax;
+	long xds;
+	long xes;
+	long xfs;
+	long xgs;
+	long orig_eax;
+	long eip;
+	long xcs;
+	long eflags;
+	long esp;
+	long xss;
+};
+
+/* PEEK requests store the word at `data`, as the raw syscall of linux does. */
+DEFINE_SYSCALL(ptrace, 26, long, int, request, pid_t, pid, void *, addr, void *, data);
+
+#endif // _SYS_PTRACE_H
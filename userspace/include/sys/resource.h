@@ -0,0 +1,35 @@
+#ifndef _SYS_RESOURCE_H
+#define _SYS_RESOURCE_H
+
+#include "kfs/internal/prelude.h"
+#include "kfs/syscall.h"
+
+#define RLIMIT_CPU 0
+#define RLIMIT_FSIZE 1
+#define RLIMIT_DATA 2
+#define RLIMIT_STACK 3
+#define RLIMIT_CORE 4
+#define RLIMIT_NPROC 6
+#define RLIMIT_NOFILE 7
+#define RLIMIT_AS 9
+
+#define RLIM_INFINITY (~0UL)
+
+typedef unsigned long rlim_t;
+
+struct rlimit {
+	rlim_t rlim_cur;
+	rlim_t rlim_max;
+};
+
+struct rlimit64 {
+	unsigned long long rlim_cur;
+	unsigned long long rlim_max;
+};
+
+DEFINE_SYSCALL(setrlimit, 75, int, int, resource, const struct rlimit *, rlim);
+DEFINE_SYSCALL(getrlimit, 191, int, int, resource, struct rlimit *, rlim);
+DEFINE_SYSCALL(prlimit64, 340, int, pid_t, pid, int, resource, const struct rlimit64 *, new_limit,
+	       struct rlimit64 *, old_limit);
+
+#endif // _SYS_RESOURCE_H
@@ -24,12 +24,33 @@ DEFINE_SYSCALL(setsid, 66, pid_t, void);
 DEFINE_SYSCALL(getsid, 147, pid_t, pid_t, pid);
 
 DEFINE_SYSCALL(pipe, 42, int, int *, pipe_pair);
+DEFINE_SYSCALL(pipe2, 331, int, int *, pipe_pair, int, flags);
+
+DEFINE_SYSCALL(dup, 41, int, int, fildes);
+DEFINE_SYSCALL(dup2, 63, int, int, fildes, int, fildes2);
+DEFINE_SYSCALL(dup3, 330, int, int, fildes, int, fildes2, int, flags);
+
+#define CLOSE_RANGE_UNSHARE (1U << 1)
+#define CLOSE_RANGE_CLOEXEC (1U << 2)
+
+DEFINE_SYSCALL(close_range, 436, int, unsigned int, first, unsigned int, last, unsigned int, flags);
 
 DEFINE_SYSCALL(getuid, 199, uid_t, void);
+DEFINE_SYSCALL(geteuid, 201, uid_t, void);
 DEFINE_SYSCALL(setuid, 213, int, uid_t, uid);
+DEFINE_SYSCALL(setreuid, 203, int, uid_t, ruid, uid_t, euid);
+DEFINE_SYSCALL(setresuid, 208, int, uid_t, ruid, uid_t, euid, uid_t, suid);
+DEFINE_SYSCALL(getresuid, 209, int, uid_t *, ruid, uid_t *, euid, uid_t *, suid);
 
 DEFINE_SYSCALL(getgid, 200, uid_t, void);
+DEFINE_SYSCALL(getegid, 202, gid_t, void);
 DEFINE_SYSCALL(setgid, 214, int, uid_t, gid);
+DEFINE_SYSCALL(setregid, 204, int, gid_t, rgid, gid_t, egid);
+DEFINE_SYSCALL(setresgid, 210, int, gid_t, rgid, gid_t, egid, gid_t, sgid);
+DEFINE_SYSCALL(getresgid, 211, int, gid_t *, rgid, gid_t *, egid, gid_t *, sgid);
+
+DEFINE_SYSCALL(getgroups, 205, int, int, size, gid_t *, list);
+DEFINE_SYSCALL(setgroups, 206, int, size_t, size, const gid_t *, list);
 
 DEFINE_SYSCALL(chdir, 12, int, const char *, path);
 DEFINE_SYSCALL(getcwd, 183, char *, char *, buf, size_t, size);
@@ -38,7 +59,7 @@ DEFINE_SYSCALL(rmdir, 40, int, const char *, path);
 DEFINE_SYSCALL(unlink, 10, int, const char *, path);
 DEFINE_SYSCALL(symlink, 83, int, const char *, target, const char *, linkpath);
 
-DEFINE_SYSCALL(reboot, 80, int, int, cmd);
+DEFINE_SYSCALL(reboot, 88, int, int, cmd);
 
 #define SEEK_SET 0
 #define SEEK_CUR 1
@@ -12,13 +12,13 @@
 
 int main(void) {
 	mkdir("/dev", 0777);
-	mount("dev", "/dev", "devfs");
+	mount("dev", "/dev", "devfs", MS_NOSUID);
 
 	mkdir("/proc", 0777);
-	mount("proc", "/proc", "procfs");
+	mount("proc", "/proc", "procfs", MS_NOSUID);
 
 	mkdir("/sys", 0777);
-	mount("sysfs", "/sys", "sysfs");
+	mount("sysfs", "/sys", "sysfs", MS_NOSUID);
 
 	open("/dev/tty1", O_RDWR);
 	open("/dev/tty1", O_RDWR);
@@ -432,6 +432,8 @@ void builtin_mount(int idx) {
 	char dev_path[1024];
 	char mount_point[1024];
 	char fs_name[1024];
+	char option[1024];
+	unsigned long flags = 0;
 
 	idx = extract(idx, dev_path);
 	idx = ignore_ws(idx);
@@ -442,7 +444,12 @@ void builtin_mount(int idx) {
 	idx = extract(idx, fs_name);
 	idx = ignore_ws(idx);
 
-	int ret = mount(dev_path, mount_point, fs_name);
+	idx = extract(idx, option);
+	if (ft_strncmp(option, "nosuid", 7) == 0) {
+		flags |= MS_NOSUID;
+	}
+
+	int ret = mount(dev_path, mount_point, fs_name, flags);
 	if (ret < 0) {
 		show_error("mount: mount", ret);
 	}
@@ -38,60 +38,58 @@ void sig_action(int num, siginfo_t *info, void *sig_ctx) {
 	ft_putstr("==== sig info ====\n");
 
 	ft_putstr("num: ");
-	ft_putnbr_x(info->num);
+	ft_putnbr_x(info->si_signo);
 
 	ft_putstr("  pid: "); // TODO '\t' not working.
-	ft_putnbr_x(info->pid);
+	ft_putnbr_x(info->si_pid);
 
 	ft_putstr("\nuid: ");
-	ft_putnbr_x(info->uid);
+	ft_putnbr_x(info->si_uid);
 
 	ft_putstr("  code: ");
-	ft_putnbr_x(info->code);
+	ft_putnbr_x(info->si_code);
 
 	ucontext_t *u = sig_ctx;
 	ft_putstr("\n==== sig context ====");
 
 	ft_putstr("\nebp: ");
-	ft_putnbr_x(u->ebp);
+	ft_putnbr_x(u->uc_mcontext.ebp);
 	ft_putstr("  edi: ");
-	ft_putnbr_x(u->edi);
+	ft_putnbr_x(u->uc_mcontext.edi);
 	ft_putstr("\nesi: ");
-	ft_putnbr_x(u->esi);
+	ft_putnbr_x(u->uc_mcontext.esi);
 	ft_putstr("  edx: ");
-	ft_putnbr_x(u->edx);
+	ft_putnbr_x(u->uc_mcontext.edx);
 	ft_putstr("\necx: ");
-	ft_putnbr_x(u->ecx);
+	ft_putnbr_x(u->uc_mcontext.ecx);
 	ft_putstr("  ebx: ");
-	ft_putnbr_x(u->ebx);
+	ft_putnbr_x(u->uc_mcontext.ebx);
 	ft_putstr("\neax: ");
-	ft_putnbr_x(u->eax);
+	ft_putnbr_x(u->uc_mcontext.eax);
 	ft_putstr("  ds: ");
-	ft_putnbr_x(u->ds);
+	ft_putnbr_x(u->uc_mcontext.ds);
 	ft_putstr("\nes: ");
-	ft_putnbr_x(u->es);
+	ft_putnbr_x(u->uc_mcontext.es);
 	ft_putstr("  fs: ");
-	ft_putnbr_x(u->fs);
+	ft_putnbr_x(u->uc_mcontext.fs);
 	ft_putstr("\ngs: ");
-	ft_putnbr_x(u->gs);
-	ft_putstr("  handler: ");
-	ft_putnbr_x(u->handler);
-	ft_putstr("\nerror_code: ");
-	ft_putnbr_x(u->error_code);
+	ft_putnbr_x(u->uc_mcontext.gs);
+	ft_putstr("  trapno: ");
+	ft_putnbr_x(u->uc_mcontext.trapno);
+	ft_putstr("\nerr: ");
+	ft_putnbr_x(u->uc_mcontext.err);
 	ft_putstr("  eip: ");
-	ft_putnbr_x(u->eip);
+	ft_putnbr_x(u->uc_mcontext.eip);
 	ft_putstr("\ncs: ");
-	ft_putnbr_x(u->cs);
+	ft_putnbr_x(u->uc_mcontext.cs);
 	ft_putstr("  eflags: ");
-	ft_putnbr_x(u->eflags);
+	ft_putnbr_x(u->uc_mcontext.eflags);
 	ft_putstr("\nesp: ");
-	ft_putnbr_x(u->esp);
+	ft_putnbr_x(u->uc_mcontext.esp);
 	ft_putstr("  ss: ");
-	ft_putnbr_x(u->ss);
+	ft_putnbr_x(u->uc_mcontext.ss);
 	ft_putstr("\nmask: ");
-	ft_putnbr_x(u->mask);
-	ft_putstr("  syscall_ret: ");
-	ft_putnbr(u->syscall_ret);
+	ft_putnbr_x(u->uc_sigmask);
 
 	write(0, "\nsig action done\n", 17);
 }